@@ -0,0 +1,119 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use rink_core::bigrat::BigRat;
+use rink_core::numeric::Numeric;
+use rink_core::*;
+
+/// Unit conversions like the ones batch jobs run.
+const CONVERSIONS: &[&str] = &[
+    "3 feet -> meters",
+    "kWh / year -> W",
+    "5 mi/hr -> km/hr",
+    "1 acre -> m^2",
+    "12 oz -> g",
+    "55 gallons / minute -> liters / second",
+    "60 mph -> m/s",
+    "100 kg * 9.8 m/s^2 -> N",
+    "1 lightyear -> parsec",
+    "3 cups + 2 tablespoons -> mL",
+    "1 furlong / fortnight -> mm/s",
+    "72 inch -> ft, inch",
+];
+
+/// Exact arithmetic on small numbers, without any units.
+const ARITHMETIC: &[&str] = &[
+    "1 + 2 * 3 - 4 / 5",
+    "(12 + 34) * (56 - 78) / 9",
+    "2^10 - 3^5 + 4^3",
+    "1/2 + 1/3 + 1/4 + 1/5 + 1/6 + 1/7",
+    "123456789 * 987654321",
+    "100! / 98!",
+    "17 mod 5 + 17 // 5",
+    "gcd(462, 1071) + lcm(4, 6)",
+];
+
+/// Fractions that fit in machine integers, like most query inputs.
+const FRACTIONS: &[(i64, i64)] = &[
+    (1, 2),
+    (2, 3),
+    (-5, 7),
+    (22, 7),
+    (-1, 9),
+    (123456789, 1000),
+    (1000003, 999983),
+    (-987654321, 123),
+];
+
+fn eval_all(ctx: &Context, queries: &[ast::Query]) {
+    for query in queries {
+        let _ = black_box(ctx.eval_outer(query));
+    }
+}
+
+fn parse_all(queries: &[&str]) -> Vec<ast::Query> {
+    queries
+        .iter()
+        .map(|input| {
+            let mut iter = text_query::TokenIterator::new(input).peekable();
+            text_query::parse_query(&mut iter)
+        })
+        .collect()
+}
+
+fn bench_queries(c: &mut Criterion) {
+    let mut ctx = simple_context().unwrap();
+    ctx.use_humanize = false;
+
+    let conversions = parse_all(CONVERSIONS);
+    c.bench_function("conversions", |b| b.iter(|| eval_all(&ctx, &conversions)));
+
+    let arithmetic = parse_all(ARITHMETIC);
+    c.bench_function("arithmetic", |b| b.iter(|| eval_all(&ctx, &arithmetic)));
+
+    c.bench_function("parse and eval", |b| {
+        b.iter(|| eval_all(&ctx, &parse_all(CONVERSIONS)))
+    });
+}
+
+/// Compares arithmetic on `Numeric`, which keeps these fractions in
+/// machine integers, against the `BigRat`s that it falls back to.
+fn bench_small_rationals(c: &mut Criterion) {
+    let big = FRACTIONS
+        .iter()
+        .map(|&(numer, denom)| BigRat::small_ratio(numer, denom))
+        .collect::<Vec<_>>();
+    let small = big.iter().cloned().map(Numeric::from).collect::<Vec<_>>();
+
+    let mut group = c.benchmark_group("small rationals");
+    group.bench_function("Numeric", |b| {
+        b.iter(|| {
+            for x in &small {
+                for y in &small {
+                    black_box(&(&(x + y) * &(x - y)) / &(x * y));
+                }
+            }
+        })
+    });
+    group.bench_function("BigRat", |b| {
+        b.iter(|| {
+            for x in &big {
+                for y in &big {
+                    black_box(&(&(x + y) * &(x - y)) / &(x * y));
+                }
+            }
+        })
+    });
+    group.finish();
+}
+
+fn bench_load(c: &mut Criterion) {
+    c.bench_function("load definitions", |b| {
+        b.iter(|| black_box(simple_context().unwrap()))
+    });
+}
+
+criterion_group!(benches, bench_queries, bench_small_rationals, bench_load);
+criterion_main!(benches);
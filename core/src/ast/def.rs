@@ -58,6 +58,74 @@ pub struct Property {
     pub doc: Option<String>,
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionParam {
+    pub name: String,
+    /// The quantity (such as `velocity`) that arguments must conform
+    /// to, if any.
+    pub quantity: Option<String>,
+}
+
+/// A user-defined function such as `reynolds(rho, v: velocity, L, mu)
+/// = rho v L / mu`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionDef {
+    pub params: Vec<FunctionParam>,
+    pub body: ExprString,
+}
+
+/// The dimensions of a nonlinear unit's readings and of the quantity
+/// they stand for, from `units=[1;K]`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaleUnits {
+    pub input: ExprString,
+    pub output: ExprString,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaleBound {
+    pub value: ExprString,
+    pub inclusive: bool,
+}
+
+/// An interval like `[-273.15,)`, where a missing end is unbounded.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaleRange {
+    pub lower: Option<ScaleBound>,
+    pub upper: Option<ScaleBound>,
+}
+
+/// A nonlinear unit in the style of GNU units, such as
+/// `tempF(x) units=[1;K] (x+(-32)) degF + stdtemp ; (tempF+(-stdtemp))/degF + 32`.
+/// The inverse refers to the quantity by the unit's own name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScaleDef {
+    pub param: String,
+    pub forward: ExprString,
+    pub inverse: ExprString,
+    /// Without this, readings and quantities are dimensionless.
+    pub units: Option<ScaleUnits>,
+    /// Allowed readings, in the base units of `units.input`.
+    pub domain: Option<ScaleRange>,
+    /// Allowed quantities, in the base units of `units.output`.
+    pub range: Option<ScaleRange>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TablePoint {
+    pub input: ExprString,
+    pub output: ExprString,
+}
+
+/// A nonlinear unit interpolated between the points of a table, as in
+/// GNU units' `wiregauge[in] 0 0.3249 1 0.2893 ...`. Outputs are
+/// multiplied by `unit`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TableDef {
+    pub unit: ExprString,
+    pub points: Vec<TablePoint>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[serde(tag = "type")]
@@ -86,6 +154,9 @@ pub enum Def {
     Category {
         display_name: String,
     },
+    Function(FunctionDef),
+    Scale(Box<ScaleDef>),
+    Table(TableDef),
     Error {
         message: String,
     },
@@ -147,6 +218,54 @@ impl fmt::Display for DatePattern {
     }
 }
 
+impl fmt::Display for FunctionParam {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.quantity {
+            Some(ref quantity) => write!(fmt, "{}: {}", self.name, quantity),
+            None => write!(fmt, "{}", self.name),
+        }
+    }
+}
+
+impl FunctionDef {
+    /// Formats the function's signature, e.g. `f(x, y: length)`.
+    pub fn signature(&self, name: &str) -> String {
+        let params = self
+            .params
+            .iter()
+            .map(ToString::to_string)
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("{}({})", name, params)
+    }
+}
+
+impl fmt::Display for ScaleRange {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.lower {
+            Some(ref bound) => write!(
+                fmt,
+                "{}{}",
+                if bound.inclusive { "[" } else { "(" },
+                *bound.value
+            )?,
+            None => write!(fmt, "(")?,
+        }
+        write!(fmt, ",")?;
+        match self.upper {
+            Some(ref bound) => {
+                write!(
+                    fmt,
+                    "{}{}",
+                    *bound.value,
+                    if bound.inclusive { "]" } else { ")" }
+                )
+            }
+            None => write!(fmt, ")"),
+        }
+    }
+}
+
 impl DatePattern {
     pub fn show(pat: &[DatePattern]) -> String {
         use std::io::Write;
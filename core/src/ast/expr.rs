@@ -13,6 +13,8 @@ pub enum Expr {
     Mul { exprs: Vec<Expr> },
     Of { property: String, expr: Box<Expr> },
     Call { func: Function, args: Vec<Expr> },
+    Interval { lower: Box<Expr>, upper: Box<Expr> },
+    List { items: Vec<Expr> },
     Error { message: String },
 }
 
@@ -63,6 +65,16 @@ impl Expr {
         Expr::new_bin(BinOpType::Equals, numer, denom)
     }
 
+    pub fn new_plus_minus(value: Expr, error: Expr) -> Expr {
+        Expr::new_bin(BinOpType::PlusMinus, value, error)
+    }
+
+    pub fn new_interval(lower: Expr, upper: Expr) -> Expr {
+        let lower = Box::new(lower);
+        let upper = Box::new(upper);
+        Expr::Interval { lower, upper }
+    }
+
     pub fn new_of(property: &str, expr: Expr) -> Expr {
         let property = property.to_owned();
         let expr = Box::new(expr);
@@ -74,11 +86,6 @@ impl Expr {
         Expr::UnaryOp(UnaryOpExpr { op, expr })
     }
 
-    pub fn new_suffix(suffix: Degree, expr: Expr) -> Expr {
-        let op = UnaryOpType::Degree(suffix);
-        Expr::new_unary(op, expr)
-    }
-
     pub fn new_plus(expr: Expr) -> Expr {
         Expr::new_unary(UnaryOpType::Positive, expr)
     }
@@ -86,6 +93,10 @@ impl Expr {
     pub fn new_negate(expr: Expr) -> Expr {
         Expr::new_unary(UnaryOpType::Negative, expr)
     }
+
+    pub fn new_factorial(expr: Expr) -> Expr {
+        Expr::new_unary(UnaryOpType::Factorial, expr)
+    }
 }
 
 #[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Copy)]
@@ -95,7 +106,12 @@ pub enum Precedence {
     Pow,
     Mul,
     Div,
+    PlusMinus,
     Add,
+    Compare,
+    Not,
+    And,
+    Or,
     Equals,
 }
 
@@ -107,6 +123,19 @@ impl Precedence {
             BinOpType::Pow => Precedence::Pow,
             BinOpType::Frac => Precedence::Div,
             BinOpType::Equals => Precedence::Equals,
+            BinOpType::PlusMinus => Precedence::PlusMinus,
+            BinOpType::Mod => Precedence::Div,
+            BinOpType::FloorDiv => Precedence::Div,
+            BinOpType::Choose => Precedence::Div,
+            BinOpType::Permute => Precedence::Div,
+            BinOpType::Less
+            | BinOpType::LessEqual
+            | BinOpType::Greater
+            | BinOpType::GreaterEqual
+            | BinOpType::Equal
+            | BinOpType::NotEqual => Precedence::Compare,
+            BinOpType::And => Precedence::And,
+            BinOpType::Or => Precedence::Or,
         }
     }
 
@@ -117,6 +146,19 @@ impl Precedence {
             BinOpType::Pow => Precedence::Term,
             BinOpType::Frac => Precedence::Mul,
             BinOpType::Equals => Precedence::Add,
+            BinOpType::PlusMinus => Precedence::PlusMinus,
+            BinOpType::Mod => Precedence::Mul,
+            BinOpType::FloorDiv => Precedence::Mul,
+            BinOpType::Choose => Precedence::Mul,
+            BinOpType::Permute => Precedence::Mul,
+            BinOpType::Less
+            | BinOpType::LessEqual
+            | BinOpType::Greater
+            | BinOpType::GreaterEqual
+            | BinOpType::Equal
+            | BinOpType::NotEqual => Precedence::Add,
+            BinOpType::And => Precedence::Not,
+            BinOpType::Or => Precedence::And,
         }
     }
 }
@@ -155,13 +197,24 @@ impl fmt::Display for Expr {
                         write!(fmt, "-")?;
                         recurse(&unaryop.expr, fmt, Precedence::Plus)
                     }
-                    UnaryOpType::Degree(ref suffix) => {
-                        if prec < Precedence::Mul {
+                    UnaryOpType::Factorial => {
+                        if matches!(*unaryop.expr, Expr::UnaryOp(ref inner) if inner.op != UnaryOpType::Factorial)
+                        {
+                            write!(fmt, "(")?;
+                            recurse(&unaryop.expr, fmt, Precedence::Equals)?;
+                            write!(fmt, ")")?;
+                        } else {
+                            recurse(&unaryop.expr, fmt, Precedence::Term)?;
+                        }
+                        write!(fmt, "!")
+                    }
+                    UnaryOpType::Not => {
+                        if prec < Precedence::Not {
                             write!(fmt, "(")?;
                         }
-                        recurse(&unaryop.expr, fmt, Precedence::Mul)?;
-                        write!(fmt, " {}", suffix)?;
-                        if prec < Precedence::Mul {
+                        write!(fmt, "not ")?;
+                        recurse(&unaryop.expr, fmt, Precedence::Not)?;
+                        if prec < Precedence::Not {
                             write!(fmt, ")")?;
                         }
                         Ok(())
@@ -208,6 +261,31 @@ impl fmt::Display for Expr {
                     }
                     Ok(())
                 }
+                Expr::Interval {
+                    ref lower,
+                    ref upper,
+                } => {
+                    write!(fmt, "[")?;
+                    recurse(lower, fmt, Precedence::Equals)?;
+                    write!(fmt, ", ")?;
+                    recurse(upper, fmt, Precedence::Equals)?;
+                    write!(fmt, "]")
+                }
+                Expr::List { ref items } => {
+                    write!(fmt, "[")?;
+                    for (i, item) in items.iter().enumerate() {
+                        if i != 0 {
+                            write!(fmt, ", ")?;
+                        }
+                        recurse(item, fmt, Precedence::Equals)?;
+                    }
+                    // Without the trailing comma, two elements would be
+                    // read back as an interval.
+                    if items.len() <= 2 {
+                        write!(fmt, ",")?;
+                    }
+                    write!(fmt, "]")
+                }
                 Expr::Error { ref message } => write!(fmt, "<error: {}>", message),
             }
         }
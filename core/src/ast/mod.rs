@@ -12,21 +12,13 @@ mod query;
 #[cfg(test)]
 mod test;
 
-pub use def::{DatePattern, Def, DefEntry, Defs, ExprString, Property};
+pub use def::{
+    DatePattern, Def, DefEntry, Defs, ExprString, FunctionDef, FunctionParam, Property, ScaleBound,
+    ScaleDef, ScaleRange, ScaleUnits, TableDef, TablePoint,
+};
 pub use expr::{Expr, Precedence};
 pub use query::{Conversion, Query};
 
-#[derive(Debug, Clone, Serialize, Copy, Eq, PartialEq)]
-#[serde(rename_all = "camelCase")]
-pub enum Degree {
-    Celsius,
-    Fahrenheit,
-    Reaumur,
-    Romer,
-    Delisle,
-    Newton,
-}
-
 #[derive(Debug, Clone, Serialize)]
 pub enum DateToken {
     Literal(String),
@@ -46,6 +38,19 @@ pub enum BinOpType {
     Frac,
     Pow,
     Equals,
+    PlusMinus,
+    Mod,
+    FloorDiv,
+    Choose,
+    Permute,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    Equal,
+    NotEqual,
+    And,
+    Or,
 }
 
 impl BinOpType {
@@ -56,6 +61,19 @@ impl BinOpType {
             BinOpType::Frac => " / ",
             BinOpType::Pow => "^",
             BinOpType::Equals => " = ",
+            BinOpType::PlusMinus => " ± ",
+            BinOpType::Mod => " mod ",
+            BinOpType::FloorDiv => " // ",
+            BinOpType::Choose => " nCr ",
+            BinOpType::Permute => " nPr ",
+            BinOpType::Less => " < ",
+            BinOpType::LessEqual => " <= ",
+            BinOpType::Greater => " > ",
+            BinOpType::GreaterEqual => " >= ",
+            BinOpType::Equal => " == ",
+            BinOpType::NotEqual => " != ",
+            BinOpType::And => " and ",
+            BinOpType::Or => " or ",
         }
     }
 }
@@ -69,20 +87,11 @@ pub struct BinOpExpr {
 
 #[derive(Debug, Clone, Serialize, Copy, Eq, PartialEq)]
 #[serde(rename_all = "camelCase")]
-#[serde(untagged)]
 pub enum UnaryOpType {
     Negative,
     Positive,
-    Degree(Degree),
-}
-
-impl UnaryOpType {
-    pub fn is_prefix(self) -> bool {
-        match self {
-            UnaryOpType::Degree(_) => false,
-            _ => true,
-        }
-    }
+    Factorial,
+    Not,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -114,6 +123,28 @@ pub enum Function {
     Log,
     Hypot,
     Atan2,
+    Re,
+    Im,
+    Abs,
+    Arg,
+    Conj,
+    Sum,
+    Mean,
+    Min,
+    Max,
+    Norm,
+    Dot,
+    Cross,
+    Round,
+    Floor,
+    Ceil,
+    Trunc,
+    Gcd,
+    Lcm,
+    Mod,
+    If,
+    /// A function defined in a session or in a units file.
+    Custom(String),
 }
 
 impl Function {
@@ -139,6 +170,27 @@ impl Function {
             Function::Log => "log",
             Function::Hypot => "hypot",
             Function::Atan2 => "atan2",
+            Function::Re => "re",
+            Function::Im => "im",
+            Function::Abs => "abs",
+            Function::Arg => "arg",
+            Function::Conj => "conj",
+            Function::Sum => "sum",
+            Function::Mean => "mean",
+            Function::Min => "min",
+            Function::Max => "max",
+            Function::Norm => "norm",
+            Function::Dot => "dot",
+            Function::Cross => "cross",
+            Function::Round => "round",
+            Function::Floor => "floor",
+            Function::Ceil => "ceil",
+            Function::Trunc => "trunc",
+            Function::Gcd => "gcd",
+            Function::Lcm => "lcm",
+            Function::Mod => "mod",
+            Function::If => "if",
+            Function::Custom(ref name) => name,
         }
     }
 
@@ -164,38 +216,32 @@ impl Function {
             "log" => Function::Log,
             "hypot" => Function::Hypot,
             "atan2" => Function::Atan2,
+            "re" => Function::Re,
+            "im" => Function::Im,
+            "abs" => Function::Abs,
+            "arg" => Function::Arg,
+            "conj" => Function::Conj,
+            "sum" => Function::Sum,
+            "mean" => Function::Mean,
+            "min" => Function::Min,
+            "max" => Function::Max,
+            "norm" => Function::Norm,
+            "dot" => Function::Dot,
+            "cross" => Function::Cross,
+            "round" => Function::Round,
+            "floor" => Function::Floor,
+            "ceil" => Function::Ceil,
+            "trunc" => Function::Trunc,
+            "gcd" => Function::Gcd,
+            "lcm" => Function::Lcm,
+            "mod" => Function::Mod,
+            "if" => Function::If,
             _ => return None,
         };
         Some(func)
     }
 }
 
-impl fmt::Display for Degree {
-    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        match *self {
-            Degree::Celsius => write!(fmt, "°C"),
-            Degree::Fahrenheit => write!(fmt, "°F"),
-            Degree::Newton => write!(fmt, "°N"),
-            Degree::Reaumur => write!(fmt, "°Ré"),
-            Degree::Romer => write!(fmt, "°Rø"),
-            Degree::Delisle => write!(fmt, "°De"),
-        }
-    }
-}
-
-impl Degree {
-    pub fn name_base_scale(&self) -> (&str, &str, &str) {
-        match *self {
-            Degree::Celsius => ("C", "zerocelsius", "kelvin"),
-            Degree::Fahrenheit => ("F", "zerofahrenheit", "degrankine"),
-            Degree::Reaumur => ("Ré", "zerocelsius", "reaumur_absolute"),
-            Degree::Romer => ("Rø", "zeroromer", "romer_absolute"),
-            Degree::Delisle => ("De", "zerodelisle", "delisle_absolute"),
-            Degree::Newton => ("N", "zerocelsius", "newton_absolute"),
-        }
-    }
-}
-
 impl fmt::Display for DateToken {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
         match *self {
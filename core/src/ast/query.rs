@@ -4,11 +4,42 @@ use super::*;
 pub enum Conversion {
     None,
     Expr(Expr),
-    Degree(Degree),
     List(Vec<String>),
     Offset(i64),
     #[serde(skip)]
     Timezone(Tz),
+    /// Complex numbers as a magnitude and an angle.
+    Polar,
+    /// Complex numbers as a real and an imaginary part.
+    Rectangular,
+    /// Engineering notation, where the exponent is a multiple of 3, in
+    /// the given units or the ones the value is normally shown in.
+    Eng(Option<Expr>),
+    /// The prefix that keeps the number between 1 and 1000, or 1024
+    /// for binary prefixes, applied to the given unit or the one the
+    /// value is normally shown in.
+    Auto {
+        binary: bool,
+        unit: Option<String>,
+    },
+    /// The closest fraction with a denominator of at most the given
+    /// one, like `355/113` for pi.
+    Fraction(u64),
+    /// The terms of the continued fraction of the exact value, like
+    /// `[3; 7, 16]`.
+    ContinuedFraction,
+    /// Dates in ISO 8601 format, like `2023-11-14T22:13:20+00:00`.
+    Iso8601,
+    /// Dates in RFC 2822 format, as used in email headers.
+    Rfc2822,
+    /// Dates as whole seconds since the Unix epoch.
+    Unix,
+    /// Dates as whole milliseconds since the Unix epoch.
+    UnixMs,
+    /// Dates in a format string using strftime's `%` specifiers.
+    Strftime(String),
+    /// Durations in ISO 8601 format, like `P2DT3H`.
+    Iso8601Duration,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -20,6 +51,8 @@ pub enum Query {
     Factorize(Expr),
     UnitsFor(Expr),
     Search(String),
+    Function(String, FunctionDef),
+    Unset(String),
     Error(String),
 }
 
@@ -28,7 +61,6 @@ impl fmt::Display for Conversion {
         match *self {
             Conversion::None => write!(fmt, "nothing"),
             Conversion::Expr(ref expr) => write!(fmt, "{}", expr),
-            Conversion::Degree(ref deg) => write!(fmt, "{}", deg),
             Conversion::List(ref list) => {
                 let list = list
                     .iter()
@@ -39,6 +71,28 @@ impl fmt::Display for Conversion {
             }
             Conversion::Offset(off) => write!(fmt, "{:02}:{:02}", off / 3600, (off / 60) % 60),
             Conversion::Timezone(ref tz) => write!(fmt, "{:?}", tz),
+            Conversion::Polar => write!(fmt, "polar"),
+            Conversion::Rectangular => write!(fmt, "rectangular"),
+            Conversion::Eng(None) => write!(fmt, "eng"),
+            Conversion::Eng(Some(ref expr)) => write!(fmt, "eng {}", expr),
+            Conversion::Auto { binary, ref unit } => {
+                write!(fmt, "auto")?;
+                if binary {
+                    write!(fmt, " binary")?;
+                }
+                if let Some(ref unit) = *unit {
+                    write!(fmt, " {}", unit)?;
+                }
+                Ok(())
+            }
+            Conversion::Fraction(max) => write!(fmt, "fraction max {}", max),
+            Conversion::ContinuedFraction => write!(fmt, "continued fraction"),
+            Conversion::Iso8601 => write!(fmt, "iso8601"),
+            Conversion::Rfc2822 => write!(fmt, "rfc2822"),
+            Conversion::Unix => write!(fmt, "unix"),
+            Conversion::UnixMs => write!(fmt, "unixms"),
+            Conversion::Strftime(ref format) => write!(fmt, "strftime {:?}", format),
+            Conversion::Iso8601Duration => write!(fmt, "iso8601 duration"),
         }
     }
 }
@@ -0,0 +1,683 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use num::bigint::BigInt as NumInt;
+use num::rational::BigRational as NumRat;
+use num::traits::{One, Signed, ToPrimitive, Zero};
+use std::cmp::{max, min, Ordering};
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::bigrat::BigRat;
+
+/// Significant digits that inexact results are computed to, unless
+/// more are asked for.
+pub const DEFAULT_DIGITS: u64 = 40;
+
+/// Bits carried past the requested precision while evaluating series,
+/// so that rounding error doesn't reach the result.
+const GUARD_BITS: u64 = 32;
+
+/// Arguments to `exp` and friends are limited to below 2^16, beyond
+/// which results would take far too long to print.
+const MAX_ARGUMENT_BITS: i64 = 16;
+
+/// A binary floating point number with a chosen number of significant
+/// bits, used to compute transcendental functions to more digits than
+/// `f64` allows. The value is `mantissa * 2^exponent`.
+#[derive(Clone, Debug)]
+pub struct BigFloat {
+    mantissa: NumInt,
+    exponent: i64,
+    precision: u64,
+}
+
+/// Shifts right, rounding to nearest with ties away from zero.
+fn shr_round(value: &NumInt, shift: u64) -> NumInt {
+    if shift == 0 {
+        return value.clone();
+    }
+    let half = NumInt::one() << (shift - 1);
+    if value.is_negative() {
+        -((-value + half) >> shift)
+    } else {
+        (value + half) >> shift
+    }
+}
+
+/// Sums `x - x^3/3 + x^5/5 - ...` when alternating, which is `atan`,
+/// or `x + x^3/3 + x^5/5 + ...` otherwise, which is `atanh`. Only
+/// converges quickly for small `x`.
+fn odd_series(x: &BigFloat, alternating: bool, precision: u64) -> BigFloat {
+    let square = x * x;
+    let square = if alternating { -&square } else { square };
+    let mut power = x.clone();
+    let mut sum = x.clone();
+    let mut n = 1;
+    loop {
+        power = &power * &square;
+        n += 2;
+        let term = &power / &BigFloat::from_int(n, precision);
+        if term.is_zero() || term.top() < sum.top() - precision as i64 - 2 {
+            return sum;
+        }
+        sum = &sum + &term;
+    }
+}
+
+impl BigFloat {
+    fn new(mantissa: NumInt, exponent: i64, precision: u64) -> BigFloat {
+        let excess = mantissa.bits().saturating_sub(precision);
+        let mantissa = shr_round(&mantissa, excess);
+        let exponent = exponent + excess as i64;
+        // Trailing zeros are removed so that equal values compare
+        // equal, and rounding up to a power of two stays in precision.
+        match mantissa.trailing_zeros() {
+            Some(zeros) => BigFloat {
+                mantissa: mantissa >> zeros,
+                exponent: exponent + zeros as i64,
+                precision,
+            },
+            None => BigFloat {
+                mantissa,
+                exponent: 0,
+                precision,
+            },
+        }
+    }
+
+    /// The number of bits needed for the given number of significant
+    /// decimal digits.
+    pub fn bits_for_digits(digits: u64) -> u64 {
+        // log2(10) is about 3.3219.
+        (digits * 33_219).div_ceil(10_000)
+    }
+
+    pub fn from_int(value: i64, precision: u64) -> BigFloat {
+        BigFloat::new(NumInt::from(value), 0, precision)
+    }
+
+    /// Rounds a fraction to the nearest number with the given number of
+    /// bits.
+    pub fn from_rat(value: &BigRat, precision: u64) -> BigFloat {
+        let numer = value.numer().into_inner();
+        let denom = value.denom().into_inner();
+        // Keep a couple of bits more than needed so that the division
+        // truncating doesn't matter.
+        let shift = precision as i64 + 2 + denom.bits() as i64 - numer.bits() as i64;
+        let quotient = if shift >= 0 {
+            (numer << shift as u64) / denom
+        } else {
+            numer / (denom << (-shift) as u64)
+        };
+        BigFloat::new(quotient, -shift, precision)
+    }
+
+    /// The exact value as a fraction.
+    pub fn to_rat(&self) -> BigRat {
+        let inner = if self.exponent >= 0 {
+            NumRat::from_integer(&self.mantissa << self.exponent as u64)
+        } else {
+            NumRat::new(
+                self.mantissa.clone(),
+                NumInt::one() << (-self.exponent) as u64,
+            )
+        };
+        BigRat::from(inner)
+    }
+
+    /// The value rounded to the number of decimal digits that the
+    /// precision covers, so that a result like `16 - 2^-130` is shown
+    /// as 16.
+    pub fn to_decimal(&self) -> BigRat {
+        if self.is_zero() {
+            return BigRat::zero();
+        }
+        let digits = (self.precision * 3_010 / 10_000).saturating_sub(1) as i64;
+        let lead = (self.top() as f64 * std::f64::consts::LOG10_2).floor() as i64;
+        let scale =
+            NumRat::from_integer(NumInt::from(10).pow((digits - lead).unsigned_abs() as u32));
+        let value = self.to_rat().into_inner();
+        let rounded = if digits >= lead {
+            (value * &scale).round() / scale
+        } else {
+            (value / &scale).round() * scale
+        };
+        BigRat::from(rounded)
+    }
+
+    pub fn to_f64(&self) -> f64 {
+        self.to_rat().as_float()
+    }
+
+    pub fn precision(&self) -> u64 {
+        self.precision
+    }
+
+    pub fn with_precision(&self, precision: u64) -> BigFloat {
+        BigFloat::new(self.mantissa.clone(), self.exponent, precision)
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.mantissa.is_zero()
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.mantissa.is_negative()
+    }
+
+    pub fn abs(&self) -> BigFloat {
+        BigFloat {
+            mantissa: self.mantissa.abs(),
+            ..self.clone()
+        }
+    }
+
+    /// One past the position of the highest set bit, so that
+    /// `2^(top - 1) <= |self| < 2^top`.
+    fn top(&self) -> i64 {
+        self.exponent + self.mantissa.bits() as i64
+    }
+
+    /// Multiplies by `2^shift`, which is exact.
+    fn mul_pow2(&self, shift: i64) -> BigFloat {
+        BigFloat {
+            exponent: self.exponent + shift,
+            ..self.clone()
+        }
+    }
+
+    /// Rounds to the nearest integer, with ties away from zero.
+    fn round_int(&self) -> NumInt {
+        if self.exponent >= 0 {
+            &self.mantissa << self.exponent as u64
+        } else {
+            shr_round(&self.mantissa, (-self.exponent) as u64)
+        }
+    }
+
+    fn mul_int(&self, value: &NumInt) -> BigFloat {
+        BigFloat::new(&self.mantissa * value, self.exponent, self.precision)
+    }
+
+    /// Computes `ln(2)` as `2 atanh(1/3)`.
+    fn ln2(precision: u64) -> BigFloat {
+        let third = &BigFloat::from_int(1, precision) / &BigFloat::from_int(3, precision);
+        odd_series(&third, false, precision).mul_pow2(1)
+    }
+
+    /// Computes pi using Machin's formula,
+    /// `pi = 16 atan(1/5) - 4 atan(1/239)`.
+    pub fn pi(precision: u64) -> BigFloat {
+        let work = precision + GUARD_BITS;
+        let one = BigFloat::from_int(1, work);
+        let a = odd_series(&(&one / &BigFloat::from_int(5, work)), true, work);
+        let b = odd_series(&(&one / &BigFloat::from_int(239, work)), true, work);
+        (&a.mul_pow2(4) - &b.mul_pow2(2)).with_precision(precision)
+    }
+
+    /// The nth root, which only exists for non-negative numbers.
+    pub fn root(&self, n: u32, precision: u64) -> Option<BigFloat> {
+        if self.is_negative() {
+            return None;
+        }
+        if self.is_zero() {
+            return Some(BigFloat::from_int(0, precision));
+        }
+        // Scale the mantissa so that its root has enough bits, while
+        // keeping the exponent divisible by n.
+        let wanted = (precision + 2) * n as u64;
+        let mut shift = wanted.saturating_sub(self.mantissa.bits()) as i64;
+        shift += (self.exponent - shift).rem_euclid(n as i64);
+        let mantissa = &self.mantissa << shift as u64;
+        Some(BigFloat::new(
+            mantissa.nth_root(n),
+            (self.exponent - shift) / n as i64,
+            precision,
+        ))
+    }
+
+    pub fn sqrt(&self, precision: u64) -> Option<BigFloat> {
+        self.root(2, precision)
+    }
+
+    pub fn exp(&self, precision: u64) -> Option<BigFloat> {
+        if self.is_zero() {
+            return Some(BigFloat::from_int(1, precision));
+        }
+        if self.top() > MAX_ARGUMENT_BITS {
+            return None;
+        }
+        // Split off a power of two, leaving |r| <= ln(2)/2, then
+        // shrink r further so that the series converges quickly.
+        const HALVINGS: i64 = 8;
+        let work = precision + GUARD_BITS;
+        let k = (self.to_f64() / std::f64::consts::LN_2).round() as i64;
+        let ln2 = BigFloat::ln2(work + MAX_ARGUMENT_BITS as u64);
+        let r = &self.with_precision(work) - &ln2.mul_int(&NumInt::from(k));
+        let r = r.with_precision(work).mul_pow2(-HALVINGS);
+        let mut sum = BigFloat::from_int(1, work);
+        let mut term = sum.clone();
+        let mut n = 1;
+        loop {
+            term = &(&term * &r) / &BigFloat::from_int(n, work);
+            if term.is_zero() || term.top() < -(work as i64) - 2 {
+                break;
+            }
+            sum = &sum + &term;
+            n += 1;
+        }
+        for _ in 0..HALVINGS {
+            sum = &sum * &sum;
+        }
+        Some(sum.mul_pow2(k).with_precision(precision))
+    }
+
+    /// The natural logarithm, which only exists for positive numbers.
+    pub fn ln(&self, precision: u64) -> Option<BigFloat> {
+        if self.is_negative() || self.is_zero() {
+            return None;
+        }
+        let work = precision + GUARD_BITS;
+        // Write the number as y 2^k with y in [0.75, 1.5], then use
+        // ln(y) = 2 atanh((y - 1) / (y + 1)).
+        let mut k = self.top() - 1;
+        let mut y = self.with_precision(work).mul_pow2(-k);
+        if y > BigFloat::new(NumInt::from(3), -1, work) {
+            y = y.mul_pow2(-1);
+            k += 1;
+        }
+        let one = BigFloat::from_int(1, work);
+        let z = &(&y - &one) / &(&y + &one);
+        let ln_y = odd_series(&z, false, work).mul_pow2(1);
+        let ln2 = BigFloat::ln2(work + 64);
+        Some((&ln2.mul_int(&NumInt::from(k)) + &ln_y).with_precision(precision))
+    }
+
+    /// The logarithm in the given base.
+    pub fn log(&self, base: &BigFloat, precision: u64) -> Option<BigFloat> {
+        let work = precision + GUARD_BITS;
+        let denom = base.ln(work)?;
+        if denom.is_zero() {
+            return None;
+        }
+        Some((&self.ln(work)? / &denom).with_precision(precision))
+    }
+
+    /// Raises a positive number to a real power, as `e^(y ln x)`.
+    pub fn pow(&self, exp: &BigFloat, precision: u64) -> Option<BigFloat> {
+        // Error in the logarithm is scaled up by the exponent.
+        let work = precision + GUARD_BITS + max(exp.top(), 0) as u64;
+        let product = &exp.with_precision(work) * &self.ln(work)?;
+        product.exp(precision)
+    }
+
+    pub fn hypot(&self, other: &BigFloat, precision: u64) -> BigFloat {
+        let work = precision + GUARD_BITS;
+        let x = self.with_precision(work);
+        let y = other.with_precision(work);
+        (&(&x * &x) + &(&y * &y))
+            .sqrt(precision)
+            .expect("Sums of squares are never negative")
+    }
+
+    /// Computes the sine and cosine together, as they share argument
+    /// reduction.
+    fn sin_cos(&self, precision: u64) -> Option<(BigFloat, BigFloat)> {
+        let work = precision + GUARD_BITS;
+        if self.is_zero() {
+            return Some((BigFloat::from_int(0, work), BigFloat::from_int(1, work)));
+        }
+        if self.top() > 1 << MAX_ARGUMENT_BITS {
+            return None;
+        }
+        // Reduce the argument to |r| <= pi/4 by subtracting a multiple
+        // of pi/2. Arguments close to a multiple lose bits to
+        // cancellation, so retry with enough extra precision.
+        let mut extra = max(self.top(), 0) as u64 + 4;
+        let mut attempts = 0;
+        let (k, r) = loop {
+            let half_pi = BigFloat::pi(work + extra).mul_pow2(-1);
+            let x = self.with_precision(work + extra);
+            let k = (&x / &half_pi).round_int();
+            let r = &x - &half_pi.mul_int(&k);
+            let lost = if r.is_zero() { 0 } else { -r.top() };
+            attempts += 1;
+            if lost <= GUARD_BITS as i64 / 2 || attempts > 2 {
+                break (k, r.with_precision(work));
+            }
+            extra += lost as u64;
+        };
+        let square = &r * &r;
+        let mut sin = r.clone();
+        let mut cos = BigFloat::from_int(1, work);
+        let mut sin_term = sin.clone();
+        let mut cos_term = cos.clone();
+        let mut n = 0;
+        loop {
+            cos_term = -&(&(&cos_term * &square) / &BigFloat::from_int((n + 1) * (n + 2), work));
+            sin_term = -&(&(&sin_term * &square) / &BigFloat::from_int((n + 2) * (n + 3), work));
+            n += 2;
+            if cos_term.is_zero() || cos_term.top() < -(work as i64) - 2 {
+                break;
+            }
+            cos = &cos + &cos_term;
+            sin = &sin + &sin_term;
+        }
+        let quadrant = (k % NumInt::from(4)).to_i64().unwrap().rem_euclid(4);
+        let (sin, cos) = match quadrant {
+            0 => (sin, cos),
+            1 => (cos, -&sin),
+            2 => (-&sin, -&cos),
+            _ => (-&cos, sin),
+        };
+        Some((sin.with_precision(precision), cos.with_precision(precision)))
+    }
+
+    pub fn sin(&self, precision: u64) -> Option<BigFloat> {
+        self.sin_cos(precision).map(|(sin, _)| sin)
+    }
+
+    pub fn cos(&self, precision: u64) -> Option<BigFloat> {
+        self.sin_cos(precision).map(|(_, cos)| cos)
+    }
+
+    pub fn tan(&self, precision: u64) -> Option<BigFloat> {
+        let (sin, cos) = self.sin_cos(precision + GUARD_BITS)?;
+        if cos.is_zero() {
+            return None;
+        }
+        Some((&sin / &cos).with_precision(precision))
+    }
+
+    pub fn atan(&self, precision: u64) -> Option<BigFloat> {
+        let work = precision + GUARD_BITS;
+        if self.is_zero() {
+            return Some(BigFloat::from_int(0, precision));
+        }
+        let one = BigFloat::from_int(1, work);
+        let x = self.with_precision(work);
+        if x.abs() > one {
+            // atan(x) = ±pi/2 - atan(1/x)
+            let half_pi = BigFloat::pi(work).mul_pow2(-1);
+            let half_pi = if x.is_negative() { -&half_pi } else { half_pi };
+            let inner = (&one / &x).atan(work)?;
+            return Some((&half_pi - &inner).with_precision(precision));
+        }
+        // Halve the angle a few times using
+        // atan(x) = 2 atan(x / (1 + sqrt(1 + x^2))).
+        const HALVINGS: i64 = 4;
+        let mut x = x;
+        for _ in 0..HALVINGS {
+            let root = (&one + &(&x * &x)).sqrt(work)?;
+            x = &x / &(&one + &root);
+        }
+        Some(
+            odd_series(&x, true, work)
+                .mul_pow2(HALVINGS)
+                .with_precision(precision),
+        )
+    }
+
+    pub fn asin(&self, precision: u64) -> Option<BigFloat> {
+        let work = precision + GUARD_BITS;
+        let one = BigFloat::from_int(1, work);
+        match self.abs().cmp(&one) {
+            Ordering::Greater => None,
+            Ordering::Equal => {
+                let half_pi = BigFloat::pi(precision).mul_pow2(-1);
+                Some(if self.is_negative() {
+                    -&half_pi
+                } else {
+                    half_pi
+                })
+            }
+            Ordering::Less => {
+                let x = self.with_precision(work);
+                let root = (&(&one - &x) * &(&one + &x)).sqrt(work)?;
+                (&x / &root).atan(precision)
+            }
+        }
+    }
+
+    pub fn acos(&self, precision: u64) -> Option<BigFloat> {
+        let work = precision + GUARD_BITS;
+        let one = BigFloat::from_int(1, work);
+        if self.abs() > one {
+            return None;
+        }
+        if *self == -&one {
+            return Some(BigFloat::pi(precision));
+        }
+        // acos(x) = 2 atan(sqrt((1 - x) / (1 + x))), which avoids
+        // cancellation near 1.
+        let x = self.with_precision(work);
+        let ratio = &(&one - &x) / &(&one + &x);
+        Some(ratio.sqrt(work)?.atan(precision)?.mul_pow2(1))
+    }
+
+    /// The angle of the point `(x, self)`, like `f64::atan2`.
+    pub fn atan2(&self, x: &BigFloat, precision: u64) -> Option<BigFloat> {
+        let work = precision + GUARD_BITS;
+        if x.is_zero() {
+            let half_pi = BigFloat::pi(precision).mul_pow2(-1);
+            return Some(match self.cmp(x) {
+                Ordering::Equal => BigFloat::from_int(0, precision),
+                Ordering::Greater => half_pi,
+                Ordering::Less => -&half_pi,
+            });
+        }
+        let angle = (&self.with_precision(work) / &x.with_precision(work)).atan(work)?;
+        let angle = if !x.is_negative() {
+            angle
+        } else if self.is_negative() {
+            &angle - &BigFloat::pi(work)
+        } else {
+            &angle + &BigFloat::pi(work)
+        };
+        Some(angle.with_precision(precision))
+    }
+
+    /// Bits lost to cancellation when computing functions like `sinh`
+    /// near zero.
+    fn small_bits(&self) -> u64 {
+        max(-self.top(), 0) as u64
+    }
+
+    pub fn sinh(&self, precision: u64) -> Option<BigFloat> {
+        let work = precision + GUARD_BITS + self.small_bits();
+        let e = self.exp(work)?;
+        let inverse = &BigFloat::from_int(1, work) / &e;
+        Some((&e - &inverse).mul_pow2(-1).with_precision(precision))
+    }
+
+    pub fn cosh(&self, precision: u64) -> Option<BigFloat> {
+        let work = precision + GUARD_BITS;
+        let e = self.exp(work)?;
+        let inverse = &BigFloat::from_int(1, work) / &e;
+        Some((&e + &inverse).mul_pow2(-1).with_precision(precision))
+    }
+
+    pub fn tanh(&self, precision: u64) -> Option<BigFloat> {
+        let work = precision + GUARD_BITS + self.small_bits();
+        let one = BigFloat::from_int(1, work);
+        let e = self.mul_pow2(1).exp(work)?;
+        Some((&(&e - &one) / &(&e + &one)).with_precision(precision))
+    }
+
+    pub fn asinh(&self, precision: u64) -> Option<BigFloat> {
+        let work = precision + GUARD_BITS + self.small_bits();
+        let one = BigFloat::from_int(1, work);
+        let x = self.with_precision(work).abs();
+        let root = (&(&x * &x) + &one).sqrt(work)?;
+        let result = (&x + &root).ln(precision)?;
+        Some(if self.is_negative() { -&result } else { result })
+    }
+
+    pub fn acosh(&self, precision: u64) -> Option<BigFloat> {
+        let work = precision + GUARD_BITS;
+        let one = BigFloat::from_int(1, work);
+        if *self < one {
+            return None;
+        }
+        let x = self.with_precision(work);
+        let root = (&(&x - &one) * &(&x + &one)).sqrt(work)?;
+        (&x + &root).ln(precision)
+    }
+
+    pub fn atanh(&self, precision: u64) -> Option<BigFloat> {
+        let work = precision + GUARD_BITS + self.small_bits();
+        let one = BigFloat::from_int(1, work);
+        if self.abs() >= one {
+            return None;
+        }
+        let x = self.with_precision(work);
+        let ratio = &(&one + &x) / &(&one - &x);
+        Some(
+            ratio
+                .ln(precision + 1)?
+                .mul_pow2(-1)
+                .with_precision(precision),
+        )
+    }
+}
+
+impl PartialEq for BigFloat {
+    fn eq(&self, other: &BigFloat) -> bool {
+        self.mantissa == other.mantissa && self.exponent == other.exponent
+    }
+}
+
+impl Eq for BigFloat {}
+
+impl PartialOrd for BigFloat {
+    fn partial_cmp(&self, other: &BigFloat) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for BigFloat {
+    fn cmp(&self, other: &BigFloat) -> Ordering {
+        let sign = self.mantissa.sign().cmp(&other.mantissa.sign());
+        if sign != Ordering::Equal || self.is_zero() {
+            return sign;
+        }
+        let magnitude = self.top().cmp(&other.top()).then_with(|| {
+            let exponent = min(self.exponent, other.exponent);
+            let left = self.mantissa.abs() << (self.exponent - exponent) as u64;
+            let right = other.mantissa.abs() << (other.exponent - exponent) as u64;
+            left.cmp(&right)
+        });
+        if self.is_negative() {
+            magnitude.reverse()
+        } else {
+            magnitude
+        }
+    }
+}
+
+impl<'a> Add for &'a BigFloat {
+    type Output = BigFloat;
+
+    fn add(self, rhs: &'a BigFloat) -> BigFloat {
+        let precision = max(self.precision, rhs.precision);
+        // Bits far below the precision of the larger number can't
+        // change the result, and aligning them would be expensive.
+        let limit = max(self.top(), rhs.top()) - precision as i64 - 2;
+        if self.is_zero() || self.top() < limit {
+            return rhs.with_precision(precision);
+        }
+        if rhs.is_zero() || rhs.top() < limit {
+            return self.with_precision(precision);
+        }
+        let exponent = min(self.exponent, rhs.exponent);
+        let left = &self.mantissa << (self.exponent - exponent) as u64;
+        let right = &rhs.mantissa << (rhs.exponent - exponent) as u64;
+        BigFloat::new(left + right, exponent, precision)
+    }
+}
+
+impl<'a> Sub for &'a BigFloat {
+    type Output = BigFloat;
+
+    fn sub(self, rhs: &'a BigFloat) -> BigFloat {
+        self + &-rhs
+    }
+}
+
+impl<'a> Neg for &'a BigFloat {
+    type Output = BigFloat;
+
+    fn neg(self) -> BigFloat {
+        BigFloat {
+            mantissa: -&self.mantissa,
+            ..self.clone()
+        }
+    }
+}
+
+impl<'a> Mul for &'a BigFloat {
+    type Output = BigFloat;
+
+    fn mul(self, rhs: &'a BigFloat) -> BigFloat {
+        BigFloat::new(
+            &self.mantissa * &rhs.mantissa,
+            self.exponent + rhs.exponent,
+            max(self.precision, rhs.precision),
+        )
+    }
+}
+
+impl<'a> Div for &'a BigFloat {
+    type Output = BigFloat;
+
+    fn div(self, rhs: &'a BigFloat) -> BigFloat {
+        let precision = max(self.precision, rhs.precision);
+        let shift = precision as i64 + 2 + rhs.mantissa.bits() as i64 - self.mantissa.bits() as i64;
+        let shift = max(shift, 0);
+        BigFloat::new(
+            (&self.mantissa << shift as u64) / &rhs.mantissa,
+            self.exponent - rhs.exponent - shift,
+            precision,
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::BigFloat;
+    use crate::bigint::BigInt;
+    use crate::bigrat::BigRat;
+
+    fn decimal(digits: &str) -> BigRat {
+        let point = digits.find('.').unwrap();
+        let numer = BigInt::from_str_radix(&digits.replace('.', ""), 10).unwrap();
+        let denom = BigInt::from(10u64).pow((digits.len() - point - 1) as u32);
+        BigRat::ratio(&numer, &denom)
+    }
+
+    #[test]
+    fn test_from_rat_rounds() {
+        let third = BigFloat::from_rat(&BigRat::small_ratio(1, 3), 10);
+        assert_eq!(third.to_rat(), BigRat::small_ratio(683, 2048));
+        let exact = BigFloat::from_rat(&BigRat::small_ratio(-3, 8), 10);
+        assert_eq!(exact.to_rat(), BigRat::small_ratio(-3, 8));
+    }
+
+    #[test]
+    fn test_functions() {
+        let p = 100;
+        let two = BigFloat::from_int(2, p);
+        let check = |value: Option<BigFloat>, expected: &str| {
+            assert_eq!(value.unwrap().to_decimal(), decimal(expected));
+        };
+        check(two.sqrt(p), "1.41421356237309504880168872421");
+        check(two.ln(p), "0.69314718055994530941723212146");
+        check(two.exp(p), "7.38905609893065022723042746058");
+        check(two.sin(p), "0.90929742682568169539601986591");
+        check(two.atan(p), "1.10714871779409050301706546018");
+        check(Some(BigFloat::pi(p)), "3.14159265358979323846264338328");
+        assert_eq!(BigFloat::from_int(-1, p).ln(p), None);
+        assert_eq!(two.asin(p), None);
+    }
+}
@@ -4,10 +4,11 @@
 
 use num::bigint::BigInt as NumInt;
 use num::cast::ToPrimitive;
+use num::integer::Integer;
 use num::traits::{Num, One, Zero};
 use std::cmp::Ord;
 use std::fmt;
-use std::ops::{Div, Mul, Rem};
+use std::ops::{Add, Div, Mul, Rem, Sub};
 
 #[derive(PartialEq, Eq, PartialOrd, Ord, Debug)]
 pub struct BigInt {
@@ -60,6 +61,12 @@ impl BigInt {
     pub fn as_int(&self) -> Option<i64> {
         self.inner.to_i64()
     }
+
+    pub fn gcd(&self, other: &BigInt) -> BigInt {
+        BigInt {
+            inner: self.inner.gcd(&other.inner),
+        }
+    }
 }
 
 impl fmt::Display for BigInt {
@@ -90,6 +97,26 @@ impl From<i64> for BigInt {
     }
 }
 
+impl<'a> Add for &'a BigInt {
+    type Output = BigInt;
+
+    fn add(self, rhs: &'a BigInt) -> BigInt {
+        BigInt {
+            inner: &self.inner + &rhs.inner,
+        }
+    }
+}
+
+impl<'a> Sub for &'a BigInt {
+    type Output = BigInt;
+
+    fn sub(self, rhs: &'a BigInt) -> BigInt {
+        BigInt {
+            inner: &self.inner - &rhs.inner,
+        }
+    }
+}
+
 impl<'a> Mul for &'a BigInt {
     type Output = BigInt;
 
@@ -66,6 +66,31 @@ impl BigRat {
     pub fn as_float(&self) -> f64 {
         self.inner.to_f64().unwrap()
     }
+
+    pub fn floor(&self) -> BigRat {
+        BigRat {
+            inner: self.inner.floor(),
+        }
+    }
+
+    pub fn ceil(&self) -> BigRat {
+        BigRat {
+            inner: self.inner.ceil(),
+        }
+    }
+
+    /// Rounds to the nearest integer, with ties away from zero.
+    pub fn round(&self) -> BigRat {
+        BigRat {
+            inner: self.inner.round(),
+        }
+    }
+
+    pub fn trunc(&self) -> BigRat {
+        BigRat {
+            inner: self.inner.trunc(),
+        }
+    }
 }
 
 impl From<NumRat> for BigRat {
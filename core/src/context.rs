@@ -2,13 +2,18 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::ast::{DatePattern, Expr};
+use crate::ast::{DatePattern, Expr, FunctionDef};
+use crate::bigfloat::{BigFloat, DEFAULT_DIGITS};
 use crate::number::{Dimension, Number, Quantity};
 use crate::numeric::Numeric;
 use crate::reply::NotFoundError;
+use crate::scale::{self, Scale};
 use crate::search;
 use crate::substance::Substance;
+use crate::symbolic::Symbolic;
+use crate::value::Value;
 use chrono::{DateTime, TimeZone, Utc};
+use std::cell::{Cell, RefCell};
 use std::collections::{BTreeMap, BTreeSet};
 
 /// The evaluation context that contains unit definitions.
@@ -28,9 +33,28 @@ pub struct Context {
     pub substances: BTreeMap<String, Substance>,
     pub substance_symbols: BTreeMap<String, String>,
     pub temporaries: BTreeMap<String, Number>,
+    /// Session variables bound with `name = expr`. These shadow units
+    /// of the same name.
+    pub variables: BTreeMap<String, Value>,
+    /// User-defined functions, from units files or the session.
+    pub functions: BTreeMap<String, FunctionDef>,
+    /// Nonlinear units like `°C` and `dBm`, by name.
+    pub scales: BTreeMap<String, Scale>,
+    /// Arguments of the user-defined functions currently being called,
+    /// innermost last.
+    pub(crate) locals: RefCell<Vec<BTreeMap<String, Value>>>,
     pub now: DateTime<Utc>,
     pub short_output: bool,
     pub use_humanize: bool,
+    /// Whether the difference between two dates is broken down into
+    /// calendar months and years, rather than 365-day years.
+    pub calendar_durations: bool,
+    /// Significant digits that functions like `sqrt` and `sin` compute
+    /// their results to.
+    pub precision: u64,
+    /// Digits asked for by the `-> digits n` query being evaluated,
+    /// which are computed on top of `precision`.
+    pub(crate) requested_digits: Cell<u64>,
 }
 
 impl Default for Context {
@@ -43,9 +67,12 @@ impl Default for Context {
 impl Context {
     /// Creates a new, empty context
     pub fn new() -> Context {
-        Context {
+        let mut ctx = Context {
             short_output: false,
             use_humanize: true,
+            calendar_durations: true,
+            precision: DEFAULT_DIGITS,
+            requested_digits: Cell::new(0),
 
             now: Utc.ymd(2000, 1, 1).and_hms(0, 0, 0),
 
@@ -63,7 +90,22 @@ impl Context {
             substances: BTreeMap::new(),
             substance_symbols: BTreeMap::new(),
             temporaries: BTreeMap::new(),
-        }
+            variables: BTreeMap::new(),
+            functions: BTreeMap::new(),
+            scales: scale::builtin(),
+            locals: RefCell::new(vec![]),
+        };
+        // Pi is built in so that it stays exact, rather than being a
+        // decimal in the units file.
+        ctx.units
+            .insert("π".to_owned(), Number::new(Symbolic::pi()));
+        ctx.docs.insert(
+            "π".to_owned(),
+            "Ratio of a circle's circumference to its diameter.".to_owned(),
+        );
+        ctx.categories
+            .insert("π".to_owned(), "constants".to_owned());
+        ctx
     }
 
     pub fn set_time(&mut self, time: DateTime<Utc>) {
@@ -78,6 +120,11 @@ impl Context {
         self.datepatterns.append(&mut dates)
     }
 
+    /// The number of bits that inexact functions are computed to.
+    pub fn working_precision(&self) -> u64 {
+        BigFloat::bits_for_digits(self.precision + self.requested_digits.get())
+    }
+
     /// Given a unit name, returns its value if it exists. Supports SI
     /// prefixes, plurals, bare dimensions like length, and quantities.
     pub fn lookup(&self, name: &str) -> Option<Number> {
@@ -124,7 +171,15 @@ impl Context {
         // after so that "ks" is kiloseconds
         if name.ends_with('s') {
             let name = &name[0..name.len() - 1];
-            outer(name)
+            let res = outer(name);
+            if res.is_some() {
+                return res;
+            }
+        }
+
+        // The imaginary unit, last so that it can't shadow any unit.
+        if name == "i" || name == "j" {
+            Some(Number::new(Numeric::i()))
         } else {
             None
         }
@@ -199,7 +254,7 @@ impl Context {
             value: Numeric::one(),
             unit: value.unit.clone(),
         }
-        .root(2)
+        .root(2, self.working_precision())
         .ok();
         let inverse = (&Number::one()
             / &Number {
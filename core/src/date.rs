@@ -2,21 +2,26 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::ast::{DatePattern, DateToken};
+use crate::ast::{Conversion, DatePattern, DateToken};
 use crate::bigint::BigInt;
 use crate::bigrat::BigRat;
 use crate::context::Context;
 use crate::number::{Dimension, Number};
-use crate::numeric::Numeric;
-use chrono::format::Parsed;
-use chrono::{DateTime, Duration, FixedOffset, TimeZone, Utc, Weekday};
+use crate::numeric::{Digits, Numeric};
+use chrono::format::{Item, Parsed, StrftimeItems};
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime,
+    Offset, SecondsFormat, TimeZone, Utc, Weekday,
+};
 use chrono_tz::Tz;
+use std::convert::TryFrom;
 use std::iter::Peekable;
 use std::str::FromStr;
 
 pub fn parse_date<I>(
     out: &mut Parsed,
     out_tz: &mut Option<Tz>,
+    out_rel: &mut RelativeDate,
     date: &mut Peekable<I>,
     pat: &[DatePattern],
 ) -> Result<(), String>
@@ -110,7 +115,8 @@ where
                     Some(x) => x,
                     None => take!(DateToken::Number(x, None), x),
                 };
-                let value = i32::from_str_radix(&*num, 10).unwrap();
+                let value = i32::from_str_radix(&num, 10)
+                    .map_err(|_| format!("Year {} is out of range", num))?;
                 out.year = Some(value * sign);
                 Ok(())
             }
@@ -129,12 +135,12 @@ where
                 x => Err(format!("Expected AD/BC or CE/BCE, got {}", ts(x))),
             },
             "hour12" => match tok {
-                Some(DateToken::Number(ref s, None)) if s.len() == 2 => {
+                Some(DateToken::Number(ref s, None)) if s.len() <= 2 => {
                     let value = u32::from_str_radix(&**s, 10).unwrap();
                     out.hour_mod_12 = Some(value % 12);
                     Ok(())
                 }
-                x => Err(format!("Expected 2-digit hour12, got {}", ts(x))),
+                x => Err(format!("Expected 1 or 2-digit hour12, got {}", ts(x))),
             },
             "hour24" => match tok {
                 Some(DateToken::Number(ref s, None)) if s.len() == 2 => {
@@ -243,12 +249,87 @@ where
                 }
                 x => Err(format!("Expected weekday, got {}", ts(x))),
             },
+            "relday" => match tok {
+                Some(DateToken::Literal(ref s)) => {
+                    let res = match &*s.to_lowercase() {
+                        "yesterday" => -1,
+                        "today" => 0,
+                        "tomorrow" => 1,
+                        x => return Err(format!("Unknown relative day: {}", x)),
+                    };
+                    out_rel.days = Some(res);
+                    Ok(())
+                }
+                x => Err(format!(
+                    "Expected yesterday, today or tomorrow, got {}",
+                    ts(x)
+                )),
+            },
+            "reldir" => match tok {
+                Some(DateToken::Literal(ref s)) => {
+                    let res = match &*s.to_lowercase() {
+                        "next" => 1,
+                        "last" => -1,
+                        x => return Err(format!("Expected next or last, got {}", x)),
+                    };
+                    out_rel.direction = Some(res);
+                    Ok(())
+                }
+                x => Err(format!("Expected next or last, got {}", ts(x))),
+            },
+            "count" => match tok {
+                Some(DateToken::Number(ref s, None)) => {
+                    let value =
+                        i64::from_str_radix(s, 10).map_err(|_| "Count is too large".to_owned());
+                    value.map(|value| out_rel.count = Some(value))
+                }
+                x => Err(format!("Expected count, got {}", ts(x))),
+            },
+            "relunit" => match tok {
+                Some(DateToken::Literal(ref s)) => {
+                    let res = match &*s.to_lowercase() {
+                        "sec" | "secs" | "second" | "seconds" => RelativeUnit::Seconds(1),
+                        "min" | "mins" | "minute" | "minutes" => RelativeUnit::Seconds(60),
+                        "hour" | "hours" => RelativeUnit::Seconds(3600),
+                        "day" | "days" => RelativeUnit::Seconds(86400),
+                        "week" | "weeks" => RelativeUnit::Seconds(7 * 86400),
+                        "fortnight" | "fortnights" => RelativeUnit::Seconds(14 * 86400),
+                        "month" | "months" => RelativeUnit::Months(1),
+                        "year" | "years" => RelativeUnit::Months(12),
+                        x => return Err(format!("Unknown relative unit: {}", x)),
+                    };
+                    out_rel.unit = Some(res);
+                    Ok(())
+                }
+                x => Err(format!("Expected unit, got {}", ts(x))),
+            },
+            "ago" => match tok {
+                Some(DateToken::Literal(ref s)) if s.to_lowercase() == "ago" => {
+                    out_rel.ago = true;
+                    Ok(())
+                }
+                x => Err(format!("Expected `ago`, got {}", ts(x))),
+            },
+            "period" => match tok {
+                Some(DateToken::Literal(ref s)) => {
+                    let res = match &*s.to_lowercase() {
+                        "day" => Period::Day,
+                        "week" => Period::Week,
+                        "month" => Period::Month,
+                        "year" => Period::Year,
+                        x => return Err(format!("Unknown period: {}", x)),
+                    };
+                    out_rel.start_of = Some(res);
+                    Ok(())
+                }
+                x => Err(format!("Expected day, week, month or year, got {}", ts(x))),
+            },
             x => Err(format!("Unknown match pattern `{}`", x)),
         },
         Some(&DatePattern::Optional(ref pats)) => {
             advance = false;
             let mut iter = date.clone();
-            if let Ok(()) = parse_date(out, out_tz, &mut iter, &pats[..]) {
+            if let Ok(()) = parse_date(out, out_tz, out_rel, &mut iter, &pats[..]) {
                 *date = iter
             }
             Ok(())
@@ -269,7 +350,41 @@ where
     if advance {
         date.next();
     }
-    res.and_then(|_| parse_date(out, out_tz, date, &pat[1..]))
+    res.and_then(|_| parse_date(out, out_tz, out_rel, date, &pat[1..]))
+}
+
+/// The parts of a relative date like `tomorrow 9am`, `next friday` or
+/// `3 weeks ago`, which are resolved against the current time.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RelativeDate {
+    /// Days from today, from `yesterday`, `today` or `tomorrow`.
+    pub days: Option<i64>,
+    /// 1 for `next` and -1 for `last`, which move to the parsed weekday.
+    pub direction: Option<i64>,
+    pub count: Option<i64>,
+    pub unit: Option<RelativeUnit>,
+    /// Whether `count` units are in the past rather than the future.
+    pub ago: bool,
+    /// The period that `start of` goes to the beginning of.
+    pub start_of: Option<Period>,
+}
+
+/// The unit of a relative date like `3 weeks ago`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RelativeUnit {
+    Seconds(i64),
+    /// Calendar months, which vary in length.
+    Months(i64),
+}
+
+/// A period of the calendar, as in `start of month`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    /// Weeks start on Monday.
+    Week,
+    Month,
+    Year,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -285,6 +400,290 @@ impl GenericDateTime {
             GenericDateTime::Timezone(ref d) => d.with_timezone(tz),
         }
     }
+
+    /// The date and time on the local clock.
+    pub fn naive_local(&self) -> NaiveDateTime {
+        match *self {
+            GenericDateTime::Fixed(ref d) => d.naive_local(),
+            GenericDateTime::Timezone(ref d) => d.naive_local(),
+        }
+    }
+
+    /// Moves the date by a fixed length of time. Returns `None` if the
+    /// result is out of range.
+    pub fn checked_add_signed(&self, duration: Duration) -> Option<GenericDateTime> {
+        match *self {
+            GenericDateTime::Fixed(ref d) => {
+                d.checked_add_signed(duration).map(GenericDateTime::Fixed)
+            }
+            GenericDateTime::Timezone(ref d) => d
+                .checked_add_signed(duration)
+                .map(GenericDateTime::Timezone),
+        }
+    }
+
+    /// Moves the date by whole calendar months, keeping the local time
+    /// of day. Returns `None` if the result is out of range.
+    pub fn add_months(&self, months: i64) -> Option<GenericDateTime> {
+        self.with_local(add_months(self.naive_local(), months)?)
+    }
+
+    /// Reads a local date and time in the same time zone. A time that
+    /// falls in a daylight saving gap is moved forward by the length of
+    /// the gap, and an ambiguous one takes the earlier offset.
+    pub fn with_local(&self, local: NaiveDateTime) -> Option<GenericDateTime> {
+        match *self {
+            GenericDateTime::Fixed(ref d) => d
+                .offset()
+                .from_local_datetime(&local)
+                .single()
+                .map(GenericDateTime::Fixed),
+            GenericDateTime::Timezone(ref d) => {
+                let tz = d.timezone();
+                match tz.from_local_datetime(&local) {
+                    LocalResult::Single(d) | LocalResult::Ambiguous(d, _) => Some(d),
+                    LocalResult::None => {
+                        // Read it in the offset from before the gap.
+                        let before = tz
+                            .offset_from_utc_datetime(&(local - Duration::days(1)))
+                            .fix();
+                        let utc = local - Duration::seconds(before.local_minus_utc() as i64);
+                        Some(tz.from_utc_datetime(&utc))
+                    }
+                }
+                .map(GenericDateTime::Timezone)
+            }
+        }
+    }
+
+    /// Formats the date for a conversion like `-> iso8601`.
+    pub fn format_as(&self, format: &Conversion) -> Result<String, String> {
+        match *self {
+            GenericDateTime::Fixed(ref d) => format_as(d, format),
+            GenericDateTime::Timezone(ref d) => format_as(d, format),
+        }
+    }
+}
+
+fn format_as<Tz>(date: &DateTime<Tz>, format: &Conversion) -> Result<String, String>
+where
+    Tz: TimeZone,
+    Tz::Offset: std::fmt::Display,
+{
+    use std::fmt::Write;
+
+    match *format {
+        Conversion::Iso8601 => Ok(date.to_rfc3339_opts(SecondsFormat::AutoSi, false)),
+        // RFC 2822 only has four digit years.
+        Conversion::Rfc2822 if !(0..=9999).contains(&date.year()) => Err(format!(
+            "Cannot write the year {} as rfc2822, which only allows years 0 to 9999",
+            date.year()
+        )),
+        Conversion::Rfc2822 => Ok(date.to_rfc2822()),
+        Conversion::Unix => Ok(date.timestamp().to_string()),
+        Conversion::UnixMs => Ok(date.timestamp_millis().to_string()),
+        Conversion::Strftime(ref string) => {
+            let items = StrftimeItems::new(string).collect::<Vec<_>>();
+            if items.contains(&Item::Error) {
+                return Err(format!("Invalid strftime format string: {:?}", string));
+            }
+            // `%#z` is only for parsing, and chrono panics if it's
+            // written. Its item is private, so it's found by parsing it.
+            let parse_only = StrftimeItems::new("%#z").next();
+            if items.iter().any(|item| Some(item) == parse_only.as_ref()) {
+                return Err(format!(
+                    "Cannot format date as strftime {:?}, as %#z is only for parsing",
+                    string
+                ));
+            }
+            let mut out = String::new();
+            write!(out, "{}", date.format_with_items(items.into_iter()))
+                .map_err(|_| format!("Cannot format date as strftime {:?}", string))?;
+            Ok(out)
+        }
+        ref format => Err(format!("{} is not a date format", format)),
+    }
+}
+
+/// Formats a duration in ISO 8601, like `P1Y2M3DT4H5M6.5S`, leaving
+/// out the parts that are zero. `seconds` may be more than a day.
+pub fn iso8601_duration(
+    negative: bool,
+    years: i64,
+    months: i64,
+    days: i64,
+    seconds: &Numeric,
+) -> String {
+    use std::fmt::Write;
+
+    let (hours, seconds) = seconds.div_rem(&Numeric::from(3600));
+    let (minutes, seconds) = seconds.div_rem(&Numeric::from(60));
+    let mut out = if negative { "-P" } else { "P" }.to_owned();
+    for &(value, designator) in &[(years, 'Y'), (months, 'M'), (days, 'D')] {
+        if value != 0 {
+            write!(out, "{}{}", value, designator).unwrap();
+        }
+    }
+    let mut time = String::new();
+    for (value, designator) in &[(hours, 'H'), (minutes, 'M'), (seconds, 'S')] {
+        if !value.is_zero() {
+            write!(
+                time,
+                "{}{}",
+                value.to_string(10, Digits::Default).1,
+                designator
+            )
+            .unwrap();
+        }
+    }
+    if !time.is_empty() {
+        write!(out, "T{}", time).unwrap();
+    } else if out.ends_with('P') {
+        out.push_str("T0S");
+    }
+    out
+}
+
+/// A whole number of calendar months, as in `now + 2 years`, along
+/// with anything of a fixed length, as in `P1M2D`. Adding it to a date
+/// moves by the calendar, rather than by its length in seconds.
+#[derive(Debug, Clone)]
+pub struct CalendarDuration {
+    pub months: i64,
+    /// What's added after the months, in seconds.
+    pub rest: Number,
+    /// The length in seconds, used when it isn't added to a date.
+    pub seconds: Number,
+}
+
+impl CalendarDuration {
+    /// A unit like `month` or `year`, which is `seconds` long on
+    /// average.
+    pub fn unit(months: i64, seconds: Number) -> CalendarDuration {
+        CalendarDuration {
+            months,
+            rest: Number::new_unit(Numeric::zero(), Dimension::new("s")),
+            seconds,
+        }
+    }
+
+    /// Returns `None` if the months overflow.
+    pub fn add(&self, other: &CalendarDuration) -> Option<CalendarDuration> {
+        Some(CalendarDuration {
+            months: self.months.checked_add(other.months)?,
+            rest: (&self.rest + &other.rest)?,
+            seconds: (&self.seconds + &other.seconds)?,
+        })
+    }
+
+    /// Adds a length of time to the rest, as in `1 month + 2 days`.
+    /// Returns `None` if it isn't a length of time.
+    pub fn add_time(&self, time: &Number) -> Option<CalendarDuration> {
+        Some(CalendarDuration {
+            months: self.months,
+            rest: (&self.rest + time)?,
+            seconds: (&self.seconds + time)?,
+        })
+    }
+
+    pub fn neg(&self) -> Option<CalendarDuration> {
+        Some(CalendarDuration {
+            months: self.months.checked_neg()?,
+            rest: (-&self.rest)?,
+            seconds: (-&self.seconds)?,
+        })
+    }
+
+    /// Multiplies by a dimensionless number. Returns `None` unless it's
+    /// exact and the result is still a whole number of months.
+    pub fn scale(&self, factor: &Number) -> Option<CalendarDuration> {
+        if !factor.dimless() || !factor.value.is_rational() {
+            return None;
+        }
+        let (months, rest) = (&Numeric::from(self.months) * &factor.value).div_rem(&Numeric::one());
+        if !rest.is_zero() {
+            return None;
+        }
+        Some(CalendarDuration {
+            months: months.to_int()?,
+            rest: (&self.rest * factor)?,
+            seconds: (&self.seconds * factor)?,
+        })
+    }
+}
+
+/// The difference between two dates, from subtracting them. The dates
+/// are kept so that it can be broken down into calendar months and
+/// years, which vary in length.
+#[derive(Debug, Clone)]
+pub struct DateSpan {
+    /// The length of the span in seconds, negative if `end` is before
+    /// `start`.
+    pub seconds: Number,
+    pub start: GenericDateTime,
+    pub end: GenericDateTime,
+}
+
+/// A `DateSpan` broken down into calendar units, all of which have the
+/// same sign.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CalendarSpan {
+    pub negative: bool,
+    pub years: i64,
+    pub months: i64,
+    pub days: i64,
+    /// What's left over, which is less than a day.
+    pub rest: Duration,
+}
+
+/// Moves a date by whole months, clamping the day to the end of the
+/// month, so that January 31 plus one month is the last day of
+/// February. Returns `None` if the result is out of range.
+fn add_months(date: NaiveDateTime, months: i64) -> Option<NaiveDateTime> {
+    let total = (date.year() as i64 * 12 + date.month0() as i64).checked_add(months)?;
+    let year = i32::try_from(total.div_euclid(12)).ok()?;
+    let month = total.rem_euclid(12) as u32 + 1;
+    let next_month = if month == 12 {
+        NaiveDate::from_ymd_opt(year.checked_add(1)?, 1, 1)?
+    } else {
+        NaiveDate::from_ymd_opt(year, month + 1, 1)?
+    };
+    let last_day = next_month.pred().day();
+    NaiveDate::from_ymd_opt(year, month, date.day().min(last_day)).map(|d| d.and_time(date.time()))
+}
+
+impl DateSpan {
+    /// Breaks the span down into the years, months and days that take
+    /// the earlier date to the later one, followed by the rest. Both
+    /// dates are read in the UTC offset of `start`.
+    pub fn calendar(&self) -> CalendarSpan {
+        let offset = match self.start {
+            GenericDateTime::Fixed(ref date) => *date.offset(),
+            GenericDateTime::Timezone(ref date) => date.offset().fix(),
+        };
+        let start = self.start.with_timezone(&offset).naive_local();
+        let end = self.end.with_timezone(&offset).naive_local();
+        let (negative, from, to) = if end < start {
+            (true, end, start)
+        } else {
+            (false, start, end)
+        };
+        let mut months =
+            (to.year() as i64 - from.year() as i64) * 12 + to.month() as i64 - from.month() as i64;
+        let add = |months| add_months(from, months).expect("Months between two valid dates");
+        while months > 0 && add(months) > to {
+            months -= 1;
+        }
+        let rest = to - add(months);
+        let days = rest.num_days();
+        CalendarSpan {
+            negative,
+            years: months / 12,
+            months: months % 12,
+            days,
+            rest: rest - Duration::days(days),
+        }
+    }
 }
 
 fn attempt(
@@ -294,8 +693,9 @@ fn attempt(
 ) -> Result<GenericDateTime, (String, usize)> {
     let mut parsed = Parsed::new();
     let mut tz = None;
+    let mut relative = RelativeDate::default();
     let mut iter = date.iter().cloned().peekable();
-    let res = parse_date(&mut parsed, &mut tz, &mut iter, pat);
+    let res = parse_date(&mut parsed, &mut tz, &mut relative, &mut iter, pat);
     let count = iter.count();
     let res = if count > 0 && res.is_ok() {
         Err(format!(
@@ -310,6 +710,9 @@ fn attempt(
         res
     };
     res.map_err(|e| (e, count))?;
+    if relative != RelativeDate::default() {
+        return resolve_relative(now, &relative, &mut parsed, tz).map_err(|e| (e, count));
+    }
     let time = parsed.to_naive_time();
     let date = parsed.to_naive_date();
     if let Some(tz) = tz {
@@ -373,7 +776,129 @@ fn attempt(
     }
 }
 
+/// Resolves a relative date against `now`, read in the time zone or
+/// offset that was given, or else UTC. Days are counted on the local
+/// calendar, and start at midnight unless a time was given.
+fn resolve_relative(
+    now: DateTime<Utc>,
+    relative: &RelativeDate,
+    parsed: &mut Parsed,
+    tz: Option<Tz>,
+) -> Result<GenericDateTime, String> {
+    let now = match tz {
+        Some(tz) => GenericDateTime::Timezone(now.with_timezone(&tz)),
+        None => GenericDateTime::Fixed(
+            now.with_timezone(
+                &parsed
+                    .to_fixed_offset()
+                    .unwrap_or_else(|_| FixedOffset::east(0)),
+            ),
+        ),
+    };
+    let out_of_range = || "Relative date is out of range".to_owned();
+    if let (Some(count), Some(unit)) = (relative.count, relative.unit) {
+        let count = if relative.ago {
+            count.checked_neg()
+        } else {
+            Some(count)
+        };
+        return match unit {
+            RelativeUnit::Months(months) => count
+                .and_then(|count| count.checked_mul(months))
+                .and_then(|months| now.add_months(months)),
+            RelativeUnit::Seconds(seconds) => count
+                .and_then(|count| count.checked_mul(seconds))
+                .filter(|&seconds| seconds.abs() <= i64::MAX / 1000)
+                .and_then(|seconds| now.checked_add_signed(Duration::seconds(seconds))),
+        }
+        .ok_or_else(out_of_range);
+    }
+    let today = now.naive_local().date();
+    let date = match (relative.days, relative.direction, relative.start_of) {
+        (Some(days), _, _) => today.checked_add_signed(Duration::days(days)),
+        (_, Some(direction), _) => {
+            let weekday = parsed
+                .weekday
+                .ok_or_else(|| "Expected a weekday after next or last".to_owned())?;
+            let mut date = Some(today);
+            loop {
+                date = date.and_then(|d| d.checked_add_signed(Duration::days(direction)));
+                match date {
+                    Some(d) if d.weekday() != weekday => (),
+                    _ => break date,
+                }
+            }
+        }
+        (_, _, Some(Period::Day)) => Some(today),
+        (_, _, Some(Period::Week)) => {
+            today.checked_sub_signed(Duration::days(today.weekday().num_days_from_monday() as i64))
+        }
+        (_, _, Some(Period::Month)) => today.with_day(1),
+        (_, _, Some(Period::Year)) => NaiveDate::from_ymd_opt(today.year(), 1, 1),
+        _ => return Err("Incomplete relative date".to_owned()),
+    }
+    .ok_or_else(out_of_range)?;
+    let time = if parsed.hour_mod_12.is_some() {
+        parsed.minute.get_or_insert(0);
+        parsed.to_naive_time().map_err(|e| e.to_string())?
+    } else {
+        NaiveTime::from_hms(0, 0, 0)
+    };
+    now.with_local(date.and_time(time)).ok_or_else(out_of_range)
+}
+
+/// Reads a Unix timestamp, either `@1700000000` or `unix 1700000000`
+/// in seconds, or `unixms 1700000000000` in milliseconds. Returns
+/// `None` if the date isn't one.
+fn decode_timestamp(date: &[DateToken]) -> Option<Result<GenericDateTime, String>> {
+    let (rest, millis) = match date {
+        [DateToken::Literal(ref at), rest @ ..] if at == "@" => (rest, false),
+        [DateToken::Literal(ref unix), DateToken::Space, rest @ ..] if unix == "unix" => {
+            (rest, false)
+        }
+        [DateToken::Literal(ref unix), DateToken::Space, rest @ ..] if unix == "unixms" => {
+            (rest, true)
+        }
+        _ => return None,
+    };
+    let (negative, whole, frac) = match rest {
+        [DateToken::Number(ref whole, ref frac)] => (false, whole, frac),
+        [DateToken::Dash, DateToken::Number(ref whole, ref frac)] => (true, whole, frac),
+        _ => return Some(Err("Expected a number for the timestamp".to_owned())),
+    };
+    // Milliseconds, plus the nanoseconds after those.
+    let digits = if millis { 0 } else { 3 };
+    let frac = format!(
+        "{:0<width$}",
+        frac.as_deref().unwrap_or(""),
+        width = digits + 6
+    );
+    let (frac_ms, frac_ns) = frac.split_at(digits);
+    let ms = whole
+        .parse::<i64>()
+        .ok()
+        .and_then(|whole| whole.checked_mul(10i64.pow(digits as u32)))
+        .and_then(|ms| ms.checked_add(frac_ms.parse().unwrap_or(0)));
+    let ns: i64 = frac_ns[..6].parse().expect("Date numbers are digits");
+    let duration = ms.map(|ms| Duration::milliseconds(ms) + Duration::nanoseconds(ns));
+    let duration = if negative {
+        duration.map(|d| -d)
+    } else {
+        duration
+    };
+    let epoch = Utc.timestamp(0, 0).with_timezone(&FixedOffset::east(0));
+    Some(
+        duration
+            .and_then(|d| epoch.checked_add_signed(d))
+            .map(GenericDateTime::Fixed)
+            .ok_or_else(|| "Timestamp is out of range".to_owned()),
+    )
+}
+
 pub fn try_decode(date: &[DateToken], context: &Context) -> Result<GenericDateTime, String> {
+    if let Some(result) = decode_timestamp(date) {
+        return result;
+    }
     let mut best = None;
     for pat in &context.datepatterns {
         match attempt(context.now, date, pat) {
@@ -429,7 +954,7 @@ pub fn from_duration(duration: &Duration) -> Result<Number, String> {
     let ms = BigRat::ratio(&BigInt::from(ms), &ms_div);
     let ns = BigRat::ratio(&BigInt::from(ns), &ns_div);
     Ok(Number::new_unit(
-        Numeric::Rational(&ms + &ns),
+        Numeric::from(&ms + &ns),
         Dimension::new("s"),
     ))
 }
@@ -540,7 +1065,13 @@ mod tests {
         let mut parsed = Parsed::new();
         let mut tz = None;
         let pat = pattern(pat);
-        let res = parse_date(&mut parsed, &mut tz, &mut date.into_iter().peekable(), &pat);
+        let res = parse_date(
+            &mut parsed,
+            &mut tz,
+            &mut RelativeDate::default(),
+            &mut date.into_iter().peekable(),
+            &pat,
+        );
 
         (res, parsed, tz)
     }
@@ -716,6 +1247,41 @@ mod tests {
         assert_eq!(parse(date, "weekday").0, Err("Unknown weekday: snu".into()));
     }
 
+    #[test]
+    fn test_relative() {
+        let date = vec![
+            DateToken::Number("3".into(), None),
+            DateToken::Space,
+            DateToken::Literal("Weeks".into()),
+            DateToken::Space,
+            DateToken::Literal("ago".into()),
+        ];
+        let mut relative = RelativeDate::default();
+        let res = parse_date(
+            &mut Parsed::new(),
+            &mut None,
+            &mut relative,
+            &mut date.into_iter().peekable(),
+            &pattern("count relunit ago"),
+        );
+        assert!(res.is_ok(), "{}", res.unwrap_err());
+        assert_eq!(
+            relative,
+            RelativeDate {
+                count: Some(3),
+                unit: Some(RelativeUnit::Seconds(7 * 86400)),
+                ago: true,
+                ..RelativeDate::default()
+            }
+        );
+
+        let date = vec![DateToken::Literal("someday".into())];
+        assert_eq!(
+            parse(date, "relday").0,
+            Err("Unknown relative day: someday".into())
+        );
+    }
+
     #[test]
     fn test_monthname() {
         for (i, &s) in [
@@ -2,39 +2,128 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::ast::{BinOpExpr, BinOpType, Conversion, Expr, Function, Query, UnaryOpType};
+use crate::ast::{
+    BinOpExpr, BinOpType, Conversion, Expr, Function, FunctionDef, Query, UnaryOpType,
+};
+use crate::bigfloat::BigFloat;
 use crate::bigint::BigInt;
 use crate::context::Context;
 use crate::date;
 use crate::factorize::{factorize, Factors};
 use crate::formula::substance_from_formula;
+use crate::interval::{self, Interval};
+use crate::list;
 use crate::number::{pow, Dimension, Number, NumberParts};
-use crate::numeric::{Digits, Numeric};
+use crate::numeric::{Digits, Numeric, Rounding};
 use crate::reply::{
-    ConformanceError, ConversionReply, DateReply, DefReply, DurationReply, ExprReply,
-    Factorization, FactorizeReply, QueryError, QueryReply, UnitListReply, UnitsForReply,
-    UnitsInCategory,
+    BindingReply, BoolReply, ConformanceError, ConversionReply, DateReply, DefReply, DurationReply,
+    ExprReply, Factorization, FactorizeReply, FractionReply, ListReply, QueryError, QueryReply,
+    UnitListReply, UnitsForReply, UnitsInCategory,
 };
+use crate::scale::Scale;
 use crate::search;
 use crate::substance::SubstanceGetError;
+use crate::uncertain::{self, Uncertain};
 use crate::value::{Show, Value};
-use chrono::FixedOffset;
+use chrono::{FixedOffset, Utc};
+use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::rc::Rc;
 
+/// How deeply user-defined functions may call each other.
+const MAX_CALL_DEPTH: usize = 64;
+
+/// How many terms of a continued fraction are shown for numbers that
+/// aren't exact. The precision they're computed to goes well beyond
+/// this.
+const MAX_CONTINUED_FRACTION_TERMS: usize = 20;
+
+/// Units that count calendar months when added to a date, and how
+/// many months each is. Plurals are listed, as `centuries` and
+/// `millennia` aren't just a trailing s.
+const CALENDAR_UNITS: &[(&str, i64)] = &[
+    ("month", 1),
+    ("months", 1),
+    ("mo", 1),
+    ("mos", 1),
+    ("year", 12),
+    ("years", 12),
+    ("yr", 12),
+    ("yrs", 12),
+    ("decade", 120),
+    ("decades", 120),
+    ("century", 1200),
+    ("centuries", 1200),
+    ("millennium", 12000),
+    ("millennia", 12000),
+    ("millenniums", 12000),
+];
+
+/// Whether a built-in function combines the elements of a list, rather
+/// than being applied to each element.
+fn is_aggregate(func: &Function) -> bool {
+    match *func {
+        Function::Sum
+        | Function::Mean
+        | Function::Min
+        | Function::Max
+        | Function::Norm
+        | Function::Dot
+        | Function::Cross => true,
+        _ => false,
+    }
+}
+
+/// Whether an expression is a plain number, like `-3` or `1/2`.
+fn is_constant(expr: &Expr) -> bool {
+    match *expr {
+        Expr::Const { .. } => true,
+        Expr::UnaryOp(ref unary) => unary.op == UnaryOpType::Negative && is_constant(&unary.expr),
+        Expr::BinOp(ref binop) => {
+            binop.op == BinOpType::Frac && is_constant(&binop.left) && is_constant(&binop.right)
+        }
+        _ => false,
+    }
+}
+
+fn is_comparison(op: BinOpType) -> bool {
+    match op {
+        BinOpType::Less
+        | BinOpType::LessEqual
+        | BinOpType::Greater
+        | BinOpType::GreaterEqual
+        | BinOpType::Equal
+        | BinOpType::NotEqual => true,
+        _ => false,
+    }
+}
+
 impl Context {
     /// Evaluates an expression to compute its value, *excluding* `->`
     /// conversions.
     pub fn eval(&self, expr: &Expr) -> Result<Value, QueryError> {
+        self.eval_keeping_dates(expr).map(Value::forget_dates)
+    }
+
+    /// Like `eval`, but the difference between two dates keeps them,
+    /// so that it can be shown in calendar months and years.
+    fn eval_keeping_dates(&self, expr: &Expr) -> Result<Value, QueryError> {
         use std::ops::*;
 
         match *expr {
             Expr::Unit { ref name } if name == "now" => Ok(Value::DateTime(
                 date::GenericDateTime::Fixed(self.now.with_timezone(&FixedOffset::east(0))),
             )),
+            Expr::Unit { ref name } if self.lookup_scale(name).is_some() => {
+                Err(QueryError::generic(format!(
+                    "{} is a nonlinear unit and must follow a number, like `20 {}`",
+                    name, name
+                )))
+            }
             Expr::Unit { ref name } => self
-                .lookup(name)
-                .map(Value::Number)
+                .lookup_variable(name)
+                .or_else(|| self.lookup_calendar_unit(name))
+                .or_else(|| self.lookup(name).map(Value::Number))
                 .or_else(|| self.substances.get(name).cloned().map(Value::Substance))
                 .or_else(|| {
                     substance_from_formula(name, &self.substance_symbols, &self.substances)
@@ -65,18 +154,48 @@ impl Context {
                         )))
                     }
                 };
-                self.eval(right)
+                self.eval_keeping_dates(right)
             }
 
-            Expr::BinOp(ref binop) => {
+            Expr::BinOp(ref binop) if binop.op == BinOpType::And || binop.op == BinOpType::Or => {
+                // `and` and `or` skip the right side when the left side
+                // already decides the result.
+                let decided = binop.op == BinOpType::Or;
+                let what = binop.op.symbol().trim();
+                if self.eval_condition(&binop.left, what)? == decided {
+                    Ok(Value::Bool(decided))
+                } else {
+                    self.eval_condition(&binop.right, what).map(Value::Bool)
+                }
+            }
+
+            Expr::BinOp(ref binop) if is_comparison(binop.op) => {
                 let left = self.eval(&binop.left)?;
                 let right = self.eval(&binop.right)?;
+                self.eval_comparison(binop.op, &left, &right)
+            }
+
+            Expr::BinOp(ref binop) => {
+                let left = self.eval_operand(&binop.left)?;
+                let right = self.eval_operand(&binop.right)?;
                 let result = match binop.op {
                     BinOpType::Add => left.add(&right),
                     BinOpType::Sub => left.sub(&right),
                     BinOpType::Frac => left.div(&right),
-                    BinOpType::Pow => left.pow(&right),
-                    BinOpType::Equals => panic!("Should be unreachable"),
+                    BinOpType::Pow => left.pow(&right, self.working_precision()),
+                    BinOpType::PlusMinus => {
+                        match (left.clone().forget_dates(), right.clone().forget_dates()) {
+                            (Value::Number(value), Value::Number(error)) => {
+                                Uncertain::new(value, error).map(Value::Uncertain)
+                            }
+                            (_, _) => Err("Uncertainty must be a number".to_string()),
+                        }
+                    }
+                    BinOpType::Mod => left.modulo(&right),
+                    BinOpType::FloorDiv => left.floor_div(&right),
+                    BinOpType::Choose => left.choose(&right),
+                    BinOpType::Permute => left.permute(&right),
+                    _ => panic!("Should be unreachable"),
                 };
                 result.map_err(|e| {
                     QueryError::generic(format!(
@@ -90,50 +209,34 @@ impl Context {
             }
 
             Expr::UnaryOp(ref unaryop) => match unaryop.op {
-                UnaryOpType::Positive => self.eval(&unaryop.expr),
-                UnaryOpType::Negative => self.eval(&unaryop.expr).and_then(|v| {
+                UnaryOpType::Positive => self.eval_operand(&unaryop.expr),
+                UnaryOpType::Negative => self.eval_operand(&unaryop.expr).and_then(|v| {
                     (-&v).map_err(|e| QueryError::generic(format!("{}: - <{}>", e, v.show(self))))
                 }),
-                UnaryOpType::Degree(ref suffix) => {
-                    let (name, base, scale) = suffix.name_base_scale();
-
-                    let expr = self.eval(&unaryop.expr)?;
-                    let expr = match expr {
-                        Value::Number(expr) => expr,
-                        _ => {
-                            return Err(QueryError::generic(format!(
-                                "Expected number, got: <{}> °{}",
-                                expr.show(self),
-                                name
-                            )))
-                        }
-                    };
-                    if expr.unit != BTreeMap::new() {
-                        Err(QueryError::generic(format!(
-                            "Expected dimensionless, got: <{}>",
-                            expr.show(self)
-                        )))
-                    } else {
-                        let expr = (&expr
-                            * &self
-                                .lookup(scale)
-                                .expect(&*format!("Missing {} unit", scale)))
-                            .unwrap();
-                        Ok(Value::Number(
-                            (&expr
-                                + &self
-                                    .lookup(base)
-                                    .expect(&*format!("Missing {} constant", base)))
-                                .unwrap(),
-                        ))
-                    }
-                }
+                UnaryOpType::Not => self
+                    .eval_condition(&unaryop.expr, "not")
+                    .map(|cond| Value::Bool(!cond)),
+                UnaryOpType::Factorial => self.eval(&unaryop.expr).and_then(|v| {
+                    v.factorial()
+                        .map_err(|e| QueryError::generic(format!("{}: <{}>!", e, v.show(self))))
+                }),
             },
 
             Expr::Mul { ref exprs } => {
-                exprs.iter().fold(Ok(Value::Number(Number::one())), |a, b| {
-                    a.and_then(|a| {
-                        let b = self.eval(b)?;
+                exprs
+                    .iter()
+                    .enumerate()
+                    .try_fold(Value::Number(Number::one()), |a, (i, b)| {
+                        // Nonlinear units apply to everything before them,
+                        // as in `20 °C`.
+                        if let Expr::Unit { ref name } = *b {
+                            if let Some(scale) = self.lookup_scale(name).filter(|_| i > 0) {
+                                return scale
+                                    .value_to_linear(self, &a)
+                                    .map_err(QueryError::generic);
+                            }
+                        }
+                        let b = self.eval_operand(b)?;
                         (&a * &b).map_err(|e| {
                             QueryError::generic(format!(
                                 "{}: <{}> * <{}>",
@@ -143,7 +246,6 @@ impl Context {
                             ))
                         })
                     })
-                })
             }
             Expr::Of {
                 ref property,
@@ -152,6 +254,19 @@ impl Context {
                 let expr = self.eval(expr)?;
                 let expr = match expr {
                     Value::Substance(sub) => sub,
+                    // Dimensionless units scale the value, as in
+                    // `percent of 80 kg`.
+                    x if self.lookup(property).is_some_and(|unit| unit.dimless()) => {
+                        let unit = Value::Number(self.lookup(property).unwrap());
+                        return (&unit * &x).map_err(|e| {
+                            QueryError::generic(format!(
+                                "{}: {} of <{}>",
+                                e,
+                                property,
+                                x.show(self)
+                            ))
+                        });
+                    }
                     x => {
                         return Err(QueryError::generic(format!(
                             "Not defined: {} of <{}>",
@@ -167,233 +282,927 @@ impl Context {
                     }
                 })
             }
+            Expr::Call {
+                func: Function::Custom(ref name),
+                ref args,
+            } => self.eval_custom_call(name, args),
+            Expr::Call {
+                func: Function::If,
+                ref args,
+            } => self.eval_if(args),
             Expr::Call { ref func, ref args } => {
                 let args = args
                     .iter()
                     .map(|x| self.eval(x))
                     .collect::<Result<Vec<_>, _>>()?;
 
-                macro_rules! func {
-                    (fn $fname:ident($($name:ident : $ty:ident),*) $block:block) => {{
-                        let mut iter = args.iter();
-                        let mut count = 0;
-                        $( count += 1; let _ = stringify!($name); )*
-                        $(
-                            let $name = match iter.next() {
-                                Some(&Value::$ty(ref v)) => v,
-                                Some(x) => return Err(QueryError::generic(
-                                    format!(
-                                        "Expected {}, got <{}>",
-                                        stringify!($ty), x.show(self)
-                                    )
-                                )),
-                                None => return Err(QueryError::generic(format!(
-                                    "Argument number mismatch for {}: \
-                                     Expected {}, got {}",
-                                    stringify!($fname), count, args.len()
-                                )))
-                            };
-                        )*
-                        if iter.next().is_some() {
-                            return Err(QueryError::generic(format!(
-                                "Argument number mismatch for {}: \
-                                 Expected {}, got {}",
-                                stringify!($fname), count, args.len()
-                            )));
-                        }
-                        let res: Result<Value, String> = {
-                            $block
-                        };
-                        res.map_err(|e| {
-                            QueryError::generic(format!(
-                                "{}: {}({})",
-                                e, stringify!($fname),
-                                args.iter()
-                                    .map(|x| x.show(self))
-                                    .collect::<Vec<_>>()
-                                    .join(", ")
-                            ))
-                        })
+                let uncertain = args.iter().any(|arg| match *arg {
+                    Value::Uncertain(_) => true,
+                    _ => false,
+                });
+                let interval = args.iter().any(|arg| match *arg {
+                    Value::Interval(_) => true,
+                    _ => false,
+                });
+                let list = args.iter().any(|arg| match *arg {
+                    Value::List(_) => true,
+                    _ => false,
+                });
+                if list && !is_aggregate(func) {
+                    self.eval_list_call(func, &args)
+                } else if interval {
+                    self.eval_interval_call(func, &args)
+                } else if uncertain {
+                    self.eval_uncertain_call(func, &args)
+                } else {
+                    self.eval_builtin(func, &args)
+                }
+            }
+            Expr::Interval {
+                ref lower,
+                ref upper,
+            } => match (self.eval(lower)?, self.eval(upper)?) {
+                (Value::Number(lower), Value::Number(upper)) => Interval::new(lower, upper)
+                    .map(Value::Interval)
+                    .map_err(QueryError::generic),
+                (lower, upper) => Err(QueryError::generic(format!(
+                    "Interval bounds must be numbers, got: [<{}>, <{}>]",
+                    lower.show(self),
+                    upper.show(self)
+                ))),
+            },
+            Expr::List { ref items } => items
+                .iter()
+                .map(|item| match self.eval(item)? {
+                    Value::Number(num) => Ok(num),
+                    x => Err(QueryError::generic(format!(
+                        "List elements must be numbers, got: <{}>",
+                        x.show(self)
+                    ))),
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::List),
+            Expr::Error { ref message } => Err(QueryError::generic(message.clone())),
+        }
+    }
+
+    /// Evaluates an operand of arithmetic. Calendar months are kept,
+    /// as in `now + 1 month * 2`, but the dates of a span aren't.
+    fn eval_operand(&self, expr: &Expr) -> Result<Value, QueryError> {
+        match self.eval_keeping_dates(expr)? {
+            Value::DateSpan(span) => Ok(Value::Number(span.seconds)),
+            value => Ok(value),
+        }
+    }
+
+    /// Units like `month` and `year` count calendar months, so that
+    /// adding them to a date goes by the calendar rather than by their
+    /// length in seconds.
+    fn lookup_calendar_unit(&self, name: &str) -> Option<Value> {
+        let &(_, months) = CALENDAR_UNITS.iter().find(|&&(unit, _)| unit == name)?;
+        let seconds = self.lookup(name)?;
+        Some(Value::CalendarDuration(date::CalendarDuration::unit(
+            months, seconds,
+        )))
+    }
+
+    /// Writes a sum like `P1Y2M3DT4H` as an ISO 8601 duration with its
+    /// calendar months kept apart from the rest, so that it reads back
+    /// the same. A sum whose parts have different signs has no such
+    /// form, and is an error rather than being folded into seconds.
+    fn iso8601_calendar_duration(
+        &self,
+        duration: &date::CalendarDuration,
+    ) -> Result<String, QueryError> {
+        let (months, rest) = (duration.months, &duration.rest);
+        let negative = months < 0 || rest.value < Numeric::zero();
+        if negative && (months > 0 || rest.value > Numeric::zero()) {
+            return Err(QueryError::generic(format!(
+                "Cannot convert <{}> to iso8601 duration, as its calendar months \
+                 and the rest have different signs",
+                duration.seconds.show(self)
+            )));
+        }
+        let (days, rest) = rest.value.abs().div_rem(&Numeric::from(86400));
+        let days = days
+            .to_int()
+            .ok_or_else(|| QueryError::generic("Duration is too long".to_owned()))?;
+        let months = months.abs();
+        Ok(date::iso8601_duration(
+            negative,
+            months / 12,
+            months % 12,
+            days,
+            &rest,
+        ))
+    }
+
+    fn eval_builtin(&self, func: &Function, args: &[Value]) -> Result<Value, QueryError> {
+        macro_rules! func {
+            (fn $fname:ident($($name:ident : $ty:ident),*) $block:block) => {{
+                let mut iter = args.iter();
+                let mut count = 0;
+                $( count += 1; let _ = stringify!($name); )*
+                $(
+                    let $name = match iter.next() {
+                        Some(&Value::$ty(ref v)) => v,
+                        Some(x) => return Err(QueryError::generic(
+                            format!(
+                                "Expected {}, got <{}>",
+                                stringify!($ty), x.show(self)
+                            )
+                        )),
+                        None => return Err(QueryError::generic(format!(
+                            "Argument number mismatch for {}: \
+                             Expected {}, got {}",
+                            stringify!($fname), count, args.len()
+                        )))
+                    };
+                )*
+                if iter.next().is_some() {
+                    return Err(QueryError::generic(format!(
+                        "Argument number mismatch for {}: \
+                         Expected {}, got {}",
+                        stringify!($fname), count, args.len()
+                    )));
+                }
+                let res: Result<Value, String> = {
+                    $block
+                };
+                res.map_err(|e| {
+                    QueryError::generic(format!(
+                        "{}: {}({})",
+                        e, stringify!($fname),
+                        args.iter()
+                            .map(|x| x.show(self))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ))
+                })
+            }
+        }}
+
+        let complex = args.iter().any(|arg| match *arg {
+            Value::Number(ref num) => num.value.is_complex(),
+            _ => false,
+        });
+        if complex {
+            match *func {
+                Function::Sqrt
+                | Function::Exp
+                | Function::Ln
+                | Function::Sum
+                | Function::Mean
+                | Function::Norm
+                | Function::Dot
+                | Function::Cross
+                | Function::Re
+                | Function::Im
+                | Function::Abs
+                | Function::Arg
+                | Function::Conj => (),
+                _ => {
+                    return Err(QueryError::generic(format!(
+                        "{} is not defined for complex numbers",
+                        func.name()
+                    )))
+                }
+            }
+        }
+
+        let precision = self.working_precision();
+        match func {
+            Function::Sqrt => func!(
+                fn sqrt(num: Number) {
+                    num.root(2, precision).map(Value::Number)
+                }
+            ),
+            Function::Exp => func!(
+                fn exp(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.exp(precision),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Ln => func!(
+                fn ln(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.ln(precision),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Log => func!(
+                fn log(num: Number, base: Number) {
+                    if !base.unit.is_empty() {
+                        Err("Base must be dimensionless".to_string())
+                    } else {
+                        Ok(Value::Number(Number {
+                            value: num.value.real_func2(
+                                &base.value,
+                                precision,
+                                BigFloat::log,
+                                f64::log,
+                            ),
+                            unit: num.unit.clone(),
+                        }))
+                    }
+                }
+            ),
+            Function::Log2 => func!(
+                fn log2(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(
+                            precision,
+                            |x, p| x.log(&BigFloat::from_int(2, p), p),
+                            f64::log2,
+                        ),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Log10 => func!(
+                fn ln(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(
+                            precision,
+                            |x, p| x.log(&BigFloat::from_int(10, p), p),
+                            f64::log10,
+                        ),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Hypot => func!(
+                fn hypot(x: Number, y: Number) {
+                    if x.unit != y.unit {
+                        Err("Arguments to hypot must have matching dimensionality".to_string())
+                    } else {
+                        Ok(Value::Number(Number {
+                            value: x.value.real_func2(
+                                &y.value,
+                                precision,
+                                |x, y, p| Some(x.hypot(y, p)),
+                                f64::hypot,
+                            ),
+                            unit: x.unit.clone(),
+                        }))
+                    }
+                }
+            ),
+            Function::Sin => func!(
+                fn sin(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(precision, BigFloat::sin, f64::sin),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Cos => func!(
+                fn cos(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(precision, BigFloat::cos, f64::cos),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Tan => func!(
+                fn tan(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(precision, BigFloat::tan, f64::tan),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Asin => func!(
+                fn asin(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(precision, BigFloat::asin, f64::asin),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Acos => func!(
+                fn acos(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(precision, BigFloat::acos, f64::acos),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Atan => func!(
+                fn atan(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(precision, BigFloat::atan, f64::atan),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Atan2 => func!(
+                fn atan2(x: Number, y: Number) {
+                    if x.unit != y.unit {
+                        Err("Arguments to atan2 must have matching dimensionality".to_string())
+                    } else {
+                        Ok(Value::Number(Number {
+                            value: x.value.real_func2(
+                                &y.value,
+                                precision,
+                                BigFloat::atan2,
+                                f64::atan2,
+                            ),
+                            unit: x.unit.clone(),
+                        }))
+                    }
+                }
+            ),
+            Function::Sinh => func!(
+                fn sinh(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(precision, BigFloat::sinh, f64::sinh),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Cosh => func!(
+                fn cosh(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(precision, BigFloat::cosh, f64::cosh),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Tanh => func!(
+                fn tanh(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(precision, BigFloat::tanh, f64::tanh),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Asinh => func!(
+                fn asinh(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(precision, BigFloat::asinh, f64::asinh),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Acosh => func!(
+                fn acosh(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(precision, BigFloat::acosh, f64::acosh),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Atanh => func!(
+                fn atanh(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.real_func(precision, BigFloat::atanh, f64::atanh),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Re => func!(
+                fn re(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.re(),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Im => func!(
+                fn im(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.im(),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Abs => func!(
+                fn abs(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.abs(),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Arg => func!(
+                fn arg(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.arg(),
+                        unit: BTreeMap::new(),
+                    }))
+                }
+            ),
+            Function::Conj => func!(
+                fn conj(num: Number) {
+                    Ok(Value::Number(Number {
+                        value: num.value.conj(),
+                        unit: num.unit.clone(),
+                    }))
+                }
+            ),
+            Function::Sum
+            | Function::Mean
+            | Function::Min
+            | Function::Max
+            | Function::Norm
+            | Function::Dot
+            | Function::Cross => self.eval_aggregate(func, args),
+            Function::Round | Function::Floor | Function::Ceil | Function::Trunc => {
+                self.eval_rounding(func, args)
+            }
+            Function::Gcd => func!(
+                fn gcd(left: Number, right: Number) {
+                    left.gcd(right).map(Value::Number)
+                }
+            ),
+            Function::Lcm => func!(
+                fn lcm(left: Number, right: Number) {
+                    left.lcm(right).map(Value::Number)
+                }
+            ),
+            Function::Mod => func!(
+                fn mod(left: Number, right: Number) {
+                    left.modulo(right).map(Value::Number)
+                }
+            ),
+            Function::If | Function::Custom(_) => unreachable!(),
+        }
+    }
+
+    /// Evaluates `if(cond, a, b)`. Only the branch that is taken gets
+    /// evaluated.
+    fn eval_if(&self, args: &[Expr]) -> Result<Value, QueryError> {
+        match *args {
+            [ref cond, ref yes, ref no] => {
+                if self.eval_condition(cond, "if")? {
+                    self.eval(yes)
+                } else {
+                    self.eval(no)
+                }
+            }
+            _ => Err(QueryError::generic(format!(
+                "Argument number mismatch for if: Expected 3, got {}",
+                args.len()
+            ))),
+        }
+    }
+
+    /// Evaluates an operand of `and`, `or`, `not` or `if`, which must
+    /// be a boolean.
+    fn eval_condition(&self, expr: &Expr, what: &str) -> Result<bool, QueryError> {
+        match self.eval(expr)? {
+            Value::Bool(cond) => Ok(cond),
+            x => Err(QueryError::generic(format!(
+                "Expected Bool for `{}`, got <{}>",
+                what,
+                x.show(self)
+            ))),
+        }
+    }
+
+    /// Compares two quantities with the same dimensions, two dates, or
+    /// two booleans (for `==` and `!=` only).
+    fn eval_comparison(
+        &self,
+        op: BinOpType,
+        left: &Value,
+        right: &Value,
+    ) -> Result<Value, QueryError> {
+        let err = |e: &str| {
+            QueryError::generic(format!(
+                "{}: <{}> {} <{}>",
+                e,
+                left.show(self),
+                op.symbol().trim(),
+                right.show(self)
+            ))
+        };
+        let equality = op == BinOpType::Equal || op == BinOpType::NotEqual;
+        let ordering = match (left, right) {
+            (Value::Number(left), Value::Number(right)) => {
+                if left.unit != right.unit {
+                    return Err(QueryError::Conformance(Box::new(
+                        self.conformance_err(left, right),
+                    )));
+                }
+                match left.value.partial_cmp(&right.value) {
+                    None if left.value == right.value => Some(Ordering::Equal),
+                    ordering => ordering,
+                }
+            }
+            (Value::DateTime(left), Value::DateTime(right)) => {
+                Some(left.with_timezone(&Utc).cmp(&right.with_timezone(&Utc)))
+            }
+            (Value::Bool(left), Value::Bool(right)) if equality => Some(left.cmp(right)),
+            _ => return Err(err("Operation is not defined")),
+        };
+        let result = match (op, ordering) {
+            (BinOpType::Equal, ordering) => ordering == Some(Ordering::Equal),
+            (BinOpType::NotEqual, ordering) => ordering != Some(Ordering::Equal),
+            (_, None) => return Err(err("Complex numbers can't be compared")),
+            (BinOpType::Less, Some(ordering)) => ordering == Ordering::Less,
+            (BinOpType::LessEqual, Some(ordering)) => ordering != Ordering::Greater,
+            (BinOpType::Greater, Some(ordering)) => ordering == Ordering::Greater,
+            (BinOpType::GreaterEqual, Some(ordering)) => ordering != Ordering::Less,
+            _ => unreachable!(),
+        };
+        Ok(Value::Bool(result))
+    }
+
+    /// Evaluates a function which combines the elements of a list, like
+    /// `sum`. Functions of one list also accept several numbers instead.
+    fn eval_aggregate(&self, func: &Function, args: &[Value]) -> Result<Value, QueryError> {
+        let err = |e: String| {
+            QueryError::generic(format!(
+                "{}: {}({})",
+                e,
+                func.name(),
+                args.iter()
+                    .map(|x| x.show(self))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        };
+        if let Function::Dot | Function::Cross = *func {
+            let (left, right) = match *args {
+                [Value::List(ref left), Value::List(ref right)] => (left, right),
+                _ => return Err(err("Expected two lists".to_string())),
+            };
+            return if let Function::Dot = *func {
+                list::dot(left, right).map(Value::Number).map_err(err)
+            } else {
+                list::cross(left, right).map(Value::List).map_err(err)
+            };
+        }
+        let items = match *args {
+            [Value::List(ref list)] => list.clone(),
+            _ => args
+                .iter()
+                .map(|arg| match *arg {
+                    Value::Number(ref num) => Ok(num.clone()),
+                    ref x => Err(err(format!(
+                        "Expected Number or List, got <{}>",
+                        x.show(self)
+                    ))),
+                })
+                .collect::<Result<Vec<_>, _>>()?,
+        };
+        match *func {
+            Function::Sum => list::sum(&items),
+            Function::Mean => list::mean(&items),
+            Function::Min => list::extreme(&items, Ordering::Less),
+            Function::Max => list::extreme(&items, Ordering::Greater),
+            Function::Norm => list::norm(&items, self.working_precision()),
+            _ => unreachable!(),
+        }
+        .map(Value::Number)
+        .map_err(err)
+    }
+
+    /// Evaluates `round`, `floor`, `ceil` or `trunc`, which take an
+    /// optional step to round to a multiple of, like `round(3.14159 m,
+    /// 1 cm)`.
+    fn eval_rounding(&self, func: &Function, args: &[Value]) -> Result<Value, QueryError> {
+        let err = |e: String| {
+            QueryError::generic(format!(
+                "{}: {}({})",
+                e,
+                func.name(),
+                args.iter()
+                    .map(|x| x.show(self))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ))
+        };
+        let mode = match *func {
+            Function::Round => Rounding::Nearest,
+            Function::Floor => Rounding::Floor,
+            Function::Ceil => Rounding::Ceil,
+            Function::Trunc => Rounding::Trunc,
+            _ => unreachable!(),
+        };
+        match *args {
+            [Value::Number(ref num)] => num.round(None, mode),
+            [Value::Number(ref num), Value::Number(ref step)] => num.round(Some(step), mode),
+            _ => Err("Expected a number and an optional step".to_string()),
+        }
+        .map(Value::Number)
+        .map_err(err)
+    }
+
+    /// Applies a built-in function to each element of its list
+    /// arguments. Plain numbers are passed to every call.
+    fn eval_list_call(&self, func: &Function, args: &[Value]) -> Result<Value, QueryError> {
+        let mut len = None;
+        for arg in args {
+            if let Value::List(ref list) = *arg {
+                match len {
+                    Some(len) if len != list.len() => {
+                        return Err(QueryError::generic(format!(
+                            "Lists must have the same length, got {} and {}",
+                            len,
+                            list.len()
+                        )))
                     }
-                }}
+                    _ => len = Some(list.len()),
+                }
+            }
+        }
+        (0..len.unwrap_or(0))
+            .map(|i| {
+                let args = args
+                    .iter()
+                    .map(|arg| match *arg {
+                        Value::List(ref list) => Value::Number(list[i].clone()),
+                        ref x => x.clone(),
+                    })
+                    .collect::<Vec<_>>();
+                match self.eval_builtin(func, &args)? {
+                    Value::Number(num) => Ok(num),
+                    x => Err(QueryError::generic(format!(
+                        "Expected Number, got <{}>",
+                        x.show(self)
+                    ))),
+                }
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(Value::List)
+    }
 
-                match func {
-                    Function::Sqrt => func!(
-                        fn sqrt(num: Number) {
-                            num.root(2).map(Value::Number)
-                        }
-                    ),
-                    Function::Exp => func!(
-                        fn exp(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().exp()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Ln => func!(
-                        fn ln(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().ln()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Log => func!(
-                        fn log(num: Number, base: Number) {
-                            if !base.unit.is_empty() {
-                                Err("Base must be dimensionless".to_string())
-                            } else {
-                                Ok(Value::Number(Number {
-                                    value: Numeric::Float(
-                                        num.value.to_f64().log(base.value.to_f64()),
-                                    ),
-                                    unit: num.unit.clone(),
-                                }))
-                            }
-                        }
-                    ),
-                    Function::Log2 => func!(
-                        fn log2(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().log2()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Log10 => func!(
-                        fn ln(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().log10()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Hypot => func!(
-                        fn hypot(x: Number, y: Number) {
-                            if x.unit != y.unit {
-                                Err("Arguments to hypot must have matching dimensionality"
-                                    .to_string())
-                            } else {
-                                Ok(Value::Number(Number {
-                                    value: Numeric::Float(x.value.to_f64().hypot(y.value.to_f64())),
-                                    unit: x.unit.clone(),
-                                }))
-                            }
-                        }
-                    ),
-                    Function::Sin => func!(
-                        fn sin(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().sin()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Cos => func!(
-                        fn cos(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().cos()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Tan => func!(
-                        fn tan(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().tan()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Asin => func!(
-                        fn asin(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().asin()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Acos => func!(
-                        fn acos(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().acos()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Atan => func!(
-                        fn atan(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().atan()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Atan2 => func!(
-                        fn atan2(x: Number, y: Number) {
-                            if x.unit != y.unit {
-                                Err("Arguments to atan2 must have matching dimensionality"
-                                    .to_string())
-                            } else {
-                                Ok(Value::Number(Number {
-                                    value: Numeric::Float(x.value.to_f64().atan2(y.value.to_f64())),
-                                    unit: x.unit.clone(),
-                                }))
-                            }
-                        }
-                    ),
-                    Function::Sinh => func!(
-                        fn sinh(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().sinh()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Cosh => func!(
-                        fn cosh(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().cosh()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Tanh => func!(
-                        fn tanh(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().tanh()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Asinh => func!(
-                        fn asinh(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().asinh()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Acosh => func!(
-                        fn acosh(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().acosh()),
-                                unit: num.unit.clone(),
-                            }))
-                        }
-                    ),
-                    Function::Atanh => func!(
-                        fn atanh(num: Number) {
-                            Ok(Value::Number(Number {
-                                value: Numeric::Float(num.value.to_f64().atanh()),
-                                unit: num.unit.clone(),
-                            }))
+    /// Evaluates a monotonic built-in function at both ends of an
+    /// interval.
+    fn eval_interval_call(&self, func: &Function, args: &[Value]) -> Result<Value, QueryError> {
+        if is_aggregate(func) {
+            return Err(QueryError::generic(format!(
+                "{} takes a list, but `[a, b]` is an interval. \
+                 Use a trailing comma for a list of two elements, as in `[a, b,]`",
+                func.name()
+            )));
+        }
+        let arg = match *args {
+            [Value::Interval(ref arg)] => arg,
+            _ => {
+                return Err(QueryError::generic(format!(
+                    "{} is not defined for intervals",
+                    func.name()
+                )))
+            }
+        };
+        // Negative values would give complex bounds, which aren't an
+        // interval, so these fail the same way as `[-2, 3]^0.5`.
+        let negative = match *func {
+            Function::Sqrt => Some("Fractional powers"),
+            Function::Ln | Function::Log2 | Function::Log10 => Some("Logarithms"),
+            _ => None,
+        };
+        if let Some(what) = negative.filter(|_| arg.lower.value < Numeric::zero()) {
+            return Err(QueryError::generic(format!(
+                "{} of intervals with negative values are undefined: {}({})",
+                what,
+                func.name(),
+                arg.show(self)
+            )));
+        }
+        if !interval::in_domain(func, &arg.lower.value)
+            || !interval::in_domain(func, &arg.upper.value)
+        {
+            return Err(QueryError::generic(format!(
+                "{} is not defined for all of <{}>",
+                func.name(),
+                arg.show(self)
+            )));
+        }
+        let at = |num: &Number| match self.eval_builtin(func, &[Value::Number(num.clone())])? {
+            Value::Number(mut result) => {
+                // Kept exact, so that rounding outwards doesn't widen
+                // the interval past values like `ln(1) = 0`.
+                if let Some(exact) = interval::exact_at(func, &num.value) {
+                    result.value = exact;
+                }
+                Ok(result)
+            }
+            x => Err(QueryError::generic(format!(
+                "Expected number from {}, got <{}>",
+                func.name(),
+                x.show(self)
+            ))),
+        };
+        let lower = at(&arg.lower)?;
+        let upper = at(&arg.upper)?;
+        match (func, interval::monotonicity(func)) {
+            // cosh has its minimum at zero.
+            (&Function::Cosh, _) if arg.contains_zero() => {
+                let zero = at(&Number {
+                    value: Numeric::zero(),
+                    unit: arg.lower.unit.clone(),
+                })?;
+                let top = if lower.value > upper.value {
+                    lower
+                } else {
+                    upper
+                };
+                Interval::from_endpoints(zero, top)
+                    .map(Value::Interval)
+                    .map_err(QueryError::generic)
+            }
+            (&Function::Cosh, _) | (_, Some(_)) => Interval::from_endpoints(lower, upper)
+                .map(Value::Interval)
+                .map_err(QueryError::generic),
+            (_, None) => Err(QueryError::generic(format!(
+                "{} is not monotonic, so it is not defined for intervals",
+                func.name()
+            ))),
+        }
+    }
+
+    /// Evaluates a built-in function at the central values of its
+    /// arguments, then propagates their uncertainties to first order.
+    fn eval_uncertain_call(&self, func: &Function, args: &[Value]) -> Result<Value, QueryError> {
+        let centers = args
+            .iter()
+            .map(|arg| match *arg {
+                Value::Uncertain(ref u) => Value::Number(u.value.clone()),
+                ref x => x.clone(),
+            })
+            .collect::<Vec<_>>();
+        let value = match self.eval_builtin(func, &centers)? {
+            Value::Number(num) => num,
+            x => {
+                return Err(QueryError::generic(format!(
+                    "Expected number from {}, got <{}>",
+                    func.name(),
+                    x.show(self)
+                )))
+            }
+        };
+        let points = centers
+            .iter()
+            .map(|arg| match *arg {
+                Value::Number(ref num) => num.value.to_f64(),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        let derivatives = uncertain::derivatives(func, &points).ok_or_else(|| {
+            QueryError::generic(format!(
+                "Uncertainty propagation is not defined for {}",
+                func.name()
+            ))
+        })?;
+        let mut error = 0.0f64;
+        for (arg, derivative) in args.iter().zip(derivatives) {
+            if let Value::Uncertain(ref u) = *arg {
+                error = error.hypot(derivative * u.error.to_f64());
+            }
+        }
+        if !error.is_finite() {
+            return Err(QueryError::generic(format!(
+                "Uncertainty is unbounded: {}({})",
+                func.name(),
+                args.iter()
+                    .map(|x| x.show(self))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            )));
+        }
+        Ok(Value::Uncertain(Uncertain {
+            value,
+            error: Numeric::Float(error),
+        }))
+    }
+
+    /// Looks up the arguments of the function currently being called,
+    /// then session variables.
+    fn lookup_variable(&self, name: &str) -> Option<Value> {
+        if let Some(frame) = self.locals.borrow().last() {
+            if let Some(value) = frame.get(name) {
+                return Some(value.clone());
+            }
+        }
+        self.variables.get(name).cloned()
+    }
+
+    /// Finds the nonlinear unit with this name, like `dBm`. Variables
+    /// shadow these the same way they shadow units.
+    fn lookup_scale(&self, name: &str) -> Option<&Scale> {
+        if self.lookup_variable(name).is_some() {
+            return None;
+        }
+        self.scales.get(name)
+    }
+
+    /// Finds the dimensions of a quantity like `velocity`, for checking
+    /// the arguments of user-defined functions. Units are accepted too.
+    fn quantity_unit(&self, name: &str) -> Result<Number, QueryError> {
+        self.quantities
+            .iter()
+            .find(|&(_, quantity)| quantity == name)
+            .map(|(unit, _)| Number {
+                value: Numeric::one(),
+                unit: unit.clone(),
+            })
+            .or_else(|| self.lookup(name))
+            .ok_or_else(|| QueryError::NotFound(self.unknown_unit_err(name)))
+    }
+
+    fn eval_custom_call(&self, name: &str, args: &[Expr]) -> Result<Value, QueryError> {
+        // Nonlinear units can be called like functions, as in GNU
+        // units: `tempF(98.6)`.
+        if let (Some(scale), [arg]) = (self.lookup_scale(name), args) {
+            let reading = self.eval(arg)?;
+            return scale
+                .value_to_linear(self, &reading)
+                .map_err(QueryError::generic);
+        }
+        let func = match self.functions.get(name) {
+            Some(func) => func,
+            // Not a function, so this is juxtaposition like `kg(m/s)`.
+            None if args.len() == 1 => {
+                return self.eval(&Expr::new_mul(vec![
+                    Expr::new_unit(name.to_owned()),
+                    args[0].clone(),
+                ]))
+            }
+            None => return Err(QueryError::generic(format!("No such function {}", name))),
+        };
+        if args.len() != func.params.len() {
+            return Err(QueryError::generic(format!(
+                "Argument number mismatch for {}: Expected {}, got {}",
+                name,
+                func.params.len(),
+                args.len()
+            )));
+        }
+        let mut frame = BTreeMap::new();
+        for (param, arg) in func.params.iter().zip(args) {
+            let value = self.eval(arg)?;
+            if let Some(ref quantity) = param.quantity {
+                let expected = self.quantity_unit(quantity)?;
+                match value {
+                    Value::Number(ref num)
+                    | Value::Uncertain(Uncertain { value: ref num, .. })
+                    | Value::Interval(Interval { lower: ref num, .. })
+                        if num.unit == expected.unit => {}
+                    Value::Number(ref num)
+                    | Value::Uncertain(Uncertain { value: ref num, .. })
+                    | Value::Interval(Interval { lower: ref num, .. }) => {
+                        return Err(QueryError::Conformance(Box::new(
+                            self.conformance_err(num, &expected),
+                        )))
+                    }
+                    Value::List(ref list) => {
+                        if let Some(num) = list.iter().find(|num| num.unit != expected.unit) {
+                            return Err(QueryError::Conformance(Box::new(
+                                self.conformance_err(num, &expected),
+                            )));
                         }
-                    ),
+                    }
+                    ref x => {
+                        return Err(QueryError::generic(format!(
+                            "Expected {} for {} in {}, got <{}>",
+                            quantity,
+                            param.name,
+                            func.signature(name),
+                            x.show(self)
+                        )))
+                    }
                 }
             }
-            Expr::Error { ref message } => Err(QueryError::generic(message.clone())),
+            frame.insert(param.name.clone(), value);
+        }
+        self.eval_in_frame(name, frame, &func.body)
+    }
+
+    /// Evaluates the body of `name` with its arguments bound as local
+    /// variables.
+    pub(crate) fn eval_in_frame(
+        &self,
+        name: &str,
+        frame: BTreeMap<String, Value>,
+        body: &Expr,
+    ) -> Result<Value, QueryError> {
+        if self.locals.borrow().len() >= MAX_CALL_DEPTH {
+            return Err(QueryError::generic(format!(
+                "Recursion limit reached while calling {}",
+                name
+            )));
+        }
+        self.locals.borrow_mut().push(frame);
+        let res = self.eval(body);
+        self.locals.borrow_mut().pop();
+        res
+    }
+
+    fn function_def_reply(&self, name: &str, func: &FunctionDef) -> DefReply {
+        DefReply {
+            canon_name: func.signature(name),
+            def: Some(func.body.to_string()),
+            def_expr: Some(ExprReply::from(&func.body)),
+            value: None,
+            doc: self.docs.get(name).cloned(),
         }
     }
 
@@ -414,6 +1223,12 @@ impl Context {
                 Ok((map, Numeric::one()))
             }
             Expr::Const { ref value } => Ok((BTreeMap::new(), value.clone())),
+            Expr::Interval { .. } => Err(QueryError::generic(
+                "Intervals are not allowed in the right hand side of conversions".to_string(),
+            )),
+            Expr::List { .. } => Err(QueryError::generic(
+                "Lists are not allowed in the right hand side of conversions".to_string(),
+            )),
             Expr::BinOp(ref binop) => match binop.op {
                 BinOpType::Equals => match *binop.left {
                     Expr::Unit { ref name } => {
@@ -439,6 +1254,25 @@ impl Context {
                     }
                     Ok((left_unit, left))
                 }
+                BinOpType::PlusMinus => Err(QueryError::generic(
+                    "Uncertainties are not allowed in the right hand side of conversions"
+                        .to_string(),
+                )),
+                BinOpType::Mod
+                | BinOpType::FloorDiv
+                | BinOpType::Choose
+                | BinOpType::Permute
+                | BinOpType::Less
+                | BinOpType::LessEqual
+                | BinOpType::Greater
+                | BinOpType::GreaterEqual
+                | BinOpType::Equal
+                | BinOpType::NotEqual
+                | BinOpType::And
+                | BinOpType::Or => Err(QueryError::generic(format!(
+                    "`{}` is not allowed in the right hand side of conversions",
+                    binop.op.symbol().trim()
+                ))),
                 BinOpType::Frac => {
                     let (left_unit, left) = self.eval_unit_name(&binop.left)?;
                     let (right_unit, right) = self.eval_unit_name(&binop.right)?;
@@ -542,8 +1376,11 @@ impl Context {
             Expr::UnaryOp(ref unaryop) => match unaryop.op {
                 UnaryOpType::Positive => self.eval_unit_name(&unaryop.expr),
                 UnaryOpType::Negative => self.eval_unit_name(&unaryop.expr).map(|(u, v)| (u, -&v)),
-                UnaryOpType::Degree(_) => Err(QueryError::generic(
-                    "Temperature conversions must not be compound units".to_string(),
+                UnaryOpType::Factorial => Err(QueryError::generic(
+                    "Factorials are not allowed in the right hand side of conversions".to_string(),
+                )),
+                UnaryOpType::Not => Err(QueryError::generic(
+                    "`not` is not allowed in the right hand side of conversions".to_string(),
                 )),
             },
             Expr::Date { .. } => Err(QueryError::generic(
@@ -618,12 +1455,395 @@ impl Context {
                 },
                 unit: Some(Number::unit_to_string(&bottom_name)),
                 raw_unit: Some(bottom_name),
+                rounding_error: match digits {
+                    Digits::Fraction(denom) => raw
+                        .value
+                        .rounding_error_string(&raw.value.round_to_fraction(denom), base),
+                    _ => None,
+                },
                 ..bottom.to_parts(self)
             },
         }
     }
 
-    fn to_list(&self, top: &Number, list: &[&str]) -> Result<Vec<NumberParts>, QueryError> {
+    fn convert_number(
+        &self,
+        top: &Number,
+        bottom: &Number,
+        bottom_name: BTreeMap<String, isize>,
+        bottom_const: Numeric,
+        base: u8,
+        digits: Digits,
+    ) -> Result<ConversionReply, QueryError> {
+        if let Digits::Fraction(_) = digits {
+            self.require_finite_fraction(top, "a fraction")?;
+        }
+        if top.unit != bottom.unit {
+            return Err(QueryError::Conformance(Box::new(
+                self.conformance_err(top, bottom),
+            )));
+        }
+        let raw = match top / bottom {
+            Some(raw) => raw,
+            None => {
+                return Err(QueryError::generic(format!(
+                    "Division by zero: {} / {}",
+                    top.show(self),
+                    bottom.show(self)
+                )))
+            }
+        };
+        Ok(self.show(&raw, bottom, bottom_name, bottom_const, base, digits))
+    }
+
+    /// Infinities and NaN have no closest fraction, so they're rejected
+    /// before any rounding to one is attempted.
+    fn require_finite_fraction(&self, top: &Number, what: &str) -> Result<(), QueryError> {
+        if top.value.is_finite() {
+            Ok(())
+        } else {
+            Err(QueryError::generic(format!(
+                "Cannot write <{}> as {}, as it is not finite",
+                top.show(self),
+                what
+            )))
+        }
+    }
+
+    /// Fractions of complex numbers would silently drop the imaginary
+    /// part, so they're rejected instead.
+    fn require_real_fraction(&self, top: &Number, what: &str) -> Result<(), QueryError> {
+        if top.value.is_complex() {
+            Err(QueryError::generic(format!(
+                "Cannot write <{}> as {}, as it is not a real number",
+                top.show(self),
+                what
+            )))
+        } else {
+            self.require_finite_fraction(top, what)
+        }
+    }
+
+    /// Shows a number in engineering notation, in the units of `bottom`
+    /// or the ones it would normally be shown in.
+    fn convert_eng(
+        &self,
+        top: &Number,
+        bottom: Option<&Expr>,
+        digits: Digits,
+    ) -> Result<ConversionReply, QueryError> {
+        if !top.value.is_finite() {
+            return Err(QueryError::generic(format!(
+                "Cannot write <{}> in engineering notation, as it is not finite",
+                top.show(self)
+            )));
+        }
+        let (bottom, bottom_name, bottom_const) = match bottom {
+            Some(expr) => match self.eval(expr)? {
+                Value::Number(bottom) => {
+                    let (bottom_name, bottom_const) = self.eval_unit_name(expr)?;
+                    (bottom, bottom_name, bottom_const)
+                }
+                x => {
+                    return Err(QueryError::generic(format!(
+                        "Cannot convert <{}> to <{}>",
+                        top.show(self),
+                        x.show(self)
+                    )))
+                }
+            },
+            None => {
+                let mut bottom = Number::one();
+                let mut bottom_name = BTreeMap::new();
+                for (dim, power) in top.pretty_unit(self) {
+                    let unit = self
+                        .lookup(&dim.id)
+                        .ok_or_else(|| QueryError::NotFound(self.unknown_unit_err(&dim.id)))?;
+                    bottom = (&bottom * &unit.powi(power as i32)).unwrap();
+                    bottom_name.insert((*dim.id).clone(), power as isize);
+                }
+                (bottom, bottom_name, Numeric::one())
+            }
+        };
+        let mut reply = self.convert_number(top, &bottom, bottom_name, bottom_const, 10, digits)?;
+        let raw = (top / &bottom).expect("convert_number checks for division by zero");
+        let (exact, repr) = raw.value.to_eng_string(digits);
+        if exact {
+            reply.value.exact_value = Some(repr);
+            reply.value.approx_value = None;
+        } else {
+            reply.value.exact_value = None;
+            reply.value.approx_value = Some(repr);
+        }
+        Ok(reply)
+    }
+
+    /// The unit that a number like `12345678 byte` was written in,
+    /// without its prefix, if it has the same dimensions as `top`. Only
+    /// a single unit multiplied by constants is recognized.
+    fn written_unit(&self, expr: &Expr, top: &Number) -> Option<String> {
+        let name = match *expr {
+            Expr::Unit { ref name } => name,
+            Expr::Mul { ref exprs } => {
+                let mut units = exprs.iter().filter(|expr| !is_constant(expr));
+                return match (units.next(), units.next()) {
+                    (Some(unit), None) => self.written_unit(unit, top),
+                    _ => None,
+                };
+            }
+            Expr::UnaryOp(ref unary) if unary.op == UnaryOpType::Negative => {
+                return self.written_unit(&unary.expr, top)
+            }
+            _ => return None,
+        };
+        let defined =
+            |name: &&str| self.dimensions.contains(*name) || self.units.contains_key(*name);
+        let name = std::iter::once(&**name)
+            .chain(name.strip_suffix('s'))
+            .flat_map(|name| {
+                std::iter::once(name).chain(
+                    self.prefixes
+                        .iter()
+                        .filter_map(move |(prefix, _)| name.strip_prefix(&**prefix)),
+                )
+            })
+            .find(defined)?;
+        match self.lookup(name) {
+            Some(ref unit) if unit.unit == top.unit => {
+                Some(self.canonicalize(name).unwrap_or_else(|| name.to_owned()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts a number to `unit`, the unit it was written in, or the
+    /// unit it would normally be shown in, with the SI or binary prefix
+    /// that keeps the number smallest while still at least 1.
+    fn convert_auto(
+        &self,
+        top: &Number,
+        written: Option<String>,
+        unit: Option<&str>,
+        binary: bool,
+        digits: Digits,
+    ) -> Result<ConversionReply, QueryError> {
+        if !top.value.is_finite() {
+            return Err(QueryError::generic(format!(
+                "Cannot pick a prefix for <{}>, as it is not finite",
+                top.show(self)
+            )));
+        }
+        let (name, power) = match (unit, written) {
+            (Some(unit), _) => (
+                self.canonicalize(unit).unwrap_or_else(|| unit.to_owned()),
+                1,
+            ),
+            (None, Some(written)) => (written, 1),
+            (None, None) => {
+                let unit = top.pretty_unit(self);
+                if unit.len() != 1 {
+                    return Err(QueryError::generic(format!(
+                        "Cannot pick a prefix for <{}>, as it is not in a single unit",
+                        top.show(self)
+                    )));
+                }
+                let (dim, power) = unit.into_iter().next().unwrap();
+                ((*dim.id).clone(), power as i32)
+            }
+        };
+        // Prefixes go on grams, rather than on kilograms.
+        let name = if name == "kg" || name == "kilogram" {
+            "gram".to_owned()
+        } else {
+            name
+        };
+        let unit = self
+            .lookup(&name)
+            .ok_or_else(|| QueryError::NotFound(self.unknown_unit_err(&name)))?
+            .powi(power);
+
+        // The longest name of each prefix that is a power of the base.
+        let base = Numeric::from(if binary { 1024 } else { 1000 });
+        let exponents = if binary { 1..=8 } else { -8..=8 };
+        let mut prefixes = BTreeMap::new();
+        prefixes.insert(0, "");
+        for (prefix, value) in &self.prefixes {
+            if !value.dimless() {
+                continue;
+            }
+            for exp in exponents.clone().filter(|&exp| exp != 0) {
+                if value.value == pow(&base, exp) {
+                    let name = prefixes.entry(exp).or_insert(&**prefix);
+                    if prefix.len() > name.len() {
+                        *name = prefix;
+                    }
+                }
+            }
+        }
+
+        let value = (&top.value / &unit.value).abs();
+        let scaled = |exp: i32| &value / &pow(&base, exp * power);
+        let exp = prefixes
+            .keys()
+            .cloned()
+            .filter(|&exp| scaled(exp) >= Numeric::one())
+            .min_by(|&a, &b| scaled(a).partial_cmp(&scaled(b)).unwrap())
+            .or_else(|| {
+                prefixes
+                    .keys()
+                    .cloned()
+                    .max_by(|&a, &b| scaled(a).partial_cmp(&scaled(b)).unwrap())
+            })
+            .unwrap();
+        let exp = if value.is_zero() { 0 } else { exp };
+
+        let bottom = Number {
+            value: &unit.value * &pow(&base, exp * power),
+            unit: unit.unit.clone(),
+        };
+        let mut bottom_name = BTreeMap::new();
+        bottom_name.insert(format!("{}{}", prefixes[&exp], name), power as isize);
+        self.convert_number(top, &bottom, bottom_name, Numeric::one(), 10, digits)
+    }
+
+    /// Finds the closest fraction to a number with a denominator of at
+    /// most `max`, in the units it's normally shown in.
+    fn to_fraction(&self, top: &Number, max: u64) -> Result<FractionReply, QueryError> {
+        self.require_real_fraction(top, "a fraction")?;
+        let value = top.prettify(self).value;
+        let fraction = value.closest_fraction(max);
+        let (numer, denom) = fraction.to_rational();
+        Ok(FractionReply {
+            numer: numer.to_string(),
+            denom: denom.to_string(),
+            terms: None,
+            truncated: false,
+            error: value.rounding_error_string(&fraction, 10),
+            rest: NumberParts {
+                exact_value: None,
+                approx_value: None,
+                ..top.to_parts(self)
+            },
+        })
+    }
+
+    /// Writes a number as a continued fraction, in the units it's
+    /// normally shown in. Numbers that aren't exact are cut off after
+    /// `MAX_CONTINUED_FRACTION_TERMS`, as the rest would only come from
+    /// rounding error.
+    fn to_continued_fraction(&self, top: &Number) -> Result<FractionReply, QueryError> {
+        self.require_real_fraction(top, "a continued fraction")?;
+        let value = top.prettify(self).value;
+        let max_terms = if value.is_rational() {
+            usize::MAX
+        } else {
+            MAX_CONTINUED_FRACTION_TERMS
+        };
+        let (terms, truncated) = value.continued_fraction(max_terms);
+        let (numer, denom) = value.to_rational();
+        Ok(FractionReply {
+            numer: numer.to_string(),
+            denom: denom.to_string(),
+            terms: Some(terms.iter().map(|term| term.to_string()).collect()),
+            truncated,
+            error: None,
+            rest: NumberParts {
+                exact_value: None,
+                approx_value: None,
+                ..top.to_parts(self)
+            },
+        })
+    }
+
+    /// Converts a value to a nonlinear unit like °F or dBm.
+    fn convert_scale(
+        &self,
+        top: &Value,
+        scale: &Scale,
+        digits: Digits,
+    ) -> Result<QueryReply, QueryError> {
+        let unit = scale.unit(self).map_err(QueryError::generic)?;
+        let mut name = BTreeMap::new();
+        name.insert(scale.symbol.clone(), 1);
+        let convert = |num: &Number| -> Result<(Number, ConversionReply), QueryError> {
+            if num.unit != unit.unit {
+                return Err(QueryError::Conformance(Box::new(
+                    self.conformance_err(num, &unit),
+                )));
+            }
+            let reading = scale.from_linear(self, num).map_err(QueryError::generic)?;
+            let reply = self.show(&reading, &unit, name.clone(), Numeric::one(), 10, digits);
+            Ok((reading, reply))
+        };
+        match *top {
+            Value::Number(ref num) => Ok(QueryReply::Conversion(Box::new(convert(num)?.1))),
+            Value::Uncertain(ref num) => {
+                let (_, mut reply) = convert(&num.value)?;
+                let slope = scale.slope(self, &num.value).map_err(QueryError::generic)?;
+                reply.value.uncertainty = Some(Uncertain::error_string(
+                    &(&num.error / &slope.abs()),
+                    10,
+                    digits,
+                ));
+                Ok(QueryReply::Conversion(Box::new(reply)))
+            }
+            Value::Interval(ref num) => {
+                let (lower, mut reply) = convert(&num.lower)?;
+                let (upper, _) = convert(&num.upper)?;
+                let readings =
+                    Interval::from_endpoints(lower, upper).map_err(QueryError::generic)?;
+                reply.value.exact_value = None;
+                reply.value.approx_value = None;
+                reply.value.rounding_error = None;
+                reply.value.interval = Some(readings.bounds_in(&Numeric::one(), 10, digits));
+                Ok(QueryReply::Conversion(Box::new(reply)))
+            }
+            Value::List(ref list) => list
+                .iter()
+                .map(|num| convert(num).map(|(_, reply)| reply.value))
+                .collect::<Result<Vec<_>, _>>()
+                .map(|items| QueryReply::List(ListReply::new(items))),
+            _ => Err(QueryError::generic(format!(
+                "Cannot convert <{}> to {}",
+                top.show(self),
+                scale.symbol
+            ))),
+        }
+    }
+
+    /// Formats a number, including one with an uncertainty or an
+    /// interval, in the given base and number of digits.
+    fn value_in_base(&self, value: &Value, base: u8, digits: Digits) -> Option<NumberParts> {
+        match *value {
+            Value::Number(ref top) => {
+                // Shown in the same (possibly prefixed) units as
+                // `to_parts` picks.
+                let pretty = top.prettify(self);
+                let (exact, approx) = pretty.numeric_value(base, digits);
+                Some(NumberParts {
+                    exact_value: exact,
+                    approx_value: approx,
+                    rounding_error: match digits {
+                        Digits::Fraction(denom) => pretty
+                            .value
+                            .rounding_error_string(&pretty.value.round_to_fraction(denom), base),
+                        _ => None,
+                    },
+                    ..top.to_parts(self)
+                })
+            }
+            Value::Uncertain(ref top) => Some(top.to_parts_in(self, base, digits)),
+            Value::Interval(ref top) => Some(top.to_parts_in(self, base, digits)),
+            _ => None,
+        }
+    }
+
+    fn to_list(
+        &self,
+        top: &Number,
+        list: &[&str],
+        digits: Digits,
+    ) -> Result<Vec<NumberParts>, QueryError> {
         let units = list
             .iter()
             .map(|x| self.lookup(x).ok_or_else(|| self.unknown_unit_err(x)))
@@ -654,8 +1874,19 @@ impl Context {
             }
         }
         let mut value = top.value.clone();
-        let mut out = vec![];
         let len = units.len();
+        // The last unit is rounded before the value is split up, so
+        // that rounding up carries into the other units.
+        let mut rounding_error = None;
+        if let Digits::Fraction(denom) = digits {
+            self.require_finite_fraction(top, "a fraction")?;
+            let last = &units[len - 1].value;
+            let scaled = &value / last;
+            let rounded = scaled.round_to_fraction(denom);
+            rounding_error = scaled.rounding_error_string(&rounded, 10);
+            value = &rounded * last;
+        }
+        let mut out = vec![];
         for (i, unit) in units.into_iter().enumerate() {
             if i == len - 1 {
                 out.push(&value / &unit.value);
@@ -668,7 +1899,13 @@ impl Context {
         Ok(list
             .iter()
             .zip(out.into_iter())
-            .map(|(name, value)| {
+            .enumerate()
+            .map(|(i, (name, value))| {
+                let last = i == len - 1;
+                let fraction = match digits {
+                    Digits::Fraction(_) if last => Some(value.to_string(10, digits).1),
+                    _ => None,
+                };
                 let pretty = Number {
                     value,
                     unit: Number::one_unit(Dimension::new(name)).unit,
@@ -684,12 +1921,14 @@ impl Context {
                 NumberParts {
                     unit: Some(unit),
                     raw_unit: Some(raw),
-                    exact_value: Some(
-                        pretty
+                    exact_value: Some(match fraction {
+                        Some(fraction) => fraction,
+                        None => pretty
                             .approx_value
                             .or(pretty.exact_value)
                             .expect("to_parts returned neither exact nor approx value"),
-                    ),
+                    }),
+                    rounding_error: if last { rounding_error.clone() } else { None },
                     ..Default::default()
                 }
             })
@@ -698,9 +1937,21 @@ impl Context {
 
     /// Evaluates an expression, include `->` conversions.
     pub fn eval_outer(&self, expr: &Query) -> Result<QueryReply, QueryError> {
+        if let Query::Convert(_, _, _, Digits::Digits(digits) | Digits::SigFigs(digits)) = *expr {
+            // Raise the precision so that every requested digit is
+            // computed, rather than made up from rounding error.
+            let previous = self.requested_digits.replace(digits);
+            let result = self.eval_query(expr);
+            self.requested_digits.set(previous);
+            return result;
+        }
+        self.eval_query(expr)
+    }
+
+    fn eval_query(&self, expr: &Query) -> Result<QueryReply, QueryError> {
         match *expr {
             Query::Expr(Expr::Unit { ref name })
-                if {
+                if !self.variables.contains_key(name) && {
                     let a = self.definitions.contains_key(name);
                     let b = self
                         .canonicalize(name)
@@ -732,7 +1983,11 @@ impl Context {
                     }
                     if self.definitions.get(unit).is_none() {
                         if self.definitions.get(&unit_canon).is_none() {
-                            if !self.dimensions.contains(&**unit) {
+                            // Base units and built in units like π have
+                            // no definition to follow.
+                            if !self.dimensions.contains(&**unit)
+                                && !self.units.contains_key(&**unit)
+                            {
                                 break;
                             } else {
                                 assert!(name != *unit || canon != unit_canon);
@@ -778,11 +2033,18 @@ impl Context {
                     doc: self.docs.get(&name).cloned(),
                 })))
             }
+            Query::Expr(Expr::Unit { ref name })
+                if !self.variables.contains_key(name) && self.functions.contains_key(name) =>
+            {
+                Ok(QueryReply::Def(Box::new(
+                    self.function_def_reply(name, &self.functions[name]),
+                )))
+            }
             Query::Convert(ref top, Conversion::None, Some(base), digits) => {
                 let top = self.eval(top)?;
-                let top = match top {
-                    Value::Number(top) => top,
-                    _ => {
+                let parts = match self.value_in_base(&top, base, digits) {
+                    Some(parts) => parts,
+                    None => {
                         return Err(QueryError::generic(format!(
                             "<{}> in base {} is not defined",
                             top.show(self),
@@ -790,22 +2052,21 @@ impl Context {
                         )))
                     }
                 };
-                let (exact, approx) = top.numeric_value(base, digits);
-                let parts = NumberParts {
-                    exact_value: exact,
-                    approx_value: approx,
-                    ..top.to_parts(self)
-                };
                 Ok(QueryReply::Conversion(Box::new(ConversionReply {
                     value: parts,
                 })))
             }
             Query::Convert(ref top, Conversion::None, base, digits @ Digits::Digits(_))
-            | Query::Convert(ref top, Conversion::None, base, digits @ Digits::FullInt) => {
+            | Query::Convert(ref top, Conversion::None, base, digits @ Digits::FullInt)
+            | Query::Convert(ref top, Conversion::None, base, digits @ Digits::SigFigs(_))
+            | Query::Convert(ref top, Conversion::None, base, digits @ Digits::Fraction(_)) => {
                 let top = self.eval(top)?;
-                let top = match top {
-                    Value::Number(top) => top,
-                    _ => {
+                if let (Digits::Fraction(_), Value::Number(ref num)) = (digits, &top) {
+                    self.require_finite_fraction(num, "a fraction")?;
+                }
+                let parts = match self.value_in_base(&top, base.unwrap_or(10), digits) {
+                    Some(parts) => parts,
+                    None => {
                         return Err(QueryError::generic(format!(
                             "<{}> to {} is not defined",
                             top.show(self),
@@ -813,51 +2074,168 @@ impl Context {
                                 Digits::Default => unreachable!(),
                                 Digits::FullInt => "digits".to_owned(),
                                 Digits::Digits(n) => format!("{} digits", n),
+                                Digits::SigFigs(n) => format!("{} significant figures", n),
+                                Digits::Fraction(None) => "fraction".to_owned(),
+                                Digits::Fraction(Some(n)) => format!("nearest 1/{}", n),
                             }
                         )))
                     }
                 };
-                let (exact, approx) = top.numeric_value(base.unwrap_or(10), digits);
-                let parts = NumberParts {
-                    exact_value: exact,
-                    approx_value: approx,
-                    ..top.to_parts(self)
-                };
                 Ok(QueryReply::Conversion(Box::new(ConversionReply {
                     value: parts,
                 })))
             }
+            Query::Convert(ref top, Conversion::Polar, base, digits) => match self.eval(top)? {
+                Value::Number(ref num) => {
+                    let pretty = num.prettify(self);
+                    let (exact, approx) = pretty.value.polar_repr(base.unwrap_or(10), digits);
+                    Ok(QueryReply::Conversion(Box::new(ConversionReply {
+                        value: NumberParts {
+                            exact_value: exact,
+                            approx_value: approx,
+                            ..num.to_parts(self)
+                        },
+                    })))
+                }
+                top => Err(QueryError::generic(format!(
+                    "<{}> to polar is not defined",
+                    top.show(self)
+                ))),
+            },
+            Query::Convert(ref top, Conversion::Fraction(max), None, Digits::Default) => {
+                match self.eval(top)? {
+                    Value::Number(ref top) => self.to_fraction(top, max).map(QueryReply::Fraction),
+                    top => Err(QueryError::generic(format!(
+                        "<{}> to fraction is not defined",
+                        top.show(self)
+                    ))),
+                }
+            }
+            Query::Convert(ref top, Conversion::ContinuedFraction, None, Digits::Default) => {
+                match self.eval(top)? {
+                    Value::Number(ref top) => {
+                        self.to_continued_fraction(top).map(QueryReply::Fraction)
+                    }
+                    top => Err(QueryError::generic(format!(
+                        "<{}> to continued fraction is not defined",
+                        top.show(self)
+                    ))),
+                }
+            }
+            Query::Convert(ref top, Conversion::Eng(ref bottom), None, digits) => {
+                match self.eval(top)? {
+                    Value::Number(ref top) => self
+                        .convert_eng(top, bottom.as_ref(), digits)
+                        .map(|reply| QueryReply::Conversion(Box::new(reply))),
+                    top => Err(QueryError::generic(format!(
+                        "<{}> to eng is not defined",
+                        top.show(self)
+                    ))),
+                }
+            }
+            Query::Convert(ref expr, Conversion::Auto { binary, ref unit }, None, digits) => {
+                match self.eval(expr)? {
+                    Value::Number(ref top) => self
+                        .convert_auto(
+                            top,
+                            self.written_unit(expr, top),
+                            unit.as_deref(),
+                            binary,
+                            digits,
+                        )
+                        .map(|reply| QueryReply::Conversion(Box::new(reply))),
+                    top => Err(QueryError::generic(format!(
+                        "<{}> to auto is not defined",
+                        top.show(self)
+                    ))),
+                }
+            }
+            Query::Convert(ref top, Conversion::Rectangular, base, digits) => {
+                let top = self.eval(top)?;
+                match self.value_in_base(&top, base.unwrap_or(10), digits) {
+                    Some(value) => Ok(QueryReply::Conversion(Box::new(ConversionReply { value }))),
+                    None => Err(QueryError::generic(format!(
+                        "<{}> to rectangular is not defined",
+                        top.show(self)
+                    ))),
+                }
+            }
+            Query::Convert(ref top, Conversion::Expr(Expr::Unit { ref name }), None, digits)
+                if self.lookup_scale(name).is_some() =>
+            {
+                let top = self.eval(top)?;
+                self.convert_scale(&top, &self.scales[name], digits)
+            }
+            Query::Convert(_, Conversion::Expr(Expr::Unit { ref name }), Some(base), _)
+                if self.lookup_scale(name).is_some() =>
+            {
+                Err(QueryError::generic(format!(
+                    "Conversion to {} is not defined in base {}",
+                    name, base
+                )))
+            }
             Query::Convert(ref top, Conversion::Expr(ref bottom), base, digits) => match (
                 self.eval(top)?,
                 self.eval(bottom)?,
                 self.eval_unit_name(bottom)?,
             ) {
-                (Value::Number(top), Value::Number(bottom), (bottom_name, bottom_const)) => {
-                    if top.unit == bottom.unit {
-                        let raw = match &top / &bottom {
-                            Some(raw) => raw,
-                            None => {
-                                return Err(QueryError::generic(format!(
-                                    "Division by zero: {} / {}",
-                                    top.show(self),
-                                    bottom.show(self)
-                                )))
-                            }
-                        };
-                        Ok(QueryReply::Conversion(Box::new(self.show(
-                            &raw,
+                (Value::Number(top), Value::Number(bottom), (bottom_name, bottom_const)) => self
+                    .convert_number(
+                        &top,
+                        &bottom,
+                        bottom_name,
+                        bottom_const,
+                        base.unwrap_or(10),
+                        digits,
+                    )
+                    .map(|reply| QueryReply::Conversion(Box::new(reply))),
+                (Value::Uncertain(top), Value::Number(bottom), (bottom_name, bottom_const)) => {
+                    let mut reply = self.convert_number(
+                        &top.value,
+                        &bottom,
+                        bottom_name,
+                        bottom_const,
+                        base.unwrap_or(10),
+                        digits,
+                    )?;
+                    reply.value.uncertainty = Some(Uncertain::error_string(
+                        &(&top.error / &bottom.value.abs()),
+                        base.unwrap_or(10),
+                        digits,
+                    ));
+                    Ok(QueryReply::Conversion(Box::new(reply)))
+                }
+                (Value::Interval(top), Value::Number(bottom), (bottom_name, bottom_const)) => {
+                    let mut reply = self.convert_number(
+                        &top.lower,
+                        &bottom,
+                        bottom_name,
+                        bottom_const,
+                        base.unwrap_or(10),
+                        digits,
+                    )?;
+                    reply.value.exact_value = None;
+                    reply.value.approx_value = None;
+                    reply.value.rounding_error = None;
+                    reply.value.interval =
+                        Some(top.bounds_in(&bottom.value, base.unwrap_or(10), digits));
+                    Ok(QueryReply::Conversion(Box::new(reply)))
+                }
+                (Value::List(top), Value::Number(bottom), (bottom_name, bottom_const)) => top
+                    .iter()
+                    .map(|x| {
+                        self.convert_number(
+                            x,
                             &bottom,
-                            bottom_name,
-                            bottom_const,
+                            bottom_name.clone(),
+                            bottom_const.clone(),
                             base.unwrap_or(10),
                             digits,
-                        ))))
-                    } else {
-                        Err(QueryError::Conformance(Box::new(
-                            self.conformance_err(&top, &bottom),
-                        )))
-                    }
-                }
+                        )
+                        .map(|reply| reply.value)
+                    })
+                    .collect::<Result<Vec<_>, _>>()
+                    .map(|items| QueryReply::List(ListReply::new(items))),
                 (Value::Substance(sub), Value::Number(bottom), (bottom_name, bottom_const)) => sub
                     .get_in_unit(
                         bottom,
@@ -889,7 +2267,12 @@ impl Context {
                     y.show(self)
                 ))),
             },
-            Query::Convert(ref top, Conversion::List(ref list), None, Digits::Default) => {
+            Query::Convert(
+                ref top,
+                Conversion::List(ref list),
+                None,
+                digits @ (Digits::Default | Digits::Fraction(_)),
+            ) => {
                 let top = self.eval(top)?;
                 let top = match top {
                     Value::Number(num) => num,
@@ -901,16 +2284,20 @@ impl Context {
                         )))
                     }
                 };
-                self.to_list(&top, &list.iter().map(|x| &**x).collect::<Vec<_>>()[..])
-                    .map(|list| {
-                        QueryReply::UnitList(UnitListReply {
-                            rest: NumberParts {
-                                quantity: self.quantities.get(&top.unit).cloned(),
-                                ..Default::default()
-                            },
-                            list,
-                        })
+                self.to_list(
+                    &top,
+                    &list.iter().map(|x| &**x).collect::<Vec<_>>()[..],
+                    digits,
+                )
+                .map(|list| {
+                    QueryReply::UnitList(UnitListReply {
+                        rest: NumberParts {
+                            quantity: self.quantities.get(&top.unit).cloned(),
+                            ..Default::default()
+                        },
+                        list,
                     })
+                })
             }
             Query::Convert(ref top, Conversion::Offset(off), None, Digits::Default) => {
                 let top = self.eval(top)?;
@@ -942,44 +2329,77 @@ impl Context {
                 let top = top.with_timezone(&tz);
                 Ok(QueryReply::Date(DateReply::new(self, top)))
             }
-            Query::Convert(ref top, Conversion::Degree(ref deg), None, digits) => {
-                let (name, base, scale) = deg.name_base_scale();
-
-                let top = self.eval(top)?;
-                let top = match top {
-                    Value::Number(ref num) => num,
-                    _ => {
+            Query::Convert(
+                ref top,
+                ref which @ (Conversion::Iso8601
+                | Conversion::Rfc2822
+                | Conversion::Unix
+                | Conversion::UnixMs
+                | Conversion::Strftime(_)),
+                None,
+                Digits::Default,
+            ) => {
+                let top = match self.eval(top)? {
+                    Value::DateTime(date) => date,
+                    top => {
                         return Err(QueryError::generic(format!(
-                            "Cannot convert <{}> to °{}",
+                            "Cannot convert <{}> to {}",
                             top.show(self),
-                            name
+                            which
                         )))
                     }
                 };
-                let bottom = self
-                    .lookup(scale)
-                    .expect(&*format!("Unit {} missing", scale));
-                if top.unit != bottom.unit {
-                    Err(QueryError::Conformance(Box::new(
-                        self.conformance_err(&top, &bottom),
-                    )))
-                } else {
-                    let res = (top
-                        - &self
-                            .lookup(base)
-                            .expect(&*format!("Constant {} missing", base)))
-                        .unwrap();
-                    let res = (&res / &bottom).unwrap();
-                    let mut name = BTreeMap::new();
-                    name.insert(deg.to_string(), 1);
-                    Ok(QueryReply::Conversion(Box::new(self.show(
-                        &res,
-                        &bottom,
-                        name,
-                        Numeric::one(),
-                        10,
-                        digits,
-                    ))))
+                let formatted = top.format_as(which).map_err(QueryError::generic)?;
+                let reply = match top {
+                    date::GenericDateTime::Fixed(d) => DateReply::new(self, d),
+                    date::GenericDateTime::Timezone(d) => DateReply::new(self, d),
+                };
+                Ok(QueryReply::Date(reply.with_format(formatted)))
+            }
+            Query::Convert(ref expr, Conversion::Iso8601Duration, None, Digits::Default) => {
+                let top = self.eval_keeping_dates(expr)?;
+                let seconds = match top {
+                    Value::DateSpan(ref span) => Some(&span.seconds),
+                    Value::Number(ref n) => Some(n),
+                    _ => None,
+                }
+                .filter(|n| n.unit == Number::one_unit(Dimension::new("s")).unit);
+                let formatted = match (&top, seconds) {
+                    (Value::DateSpan(span), _) if self.calendar_durations => {
+                        let calendar = span.calendar();
+                        let rest = date::from_duration(&calendar.rest)
+                            .expect("Rest of calendar span is less than a day");
+                        date::iso8601_duration(
+                            calendar.negative,
+                            calendar.years,
+                            calendar.months,
+                            calendar.days,
+                            &rest.value,
+                        )
+                    }
+                    (Value::CalendarDuration(duration), _) => {
+                        self.iso8601_calendar_duration(duration)?
+                    }
+                    (_, Some(seconds)) => {
+                        let (days, rest) = seconds.value.abs().div_rem(&Numeric::from(86400));
+                        let days = days.to_int().ok_or_else(|| {
+                            QueryError::generic("Duration is too long".to_owned())
+                        })?;
+                        date::iso8601_duration(seconds.value < Numeric::zero(), 0, 0, days, &rest)
+                    }
+                    (_, None) => {
+                        return Err(QueryError::generic(format!(
+                            "Cannot convert <{}> to iso8601 duration",
+                            top.show(self)
+                        )))
+                    }
+                };
+                match self.value_to_reply(top)? {
+                    QueryReply::Duration(mut reply) => {
+                        reply.formatted = Some(formatted);
+                        Ok(QueryReply::Duration(reply))
+                    }
+                    _ => unreachable!("Durations in seconds are shown as DurationReply"),
                 }
             }
             Query::Convert(ref _expr, ref which, Some(base), _digits) => Err(QueryError::generic(
@@ -991,6 +2411,18 @@ impl Context {
                     which, digits
                 )))
             }
+            Query::Convert(ref _expr, ref which, _base, Digits::SigFigs(figs)) => {
+                Err(QueryError::generic(format!(
+                    "Conversion to {} is not defined to {} significant figures",
+                    which, figs
+                )))
+            }
+            Query::Convert(ref _expr, ref which, _base, Digits::Fraction(_)) => {
+                Err(QueryError::generic(format!(
+                    "Conversion to {} is not defined as a fraction",
+                    which
+                )))
+            }
             Query::Convert(ref _expr, ref which, _base, Digits::FullInt) => Err(
                 QueryError::generic(format!("Conversion to digits of {} is not defined", which)),
             ),
@@ -1130,50 +2562,162 @@ impl Context {
             Query::Search(ref string) => Ok(QueryReply::Search(search::query(self, &**string, 5))),
             Query::Expr(ref expr)
             | Query::Convert(ref expr, Conversion::None, None, Digits::Default) => {
-                let val = self.eval(expr)?;
-                match val {
-                    Value::Number(ref n)
-                        if n.unit == Number::one_unit(Dimension::new("s")).unit =>
-                    {
-                        let units = &["year", "week", "day", "hour", "minute", "second"];
-                        let list = self.to_list(&n, units)?;
-                        let mut list = list.into_iter();
-                        Ok(QueryReply::Duration(Box::new(DurationReply {
-                            raw: n.to_parts(self),
-                            years: list.next().expect("Unexpected end of iterator"),
-                            //months: list.next().expect("Unexpected end of iterator"),
-                            months: NumberParts {
-                                exact_value: Some("0".to_owned()),
-                                unit: Some("month".to_owned()),
-                                raw_unit: Some({
-                                    let mut raw = BTreeMap::new();
-                                    raw.insert(Dimension::new("month"), 1);
-                                    raw
-                                }),
-                                ..Default::default()
-                            },
-                            weeks: list.next().expect("Unexpected end of iterator"),
-                            days: list.next().expect("Unexpected end of iterator"),
-                            hours: list.next().expect("Unexpected end of iterator"),
-                            minutes: list.next().expect("Unexpected end of iterator"),
-                            seconds: list.next().expect("Unexpected end of iterator"),
-                        })))
+                let val = self.eval_keeping_dates(expr)?;
+                self.value_to_reply(val)
+            }
+            Query::Unset(ref name) => Err(QueryError::generic(format!(
+                "Cannot unset {}: variables can only be changed in a session",
+                name
+            ))),
+            Query::Function(ref name, _) => Err(QueryError::generic(format!(
+                "Cannot define {}: functions can only be defined in a session",
+                name
+            ))),
+            Query::Error(ref e) => Err(QueryError::generic(e.clone())),
+        }
+    }
+
+    /// Evaluates a query, allowing it to modify the session. Queries of
+    /// the form `name = expr` bind a variable, and `unset name` removes
+    /// one. Anything else is handled by `eval_outer`.
+    pub fn eval_outer_mut(&mut self, expr: &Query) -> Result<QueryReply, QueryError> {
+        match *expr {
+            Query::Expr(Expr::BinOp(BinOpExpr {
+                op: BinOpType::Equals,
+                ref left,
+                ref right,
+            })) => {
+                let name = match **left {
+                    Expr::Unit { ref name } if name == "now" => {
+                        return Err(QueryError::generic("Cannot assign to now".to_owned()))
+                    }
+                    Expr::Unit { ref name } => name.clone(),
+                    ref x => {
+                        return Err(QueryError::generic(format!(
+                            "Cannot assign to <{}>: expected a variable name",
+                            x
+                        )))
+                    }
+                };
+                // The dates of a span are kept, so that the variable is
+                // shown the same way as this reply.
+                let val = self.eval_keeping_dates(right)?;
+                let reply = self.value_to_reply(val.clone())?;
+                self.variables.insert(name.clone(), val);
+                Ok(QueryReply::Binding(Box::new(BindingReply {
+                    name,
+                    value: Some(reply),
+                })))
+            }
+            Query::Function(ref name, ref func) => {
+                for param in &func.params {
+                    if let Some(ref quantity) = param.quantity {
+                        self.quantity_unit(quantity)?;
                     }
-                    Value::Number(n) => Ok(QueryReply::Number(n.to_parts(self))),
-                    Value::DateTime(d) => match d {
-                        date::GenericDateTime::Fixed(d) => {
-                            Ok(QueryReply::Date(DateReply::new(self, d)))
-                        }
-                        date::GenericDateTime::Timezone(d) => {
-                            Ok(QueryReply::Date(DateReply::new(self, d)))
-                        }
-                    },
-                    Value::Substance(s) => Ok(QueryReply::Substance(
-                        s.to_reply(self).map_err(QueryError::generic)?,
-                    )),
                 }
+                let reply = self.function_def_reply(name, func);
+                self.functions.insert(name.clone(), func.clone());
+                Ok(QueryReply::Def(Box::new(reply)))
             }
-            Query::Error(ref e) => Err(QueryError::generic(e.clone())),
+            Query::Unset(ref name) => {
+                if self.variables.remove(name).is_some() || self.functions.remove(name).is_some() {
+                    Ok(QueryReply::Binding(Box::new(BindingReply {
+                        name: name.clone(),
+                        value: None,
+                    })))
+                } else {
+                    Err(QueryError::generic(format!("No such variable: {}", name)))
+                }
+            }
+            _ => self.eval_outer(expr),
+        }
+    }
+
+    /// Breaks the difference between two dates down into calendar
+    /// years and months, rather than ones of a fixed length.
+    fn calendar_duration(&self, span: &date::DateSpan) -> DurationReply {
+        let calendar = span.calendar();
+        // Calendar years and months aren't the units of the same name,
+        // which have a fixed length.
+        let part = |value: Numeric, unit: &str| {
+            let value = if calendar.negative { -&value } else { value };
+            let mut raw = BTreeMap::new();
+            raw.insert(Dimension::new(unit), 1);
+            NumberParts {
+                exact_value: Some(value.to_string(10, Digits::Default).1),
+                unit: Some(unit.to_owned()),
+                raw_unit: Some(raw),
+                ..Default::default()
+            }
+        };
+        let rest = calendar.rest;
+        let hours = rest.num_hours();
+        let minutes = (rest - chrono::Duration::hours(hours)).num_minutes();
+        let seconds = rest - chrono::Duration::hours(hours) - chrono::Duration::minutes(minutes);
+        let seconds = date::from_duration(&seconds)
+            .expect("Rest of calendar span is less than a day")
+            .value;
+        DurationReply {
+            raw: span.seconds.to_parts(self),
+            years: part(Numeric::from(calendar.years), "year"),
+            months: part(Numeric::from(calendar.months), "month"),
+            weeks: part(Numeric::from(calendar.days / 7), "week"),
+            days: part(Numeric::from(calendar.days % 7), "day"),
+            hours: part(Numeric::from(hours), "hour"),
+            minutes: part(Numeric::from(minutes), "minute"),
+            seconds: part(seconds, "second"),
+            formatted: None,
+        }
+    }
+
+    fn value_to_reply(&self, val: Value) -> Result<QueryReply, QueryError> {
+        match val {
+            Value::DateSpan(ref span) if self.calendar_durations => {
+                Ok(QueryReply::Duration(Box::new(self.calendar_duration(span))))
+            }
+            Value::DateSpan(_) | Value::CalendarDuration(_) => {
+                self.value_to_reply(val.forget_dates())
+            }
+            Value::Number(ref n) if n.unit == Number::one_unit(Dimension::new("s")).unit => {
+                let units = &["year", "week", "day", "hour", "minute", "second"];
+                let list = self.to_list(n, units, Digits::Default)?;
+                let mut list = list.into_iter();
+                Ok(QueryReply::Duration(Box::new(DurationReply {
+                    raw: n.to_parts(self),
+                    years: list.next().expect("Unexpected end of iterator"),
+                    //months: list.next().expect("Unexpected end of iterator"),
+                    months: NumberParts {
+                        exact_value: Some("0".to_owned()),
+                        unit: Some("month".to_owned()),
+                        raw_unit: Some({
+                            let mut raw = BTreeMap::new();
+                            raw.insert(Dimension::new("month"), 1);
+                            raw
+                        }),
+                        ..Default::default()
+                    },
+                    weeks: list.next().expect("Unexpected end of iterator"),
+                    days: list.next().expect("Unexpected end of iterator"),
+                    hours: list.next().expect("Unexpected end of iterator"),
+                    minutes: list.next().expect("Unexpected end of iterator"),
+                    seconds: list.next().expect("Unexpected end of iterator"),
+                    formatted: None,
+                })))
+            }
+            Value::Number(n) => Ok(QueryReply::Number(n.to_parts(self))),
+            Value::Uncertain(u) => Ok(QueryReply::Number(u.to_parts(self))),
+            Value::Interval(i) => Ok(QueryReply::Number(i.to_parts(self))),
+            Value::List(list) => Ok(QueryReply::List(ListReply::new(
+                list.iter().map(|x| x.to_parts(self)).collect(),
+            ))),
+            Value::DateTime(d) => match d {
+                date::GenericDateTime::Fixed(d) => Ok(QueryReply::Date(DateReply::new(self, d))),
+                date::GenericDateTime::Timezone(d) => Ok(QueryReply::Date(DateReply::new(self, d))),
+            },
+            Value::Bool(value) => Ok(QueryReply::Bool(BoolReply { value })),
+            Value::Substance(s) => Ok(QueryReply::Substance(
+                s.to_reply(self).map_err(QueryError::generic)?,
+            )),
         }
     }
 }
@@ -15,6 +15,9 @@ pub enum Token {
     Newline,
     Doc(String),
     Ident(String),
+    /// An identifier immediately followed by `(`, which starts a
+    /// function definition.
+    Call(String),
     Number(String, Option<String>, Option<String>),
     LPar,
     RPar,
@@ -28,6 +31,10 @@ pub enum Token {
     Question,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
+    Semicolon,
+    Comma,
     Error(String),
 }
 
@@ -45,6 +52,7 @@ fn is_ident(c: char) -> bool {
         //c if c.is_alphabetic() => true,
         //'_' | '$' | '-' | '\'' | '"' | '%' | ',' => true,
         ' ' | '\t' | '\n' | '\r' | '(' | ')' | '/' | '|' | '^' | '+' | '*' | '\\' | '#' => false,
+        '[' | ']' | ';' | ',' => false,
         _ => true,
     }
 }
@@ -78,6 +86,10 @@ impl<'a> Iterator for TokenIterator<'a> {
             '*' => Token::Asterisk,
             '{' => Token::LeftBrace,
             '}' => Token::RightBrace,
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
+            ';' => Token::Semicolon,
+            ',' => Token::Comma,
             '?' => {
                 if self.0.peek() == Some(&'?') {
                     self.0.next();
@@ -186,7 +198,11 @@ impl<'a> Iterator for TokenIterator<'a> {
                         break;
                     }
                 }
-                Token::Ident(buf)
+                if self.0.peek() == Some(&'(') {
+                    Token::Call(buf)
+                } else {
+                    Token::Ident(buf)
+                }
             }
             x => Token::Error(format!("Unknown character: '{}'", x)),
         };
@@ -208,6 +224,7 @@ fn parse_term(iter: &mut Iter<'_>) -> Expr {
             }
             _ => Expr::new_unit(name),
         },
+        Token::Call(name) => Expr::new_unit(name),
         Token::Number(num, frac, exp) => crate::number::Number::from_parts(
             &*num,
             frac.as_ref().map(|x| &**x),
@@ -262,6 +279,9 @@ fn parse_mul(iter: &mut Iter<'_>) -> Expr {
             | Token::Plus
             | Token::Dash
             | Token::RPar
+            | Token::RightBracket
+            | Token::Semicolon
+            | Token::Comma
             | Token::Newline
             | Token::Eof => break,
             Token::Asterisk => {
@@ -308,6 +328,210 @@ pub fn parse_expr(iter: &mut Iter<'_>) -> Expr {
     parse_add(iter)
 }
 
+/// Parses the parameter list of a function definition like
+/// `reynolds(rho, v: velocity, L, mu) rho v L / mu`, after the `(`.
+fn parse_params(iter: &mut Iter<'_>) -> Result<Vec<FunctionParam>, String> {
+    // `:` is an identifier character, so the list is split up after
+    // joining its tokens back together.
+    let mut buf = String::new();
+    loop {
+        match iter.next().unwrap() {
+            Token::RPar => break,
+            Token::Comma => buf.push(','),
+            Token::Ident(ref s) => {
+                buf.push_str(s);
+                buf.push(' ');
+            }
+            x => return Err(format!("Expected parameter name, got {:?}", x)),
+        }
+    }
+    if buf.trim().is_empty() {
+        return Ok(vec![]);
+    }
+    buf.split(',')
+        .map(|param| {
+            let mut parts = param.splitn(2, ':');
+            let name = parts.next().unwrap().trim();
+            if name.is_empty() {
+                return Err("Expected parameter name".to_owned());
+            }
+            Ok(FunctionParam {
+                name: name.to_owned(),
+                quantity: parts.next().map(|x| x.trim().to_owned()),
+            })
+        })
+        .collect()
+}
+
+/// Parses one side of `units=[1;K]`, where an empty side means
+/// dimensionless.
+fn parse_scale_unit(iter: &mut Iter<'_>) -> ExprString {
+    match *iter.peek().unwrap() {
+        Token::Semicolon | Token::RightBracket => ExprString(Expr::new_const(Numeric::one())),
+        _ => ExprString(parse_expr(iter)),
+    }
+}
+
+/// Parses `[1;K]`, after the `[`.
+fn parse_scale_units(iter: &mut Iter<'_>) -> Result<ScaleUnits, String> {
+    let input = parse_scale_unit(iter);
+    match iter.next().unwrap() {
+        Token::Semicolon => (),
+        x => return Err(format!("Expected ;, got {:?}", x)),
+    }
+    let output = parse_scale_unit(iter);
+    match iter.next().unwrap() {
+        Token::RightBracket => Ok(ScaleUnits { input, output }),
+        x => Err(format!("Expected ], got {:?}", x)),
+    }
+}
+
+/// Parses an interval like `[-273.15,)` or `(0,1]`.
+fn parse_scale_range(iter: &mut Iter<'_>) -> Result<ScaleRange, String> {
+    let lower_inclusive = match iter.next().unwrap() {
+        Token::LeftBracket => true,
+        Token::LPar => false,
+        x => return Err(format!("Expected [ or (, got {:?}", x)),
+    };
+    let lower = match *iter.peek().unwrap() {
+        Token::Comma => None,
+        _ => Some(ScaleBound {
+            value: ExprString(parse_expr(iter)),
+            inclusive: lower_inclusive,
+        }),
+    };
+    match iter.next().unwrap() {
+        Token::Comma => (),
+        x => return Err(format!("Expected ,, got {:?}", x)),
+    }
+    let upper = match *iter.peek().unwrap() {
+        Token::RightBracket | Token::RPar => None,
+        _ => Some(parse_expr(iter)),
+    };
+    let upper_inclusive = match iter.next().unwrap() {
+        Token::RightBracket => true,
+        Token::RPar => false,
+        x => return Err(format!("Expected ] or ), got {:?}", x)),
+    };
+    Ok(ScaleRange {
+        lower,
+        upper: upper.map(|value| ScaleBound {
+            value: ExprString(value),
+            inclusive: upper_inclusive,
+        }),
+    })
+}
+
+/// Skips the rest of a definition that failed to parse.
+fn skip_line(iter: &mut Iter<'_>) {
+    while let Some(tok) = iter.peek() {
+        match *tok {
+            Token::Newline | Token::Eof => break,
+            _ => iter.next(),
+        };
+    }
+}
+
+/// Parses one number of a table, which may be negative.
+fn parse_table_number(iter: &mut Iter<'_>) -> Result<ExprString, String> {
+    match *iter.peek().unwrap() {
+        Token::Number(..) | Token::Dash => Ok(ExprString(parse_term(iter))),
+        ref x => Err(format!("Expected number in table, got {:?}", x)),
+    }
+}
+
+/// Parses the rest of a table like `wiregauge[in] 0 0.3249 1 0.2893`,
+/// after the `[`. The points are pairs of a reading and an output.
+fn parse_table(iter: &mut Iter<'_>) -> Result<TableDef, String> {
+    let unit = ExprString(parse_expr(iter));
+    match iter.next().unwrap() {
+        Token::RightBracket => (),
+        x => return Err(format!("Expected ], got {:?}", x)),
+    }
+    let mut points = vec![];
+    loop {
+        match *iter.peek().unwrap() {
+            Token::Newline | Token::Eof => break,
+            _ => (),
+        }
+        let input = parse_table_number(iter)?;
+        let output = parse_table_number(iter)?;
+        points.push(TablePoint { input, output });
+    }
+    if points.len() < 2 {
+        return Err("Tables must have at least two points".to_owned());
+    }
+    Ok(TableDef { unit, points })
+}
+
+/// Parses the rest of a function definition after its parameters.
+/// Definitions with an inverse after a `;`, like `tempC(x)
+/// units=[1;K] x K + stdtemp ; (tempC +(-stdtemp))/K`, are nonlinear
+/// units as in GNU units.
+fn parse_function(
+    iter: &mut Iter<'_>,
+    name: &str,
+    params: Vec<FunctionParam>,
+) -> Result<Def, String> {
+    let mut units = None;
+    let mut domain = None;
+    let mut range = None;
+    loop {
+        match iter.peek().cloned().unwrap() {
+            Token::LeftBracket => {
+                iter.next();
+                units = Some(parse_scale_units(iter)?);
+            }
+            Token::Ident(ref s) | Token::Call(ref s) if s == "units=" => {
+                iter.next();
+                match iter.next().unwrap() {
+                    Token::LeftBracket => units = Some(parse_scale_units(iter)?),
+                    x => return Err(format!("Expected [, got {:?}", x)),
+                }
+            }
+            Token::Ident(ref s) | Token::Call(ref s) if s == "domain=" => {
+                iter.next();
+                domain = Some(parse_scale_range(iter)?);
+            }
+            Token::Ident(ref s) | Token::Call(ref s) if s == "range=" => {
+                iter.next();
+                range = Some(parse_scale_range(iter)?);
+            }
+            _ => break,
+        }
+    }
+    let body = ExprString(parse_expr(iter));
+    if let Token::Semicolon = *iter.peek().unwrap() {
+        iter.next();
+        let inverse = ExprString(parse_expr(iter));
+        let mut params = params.into_iter();
+        return match (params.next(), params.next()) {
+            (
+                Some(FunctionParam {
+                    name,
+                    quantity: None,
+                }),
+                None,
+            ) => Ok(Def::Scale(Box::new(ScaleDef {
+                param: name,
+                forward: body,
+                inverse,
+                units,
+                domain,
+                range,
+            }))),
+            _ => Err(format!(
+                "Nonlinear unit {} must have a single parameter without a quantity",
+                name
+            )),
+        };
+    }
+    if units.is_some() || domain.is_some() || range.is_some() {
+        return Err(format!("Expected ; followed by the inverse of {}", name));
+    }
+    Ok(Def::Function(FunctionDef { params, body }))
+}
+
 pub fn parse(iter: &mut Iter<'_>) -> Defs {
     let mut map = vec![];
     let mut line = 1;
@@ -362,6 +586,25 @@ pub fn parse(iter: &mut Iter<'_>) -> Defs {
                     Some(old) => Some(format!("{} {}", old.trim(), line.trim())),
                 };
             }
+            Token::Call(name) => {
+                // function
+                iter.next();
+                let def = match parse_params(iter)
+                    .and_then(|params| parse_function(iter, &name, params))
+                {
+                    Ok(def) => def,
+                    Err(message) => {
+                        skip_line(iter);
+                        Def::Error { message }
+                    }
+                };
+                map.push(DefEntry {
+                    name,
+                    def: Rc::new(def),
+                    doc: doc.take(),
+                    category: category.clone(),
+                });
+            }
             Token::Ident(name) => {
                 if name.ends_with('-') {
                     // prefix
@@ -427,6 +670,22 @@ pub fn parse(iter: &mut Iter<'_>) -> Defs {
                             doc: doc.take(),
                             category: category.clone(),
                         });
+                    } else if let Some(&Token::LeftBracket) = iter.peek() {
+                        // table
+                        iter.next();
+                        let def = match parse_table(iter) {
+                            Ok(table) => Def::Table(table),
+                            Err(message) => {
+                                skip_line(iter);
+                                Def::Error { message }
+                            }
+                        };
+                        map.push(DefEntry {
+                            name,
+                            def: Rc::new(def),
+                            doc: doc.take(),
+                            category: category.clone(),
+                        });
                     } else if let Some(&Token::LeftBrace) = iter.peek() {
                         // substance
                         iter.next();
@@ -638,7 +897,7 @@ mod tests {
             ".123",
             Expr::Const { value },
             value,
-            Numeric::Rational(BigRat::small_ratio(123, 1000))
+            Numeric::from(BigRat::small_ratio(123, 1000))
         );
     }
 
@@ -646,4 +905,91 @@ mod tests {
     fn test_escaped_quotes() {
         expect!("\"ab\\\"\"", Expr::Unit { ref name }, name, "ab\"")
     }
+
+    #[test]
+    fn test_function_def() {
+        let defs =
+            parse_str("reynolds(rho,v: velocity, L, mu) rho v L / mu\nf() 2\nsq (10 ft)^2\n");
+        match *defs.defs[0].def {
+            Def::Function(ref func) => {
+                let params = func
+                    .params
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>();
+                assert_eq!(params, ["rho", "v: velocity", "L", "mu"]);
+                assert_eq!(func.body.to_string(), "rho v L / mu");
+            }
+            ref x => panic!("{:?}", x),
+        }
+        match *defs.defs[1].def {
+            Def::Function(ref func) => assert!(func.params.is_empty()),
+            ref x => panic!("{:?}", x),
+        }
+        match *defs.defs[2].def {
+            Def::Unit { ref expr } => assert_eq!(expr.to_string(), "(10 ft)^2"),
+            ref x => panic!("{:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_table_def() {
+        let defs = parse_str("gauge[in] \\\n -1 0.5  0 .25 \\\n 10 0.01\nbad[in] 1 2 3\n");
+        match *defs.defs[0].def {
+            Def::Table(ref table) => {
+                assert_eq!(table.unit.to_string(), "in");
+                let points = table
+                    .points
+                    .iter()
+                    .map(|point| format!("{} {}", *point.input, *point.output))
+                    .collect::<Vec<_>>();
+                assert_eq!(points, ["-1 0.5", "0 0.25", "10 0.01"]);
+            }
+            ref x => panic!("{:?}", x),
+        }
+        match *defs.defs[1].def {
+            Def::Error { ref message } => {
+                assert_eq!(message, "Expected number in table, got Newline")
+            }
+            ref x => panic!("{:?}", x),
+        }
+    }
+
+    #[test]
+    fn test_scale_def() {
+        let defs = parse_str(
+            "tempF(x) units=[1;K] domain=[-459.67,) range=[0,) \\\n\
+             (x + 459.67) degR ; tempF / degR - 459.67\n\
+             half(x) [;m] 2 x ; 1|2 half\n",
+        );
+        match *defs.defs[0].def {
+            Def::Scale(ref scale) => {
+                assert_eq!(scale.param, "x");
+                assert_eq!(scale.forward.to_string(), "(x + 459.67) degR");
+                assert_eq!(scale.inverse.to_string(), "tempF / degR - 459.67");
+                let units = scale.units.as_ref().unwrap();
+                assert_eq!(units.input.to_string(), "1");
+                assert_eq!(units.output.to_string(), "K");
+                assert_eq!(scale.domain.as_ref().unwrap().to_string(), "[-459.67,)");
+                assert_eq!(scale.range.as_ref().unwrap().to_string(), "[0,)");
+            }
+            ref x => panic!("{:?}", x),
+        }
+        match *defs.defs[1].def {
+            Def::Scale(ref scale) => {
+                let units = scale.units.as_ref().unwrap();
+                assert_eq!(units.input.to_string(), "1");
+                assert_eq!(units.output.to_string(), "m");
+                assert!(scale.domain.is_none());
+            }
+            ref x => panic!("{:?}", x),
+        }
+        match *parse_str("bad(x, y) x ; y").defs[0].def {
+            Def::Error { ref message } => assert_eq!(
+                message,
+                "Nonlinear unit bad must have a single parameter without a quantity"
+            ),
+            ref x => panic!("{:?}", x),
+        }
+    }
 }
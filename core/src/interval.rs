@@ -0,0 +1,426 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::ast::Function;
+use crate::bigint::BigInt;
+use crate::context::Context;
+use crate::number::{pow, Number, NumberParts, Quantity};
+use crate::numeric::{Digits, Numeric};
+use crate::value::Show;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A range of numbers like `[2, 3] kg`, which is guaranteed to contain
+/// the true value.
+///
+/// Bounds that are floats are rounded outwards after every operation,
+/// so that rounding error can only make the interval wider. Big floats
+/// become machine floats when this happens.
+#[derive(Clone, Debug)]
+pub struct Interval {
+    pub lower: Number,
+    pub upper: Number,
+}
+
+fn next_up(x: f64) -> f64 {
+    if x.is_nan() || x == f64::INFINITY {
+        x
+    } else if x == 0.0 {
+        f64::from_bits(1)
+    } else if x > 0.0 {
+        f64::from_bits(x.to_bits() + 1)
+    } else {
+        f64::from_bits(x.to_bits() - 1)
+    }
+}
+
+fn next_down(x: f64) -> f64 {
+    -next_up(-x)
+}
+
+fn round_down(value: Numeric) -> Numeric {
+    match value {
+        Numeric::Float(f) => Numeric::Float(next_down(f)),
+        // Converting to f64 rounds to nearest, so step past that too.
+        Numeric::BigFloat(f) => Numeric::Float(next_down(f.to_f64())),
+        exact => exact,
+    }
+}
+
+fn round_up(value: Numeric) -> Numeric {
+    match value {
+        Numeric::Float(f) => Numeric::Float(next_up(f)),
+        Numeric::BigFloat(f) => Numeric::Float(next_up(f.to_f64())),
+        exact => exact,
+    }
+}
+
+fn min(a: Numeric, b: Numeric) -> Numeric {
+    if b < a {
+        b
+    } else {
+        a
+    }
+}
+
+fn max(a: Numeric, b: Numeric) -> Numeric {
+    if b > a {
+        b
+    } else {
+        a
+    }
+}
+
+/// Parses a number as printed by `Numeric::to_string`, along with the
+/// value of its last digit. Only bases where `e` can't be a digit are
+/// supported.
+fn parse_repr(repr: &str, base: u8) -> Option<(Numeric, Numeric)> {
+    if base > 14 {
+        return None;
+    }
+    let (negative, repr) = match repr.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, repr),
+    };
+    let (mantissa, exp) = match repr.find('e') {
+        Some(i) => (&repr[..i], repr[i + 1..].parse::<i32>().ok()?),
+        None => (repr, 0),
+    };
+    let frac_len = mantissa.find('.').map_or(0, |i| mantissa.len() - i - 1) as i32;
+    let digits = mantissa.replace('.', "");
+    let digits = BigInt::from_str_radix(&digits, base as u32).ok()?;
+    let ulp = pow(&Numeric::from(base as i64), exp - frac_len);
+    let value = &Numeric::from(digits) * &ulp;
+    Some((if negative { -&value } else { value }, ulp))
+}
+
+/// Whether a built-in function is increasing (`Some(true)`) or
+/// decreasing (`Some(false)`) over its whole domain.
+pub fn monotonicity(func: &Function) -> Option<bool> {
+    match *func {
+        Function::Sqrt
+        | Function::Exp
+        | Function::Ln
+        | Function::Log2
+        | Function::Log10
+        | Function::Asin
+        | Function::Atan
+        | Function::Sinh
+        | Function::Tanh
+        | Function::Asinh
+        | Function::Acosh
+        | Function::Atanh
+        | Function::Round
+        | Function::Floor
+        | Function::Ceil
+        | Function::Trunc => Some(true),
+        Function::Acos => Some(false),
+        _ => None,
+    }
+}
+
+/// Checks that the bounds of an interval are real and finite, which
+/// is what lets it promise to contain the true value.
+fn check_bounds(a: &Numeric, b: &Numeric) -> Result<(), String> {
+    if a.is_complex() || b.is_complex() {
+        return Err("Interval bounds must be real numbers".to_string());
+    }
+    if !a.is_finite() || !b.is_finite() {
+        return Err("Interval bounds must be finite".to_string());
+    }
+    Ok(())
+}
+
+/// Whether a built-in function is defined for a real number. The
+/// domains are all intervals themselves, so an interval fits one if
+/// both of its bounds do.
+pub fn in_domain(func: &Function, x: &Numeric) -> bool {
+    let one = Numeric::one();
+    match *func {
+        Function::Sqrt => *x >= Numeric::zero(),
+        Function::Ln | Function::Log2 | Function::Log10 => *x > Numeric::zero(),
+        Function::Asin | Function::Acos => *x >= -&one && *x <= one,
+        Function::Acosh => *x >= one,
+        Function::Atanh => *x > -&one && *x < one,
+        _ => true,
+    }
+}
+
+/// The value of a built-in function at the points where it's known
+/// exactly, like `exp(0) = 1`, which floating point would otherwise
+/// only approximate.
+pub fn exact_at(func: &Function, x: &Numeric) -> Option<Numeric> {
+    let zero = Numeric::zero();
+    let one = Numeric::one();
+    match *func {
+        Function::Exp | Function::Cosh if *x == zero => Some(one),
+        Function::Sinh
+        | Function::Tanh
+        | Function::Asin
+        | Function::Atan
+        | Function::Asinh
+        | Function::Atanh
+            if *x == zero =>
+        {
+            Some(zero)
+        }
+        Function::Ln | Function::Log2 | Function::Log10 | Function::Acos | Function::Acosh
+            if *x == one =>
+        {
+            Some(zero)
+        }
+        _ => None,
+    }
+}
+
+impl Interval {
+    pub fn new(lower: Number, upper: Number) -> Result<Interval, String> {
+        if lower.unit != upper.unit {
+            return Err("Interval bounds must have the same units".to_string());
+        }
+        check_bounds(&lower.value, &upper.value)?;
+        if lower.value > upper.value {
+            return Err("Lower bound of interval is greater than upper bound".to_string());
+        }
+        Ok(Interval { lower, upper })
+    }
+
+    /// An interval containing a single number.
+    pub fn exact(value: Number) -> Interval {
+        Interval {
+            lower: value.clone(),
+            upper: value,
+        }
+    }
+
+    /// Builds an interval from two endpoints in either order, rounding
+    /// them outwards.
+    pub fn from_endpoints(a: Number, b: Number) -> Result<Interval, String> {
+        check_bounds(&a.value, &b.value)?;
+        let (lower, upper) = if b.value < a.value {
+            (b.value, a.value)
+        } else {
+            (a.value, b.value)
+        };
+        Interval::rounded(lower, upper, &a.unit)
+    }
+
+    fn from_values(values: Vec<Numeric>, unit: &Number) -> Result<Interval, String> {
+        for value in &values {
+            check_bounds(value, value)?;
+        }
+        let mut iter = values.into_iter();
+        let first = iter.next().expect("Interval needs at least one value");
+        let (lower, upper) = iter.fold((first.clone(), first), |(lo, hi), x| {
+            (min(lo, x.clone()), max(hi, x))
+        });
+        Interval::rounded(lower, upper, &unit.unit)
+    }
+
+    /// Rounds the bounds outwards, which can overflow a big float that
+    /// doesn't fit in a machine float, so they're checked again after.
+    fn rounded(lower: Numeric, upper: Numeric, unit: &Quantity) -> Result<Interval, String> {
+        let (lower, upper) = (round_down(lower), round_up(upper));
+        check_bounds(&lower, &upper)?;
+        Ok(Interval {
+            lower: Number {
+                value: lower,
+                unit: unit.clone(),
+            },
+            upper: Number {
+                value: upper,
+                unit: unit.clone(),
+            },
+        })
+    }
+
+    pub fn contains_zero(&self) -> bool {
+        self.lower.value <= Numeric::zero() && self.upper.value >= Numeric::zero()
+    }
+
+    pub fn pow(&self, exp: &Number, precision: u64) -> Result<Interval, String> {
+        let (num, den) = exp.value.to_rational();
+        if exp.value < Numeric::zero() && self.contains_zero() {
+            return Err(
+                "Raising an interval containing zero to a negative power is undefined".to_string(),
+            );
+        }
+        if den != BigInt::one() && self.lower.value < Numeric::zero() {
+            return Err(
+                "Fractional powers of intervals with negative values are undefined".to_string(),
+            );
+        }
+        let lower = self.lower.pow(exp, precision)?;
+        let upper = self.upper.pow(exp, precision)?;
+        let even = den == BigInt::one() && &num % &BigInt::from(2u64) == BigInt::zero();
+        if even && self.contains_zero() {
+            // The minimum is at zero rather than at either end.
+            return Interval::from_values(
+                vec![Numeric::zero(), lower.value.clone(), upper.value],
+                &lower,
+            );
+        }
+        Interval::from_endpoints(lower, upper)
+    }
+
+    /// Formats one of the bounds, rounding it outwards if it can't be
+    /// shown exactly.
+    pub fn bound_string(value: &Numeric, base: u8, digits: Digits, upward: bool) -> String {
+        if value.is_rational() {
+            if let (Some(exact), _) = value.string_repr(base, digits) {
+                return exact;
+            }
+        }
+        let (exact, repr) = value.to_string(base, digits);
+        if exact {
+            return repr;
+        }
+        // Inexact output is truncated or rounded, so it's moved one
+        // digit, or one step of the fraction, outwards if that put it
+        // inside the interval.
+        let shown = match digits {
+            Digits::Fraction(Some(denom)) => Some((
+                value.round_to_fraction(Some(denom)),
+                &Numeric::one() / &Numeric::from(denom as i64),
+            )),
+            Digits::Fraction(None) => None,
+            _ => parse_repr(&repr, base),
+        };
+        match shown {
+            Some((shown, ulp)) if upward && shown < *value => {
+                (&shown + &ulp).to_string(base, digits).1
+            }
+            Some((shown, ulp)) if !upward && shown > *value => {
+                (&shown - &ulp).to_string(base, digits).1
+            }
+            _ => repr,
+        }
+    }
+
+    /// Converts both bounds into the units of `bottom`, for `->`
+    /// conversions.
+    pub fn bounds_in(&self, bottom: &Numeric, base: u8, digits: Digits) -> (String, String) {
+        let lower = &self.lower.value / bottom;
+        let upper = &self.upper.value / bottom;
+        let (lower, upper) = if upper < lower {
+            (upper, lower)
+        } else {
+            (lower, upper)
+        };
+        (
+            Interval::bound_string(&lower, base, digits, false),
+            Interval::bound_string(&upper, base, digits, true),
+        )
+    }
+
+    pub fn to_parts(&self, context: &Context) -> NumberParts {
+        self.to_parts_in(context, 10, Digits::Default)
+    }
+
+    /// Like `to_parts`, but with the bounds shown in the given base and
+    /// number of digits.
+    pub fn to_parts_in(&self, context: &Context, base: u8, digits: Digits) -> NumberParts {
+        // Both bounds are shown in the units picked for the larger one.
+        let reference = if self.lower.value.abs() > self.upper.value.abs() {
+            &self.lower
+        } else {
+            &self.upper
+        };
+        let pretty = reference.prettify(context);
+        // Bounds that overflowed can't be scaled, so they're shown as
+        // they are.
+        let scale = if reference.value == Numeric::zero() || !pretty.value.is_finite() {
+            Numeric::one()
+        } else {
+            &reference.value / &pretty.value
+        };
+        NumberParts {
+            exact_value: None,
+            approx_value: None,
+            interval: Some(self.bounds_in(&scale, base, digits)),
+            ..reference.to_parts(context)
+        }
+    }
+}
+
+impl Show for Interval {
+    fn show(&self, context: &Context) -> String {
+        self.to_parts(context).to_string()
+    }
+}
+
+impl<'a, 'b> Add<&'b Interval> for &'a Interval {
+    type Output = Result<Interval, String>;
+
+    fn add(self, other: &Interval) -> Self::Output {
+        let mismatch = || "Addition of units with mismatched units is not meaningful".to_string();
+        let lower = (&self.lower + &other.lower).ok_or_else(mismatch)?;
+        let upper = (&self.upper + &other.upper).ok_or_else(mismatch)?;
+        Interval::from_endpoints(lower, upper)
+    }
+}
+
+impl<'a, 'b> Sub<&'b Interval> for &'a Interval {
+    type Output = Result<Interval, String>;
+
+    fn sub(self, other: &Interval) -> Self::Output {
+        let mismatch =
+            || "Subtraction of units with mismatched units is not meaningful".to_string();
+        let lower = (&self.lower - &other.upper).ok_or_else(mismatch)?;
+        let upper = (&self.upper - &other.lower).ok_or_else(mismatch)?;
+        Interval::from_endpoints(lower, upper)
+    }
+}
+
+impl<'a> Neg for &'a Interval {
+    type Output = Interval;
+
+    fn neg(self) -> Interval {
+        Interval {
+            lower: Number {
+                value: -&self.upper.value,
+                unit: self.upper.unit.clone(),
+            },
+            upper: Number {
+                value: -&self.lower.value,
+                unit: self.lower.unit.clone(),
+            },
+        }
+    }
+}
+
+impl<'a, 'b> Mul<&'b Interval> for &'a Interval {
+    type Output = Result<Interval, String>;
+
+    fn mul(self, other: &Interval) -> Self::Output {
+        let unit =
+            (&self.lower * &other.lower).ok_or_else(|| "Bug: Mul should not fail".to_string())?;
+        let values = vec![
+            &self.lower.value * &other.lower.value,
+            &self.lower.value * &other.upper.value,
+            &self.upper.value * &other.lower.value,
+            &self.upper.value * &other.upper.value,
+        ];
+        Interval::from_values(values, &unit)
+    }
+}
+
+impl<'a, 'b> Div<&'b Interval> for &'a Interval {
+    type Output = Result<Interval, String>;
+
+    fn div(self, other: &Interval) -> Self::Output {
+        if other.contains_zero() {
+            return Err("Division by an interval containing zero is undefined, \
+                 since the result would be unbounded"
+                .to_string());
+        }
+        let unit = (&self.lower / &other.lower).ok_or_else(|| "Division by zero".to_string())?;
+        let values = vec![
+            &self.lower.value / &other.lower.value,
+            &self.lower.value / &other.upper.value,
+            &self.upper.value / &other.lower.value,
+            &self.upper.value / &other.upper.value,
+        ];
+        Interval::from_values(values, &unit)
+    }
+}
@@ -40,6 +40,7 @@ println!("{}", one_line(&mut ctx, "kWh / year -> W").unwrap());
 extern crate serde_derive;
 
 pub mod ast;
+pub mod bigfloat;
 pub mod bigint;
 pub mod bigrat;
 pub mod context;
@@ -49,13 +50,18 @@ pub mod factorize;
 pub mod fmt;
 pub mod formula;
 pub mod gnu_units;
+pub mod interval;
+pub mod list;
 pub mod load;
 pub mod number;
 pub mod numeric;
 pub mod reply;
+pub mod scale;
 pub mod search;
 pub mod substance;
+pub mod symbolic;
 pub mod text_query;
+pub mod uncertain;
 pub mod value;
 
 use reply::{QueryError, QueryReply};
@@ -78,7 +84,7 @@ pub fn eval(ctx: &mut Context, line: &str) -> Result<QueryReply, QueryError> {
     ctx.update_time();
     let mut iter = text_query::TokenIterator::new(line.trim()).peekable();
     let expr = text_query::parse_query(&mut iter);
-    ctx.eval_outer(&expr)
+    ctx.eval_outer_mut(&expr)
 }
 
 /// Evaluates a single line within a context.
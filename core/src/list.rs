@@ -0,0 +1,106 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::number::Number;
+use crate::numeric::Numeric;
+use std::cmp::Ordering;
+
+fn add(left: &Number, right: &Number) -> Result<Number, String> {
+    (left + right)
+        .ok_or_else(|| "Addition of units with mismatched units is not meaningful".to_string())
+}
+
+fn mul(left: &Number, right: &Number) -> Result<Number, String> {
+    (left * right).ok_or_else(|| "Bug: Mul should not fail".to_string())
+}
+
+fn first(items: &[Number]) -> Result<&Number, String> {
+    items
+        .first()
+        .ok_or_else(|| "Expected at least one element".to_string())
+}
+
+/// Adds up the elements, which must all have the same units.
+pub fn sum(items: &[Number]) -> Result<Number, String> {
+    let first = first(items)?;
+    items[1..]
+        .iter()
+        .try_fold(first.clone(), |acc, x| add(&acc, x))
+}
+
+pub fn mean(items: &[Number]) -> Result<Number, String> {
+    let total = sum(items)?;
+    let count = Number::new(Numeric::from(items.len() as i64));
+    (&total / &count).ok_or_else(|| "Division by zero".to_string())
+}
+
+/// The smallest or largest element, depending on `ordering`.
+pub fn extreme(items: &[Number], ordering: Ordering) -> Result<Number, String> {
+    let first = first(items)?;
+    items[1..].iter().try_fold(first.clone(), |best, x| {
+        if x.unit != best.unit {
+            return Err("Arguments must have matching dimensionality".to_string());
+        }
+        match x.value.partial_cmp(&best.value) {
+            Some(order) if order == ordering => Ok(x.clone()),
+            Some(_) => Ok(best),
+            None => Err("Complex numbers can't be compared".to_string()),
+        }
+    })
+}
+
+/// The Euclidean length of a vector.
+pub fn norm(items: &[Number], precision: u64) -> Result<Number, String> {
+    let squares = items
+        .iter()
+        .map(|x| {
+            let abs = Number {
+                value: x.value.abs(),
+                unit: x.unit.clone(),
+            };
+            mul(&abs, &abs)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    sum(&squares)?.root(2, precision)
+}
+
+fn check_lengths(left: &[Number], right: &[Number]) -> Result<(), String> {
+    if left.len() != right.len() {
+        Err(format!(
+            "Lists must have the same length, got {} and {}",
+            left.len(),
+            right.len()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+pub fn dot(left: &[Number], right: &[Number]) -> Result<Number, String> {
+    check_lengths(left, right)?;
+    let products = left
+        .iter()
+        .zip(right)
+        .map(|(a, b)| mul(a, b))
+        .collect::<Result<Vec<_>, _>>()?;
+    sum(&products)
+}
+
+pub fn cross(left: &[Number], right: &[Number]) -> Result<Vec<Number>, String> {
+    check_lengths(left, right)?;
+    if left.len() != 3 {
+        return Err(format!(
+            "Cross product is only defined for 3-vectors, got {} elements",
+            left.len()
+        ));
+    }
+    let term = |i: usize, j: usize| -> Result<Number, String> {
+        let pos = mul(&left[i], &right[j])?;
+        let neg = mul(&left[j], &right[i])?;
+        (&pos - &neg).ok_or_else(|| {
+            "Subtraction of units with mismatched units is not meaningful".to_string()
+        })
+    };
+    Ok(vec![term(1, 2)?, term(2, 0)?, term(0, 1)?])
+}
@@ -2,9 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::ast::{BinOpExpr, Def, DefEntry, Defs, Expr};
+use crate::ast::{BinOpExpr, Def, DefEntry, Defs, Expr, ExprString, Function};
 use crate::number::{Dimension, Number};
 use crate::numeric::Numeric;
+use crate::scale::{Scale, ScaleKind};
 use crate::substance::{Properties, Property, Substance};
 use crate::value::Value;
 use crate::Context;
@@ -108,7 +109,29 @@ impl Resolver {
                 self.eval(right);
             }
             Expr::UnaryOp(ref unaryop) => self.eval(&unaryop.expr),
+            Expr::Interval {
+                ref lower,
+                ref upper,
+            } => {
+                self.eval(lower);
+                self.eval(upper);
+            }
+            Expr::List { ref items } => {
+                for item in items {
+                    self.eval(item);
+                }
+            }
             Expr::Of { ref expr, .. } => self.eval(expr),
+            Expr::Call {
+                func: Function::Custom(ref name),
+                ref args,
+            } => {
+                let name = self.intern(name);
+                self.lookup(&name);
+                for arg in args {
+                    self.eval(arg);
+                }
+            }
 
             Expr::Mul { ref exprs }
             | Expr::Call {
@@ -135,6 +158,20 @@ impl Resolver {
                     | Def::SPrefix { ref expr }
                     | Def::Unit { ref expr }
                     | Def::Quantity { ref expr } => self.eval(expr),
+                    Def::Function(ref func) => self.eval(&func.body),
+                    Def::Scale(ref scale) => {
+                        self.eval(&scale.forward);
+                        if let Some(ref units) = scale.units {
+                            self.eval(&units.input);
+                            self.eval(&units.output);
+                        }
+                        // The inverse refers to the unit by its own name,
+                        // which isn't a cycle.
+                        self.unmarked.remove(name);
+                        self.temp_marks.remove(name);
+                        self.eval(&scale.inverse);
+                    }
+                    Def::Table(ref table) => self.eval(&table.unit),
                     Def::Canonicalization { ref of } => {
                         self.lookup(&Rc::new(of.clone()));
                     }
@@ -249,6 +286,13 @@ impl Context {
                         }
                     }
                 }
+                // Aliases of nonlinear units, like `degC °C`.
+                Def::Unit {
+                    expr: ExprString(Expr::Unit { name: ref target }),
+                } if self.scales.contains_key(target) => {
+                    let scale = self.scales[target].clone();
+                    self.scales.insert(name.clone(), scale);
+                }
                 Def::Unit { ref expr } => match self.eval(expr) {
                     Ok(Value::Number(v)) => {
                         if v.value == Numeric::one() && reverse.contains(&*name) {
@@ -400,6 +444,45 @@ impl Context {
                     self.category_names
                         .insert(name.clone(), display_name.clone());
                 }
+                Def::Function(ref func) => {
+                    self.functions.insert(name.clone(), func.clone());
+                }
+                Def::Scale(ref scale) => {
+                    self.scales.insert(
+                        name.clone(),
+                        Scale {
+                            symbol: name.clone(),
+                            kind: ScaleKind::Defined(scale.clone()),
+                        },
+                    );
+                }
+                Def::Table(ref table) => {
+                    let eval_point = |expr: &ExprString| match self.eval(expr) {
+                        Ok(Value::Number(ref v)) if v.dimless() => Ok(v.value.clone()),
+                        Ok(_) => Err(format!("{} is not a number", expr.0)),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    let points = table
+                        .points
+                        .iter()
+                        .map(|point| Ok((eval_point(&point.input)?, eval_point(&point.output)?)))
+                        .collect::<Result<Vec<_>, String>>();
+                    match points {
+                        Ok(points) => {
+                            self.scales.insert(
+                                name.clone(),
+                                Scale {
+                                    symbol: name.clone(),
+                                    kind: ScaleKind::Table {
+                                        unit: table.unit.0.clone(),
+                                        points,
+                                    },
+                                },
+                            );
+                        }
+                        Err(e) => println!("Table {} is malformed: {}", name, e),
+                    }
+                }
                 Def::Error { ref message } => println!("Def {}: {}", name, message),
             };
         }
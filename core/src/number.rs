@@ -56,12 +56,35 @@ pub fn pow(left: &Numeric, exp: i32) -> Numeric {
         &Numeric::one() / &pow(left, -exp)
     } else {
         let left = match *left {
-            Numeric::Rational(ref left) => left,
+            Numeric::Small(numer, denom) => {
+                if let (Some(numer), Some(denom)) =
+                    (numer.checked_pow(exp as u32), denom.checked_pow(exp as u32))
+                {
+                    return Numeric::Small(numer, denom);
+                }
+                BigRat::small_ratio(numer, denom)
+            }
+            Numeric::Rational(ref left) => left.clone(),
             Numeric::Float(f) => return Numeric::Float(f.powi(exp)),
+            Numeric::Symbolic(ref s) => return s.powi(exp),
+            Numeric::BigFloat(_) | Numeric::Complex(_, _) => {
+                // Exponentiation by squaring.
+                let mut result = Numeric::one();
+                let mut square = left.clone();
+                let mut exp = exp;
+                while exp > 0 {
+                    if exp & 1 == 1 {
+                        result = &result * &square;
+                    }
+                    square = &square * &square;
+                    exp >>= 1;
+                }
+                return result;
+            }
         };
         let num = left.numer().pow(exp as u32);
         let den = left.denom().pow(exp as u32);
-        Numeric::Rational(BigRat::ratio(&num, &den))
+        Numeric::from(BigRat::ratio(&num, &den))
     }
 }
 
@@ -90,6 +113,14 @@ pub struct NumberParts {
     pub dimensions: Option<String>,
     /// Map of base units and their dimensions.
     pub raw_dimensions: Option<Quantity>,
+    /// Standard uncertainty of the value, in the same units.
+    pub uncertainty: Option<String>,
+    /// How far the shown value was moved by rounding it to a fraction,
+    /// with an explicit sign, in the same units.
+    pub rounding_error: Option<String>,
+    /// Lower and upper bounds, if the number is an interval. Used in
+    /// place of the exact and approximate values.
+    pub interval: Option<(String, String)>,
 }
 
 pub struct NumberPartsFmt<'a> {
@@ -102,7 +133,8 @@ impl NumberParts {
     ///
     /// - `a`: Approximate numerical value, if exists.
     /// - `e`: Exact numerical value, if exists.
-    /// - `n`: Exact and approximate values.
+    /// - `n`: Exact and approximate values or interval bounds, and
+    ///   uncertainty or rounding error if any.
     /// - `u`: Unit.
     /// - `q`: Quantity, if exists.
     /// - `w`: Quantity in parentheses, if exists.
@@ -135,12 +167,24 @@ impl NumberParts {
                         continue;
                     }
                 }
-                'n' => match (self.exact_value.as_ref(), self.approx_value.as_ref()) {
-                    (Some(ex), Some(ap)) => write!(out, "{}, approx. {}", ex, ap).unwrap(),
-                    (Some(ex), None) => write!(out, "{}", ex).unwrap(),
-                    (None, Some(ap)) => write!(out, "approx. {}", ap).unwrap(),
-                    (None, None) => continue,
-                },
+                'n' => {
+                    if let Some((lower, upper)) = self.interval.as_ref() {
+                        write!(out, "[{}, {}]", lower, upper).unwrap();
+                    }
+                    match (self.exact_value.as_ref(), self.approx_value.as_ref()) {
+                        (Some(ex), Some(ap)) => write!(out, "{}, approx. {}", ex, ap).unwrap(),
+                        (Some(ex), None) => write!(out, "{}", ex).unwrap(),
+                        (None, Some(ap)) => write!(out, "approx. {}", ap).unwrap(),
+                        (None, None) if self.interval.is_none() => continue,
+                        (None, None) => (),
+                    }
+                    if let Some(u) = self.uncertainty.as_ref() {
+                        write!(out, " ± {}", u).unwrap();
+                    }
+                    if let Some(e) = self.rounding_error.as_ref() {
+                        write!(out, " (error {})", e).unwrap();
+                    }
+                }
                 'u' => {
                     if let Some(unit) = self.raw_unit.as_ref() {
                         if unit.is_empty() {
@@ -270,7 +314,8 @@ impl NumberParts {
     ///
     /// - `a`: Approximate numerical value, if exists.
     /// - `e`: Exact numerical value, if exists.
-    /// - `n`: Exact and approximate values.
+    /// - `n`: Exact and approximate values or interval bounds, and
+    ///   uncertainty or rounding error if any.
     /// - `u`: Unit.
     /// - `q`: Quantity, if exists.
     /// - `w`: Quantity in parentheses, if exists.
@@ -389,6 +434,13 @@ impl<'a> NumberPartsFmt<'a> {
                     }
                 }
                 PatternToken::Numeric => {
+                    if let Some((ref lower, ref upper)) = parts.interval {
+                        tokens.push(Span::plain("["));
+                        tokens.push(Span::number(lower));
+                        tokens.push(Span::plain(", "));
+                        tokens.push(Span::number(upper));
+                        tokens.push(Span::plain("]"));
+                    }
                     match (parts.exact_value.as_ref(), parts.approx_value.as_ref()) {
                         (Some(ex), Some(ap)) => {
                             tokens.push(Span::number(ex));
@@ -404,6 +456,15 @@ impl<'a> NumberPartsFmt<'a> {
                         }
                         (None, None) => (),
                     }
+                    if let Some(ref u) = parts.uncertainty {
+                        tokens.push(Span::plain(" ± "));
+                        tokens.push(Span::number(u));
+                    }
+                    if let Some(ref e) = parts.rounding_error {
+                        tokens.push(Span::plain(" (error "));
+                        tokens.push(Span::number(e));
+                        tokens.push(Span::plain(")"));
+                    }
                 }
                 PatternToken::Unit => {
                     if let Some(ref unit) = parts.raw_unit {
@@ -557,6 +618,19 @@ impl fmt::Display for NumberParts {
     }
 }
 
+/// Limits the number of factors in factorials, since the exact result
+/// grows without bound.
+const MAX_FACTORIAL: u64 = 10_000;
+
+/// Computes `n * (n-1) * ... * (n-k+1)`.
+fn falling_factorial(n: u64, k: u64, what: &str) -> Result<Numeric, String> {
+    if k > MAX_FACTORIAL {
+        return Err(format!("{} is limited to {} factors", what, MAX_FACTORIAL));
+    }
+    let product = (n - k + 1..=n).fold(BigInt::one(), |acc, x| &acc * &BigInt::from(x));
+    Ok(Numeric::from(product))
+}
+
 impl Number {
     pub fn one() -> Number {
         Number {
@@ -625,7 +699,7 @@ impl Number {
             BigRat::one()
         };
         let num = &BigRat::ratio(&num, &BigInt::one()) + &frac;
-        Ok(Numeric::Rational(&num * &exp))
+        Ok(Numeric::from(&num * &exp))
     }
 
     /// Computes the reciprocal (1/x) of the value.
@@ -654,11 +728,9 @@ impl Number {
     }
 
     /// Computes the nth root of a value iff all of its units have
-    /// powers divisible by n.
-    pub fn root(&self, exp: i32) -> Result<Number, String> {
-        if self.value < Numeric::zero() {
-            return Err("Complex numbers are not implemented".to_string());
-        }
+    /// powers divisible by n. Inexact roots are computed to the given
+    /// number of bits.
+    pub fn root(&self, exp: i32, precision: u64) -> Result<Number, String> {
         let mut res = Quantity::new();
         for (dim, &power) in &self.unit {
             if power % exp as i64 != 0 {
@@ -668,15 +740,26 @@ impl Number {
             }
         }
         Ok(Number {
-            value: Numeric::Float(self.value.to_f64().powf(1.0 / exp as f64)),
+            value: self.value.root(exp as u32, precision),
             unit: res,
         })
     }
 
-    pub fn pow(&self, exp: &Number) -> Result<Number, String> {
+    pub fn pow(&self, exp: &Number, precision: u64) -> Result<Number, String> {
         if !exp.dimless() {
             return Err("Exponent must be dimensionless".to_string());
         }
+        if exp.value.is_complex() {
+            return if self.dimless() {
+                // a^b = e^(b ln a)
+                Ok(Number {
+                    value: (&exp.value * &self.value.ln(precision)).exp(precision),
+                    unit: self.unit.clone(),
+                })
+            } else {
+                Err("Complex exponents require a dimensionless base".to_string())
+            };
+        }
         if exp.value.abs() >= Numeric::from(1 << 31) {
             return Err("Exponent is too large".to_string());
         }
@@ -687,18 +770,141 @@ impl Number {
             Ok(self.powi(exp.unwrap() as i32))
         } else if num == one {
             let exp: Option<i64> = den.as_int();
-            self.root(exp.unwrap() as i32)
+            self.root(exp.unwrap() as i32, precision)
         } else if !self.dimless() {
             Err("Exponentiation must result in integer dimensions".to_string())
         } else {
-            let exp = exp.value.to_f64();
             Ok(Number {
-                value: Numeric::Float(self.value.to_f64().powf(exp)),
+                value: self.value.pow_real(&exp.value, precision),
                 unit: self.unit.clone(),
             })
         }
     }
 
+    fn check_units(&self, other: &Number) -> Result<(), String> {
+        if self.unit != other.unit {
+            Err("Arguments must have matching dimensionality".to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Rounds to a whole multiple of `step`, which must have the same
+    /// units, or else to a whole number of base units.
+    pub fn round(&self, step: Option<&Number>, mode: Rounding) -> Result<Number, String> {
+        let step = match step {
+            Some(step) => {
+                self.check_units(step)?;
+                if step.value == Numeric::zero() {
+                    return Err("Step must not be zero".to_string());
+                }
+                step.value.clone()
+            }
+            None => Numeric::one(),
+        };
+        Ok(Number {
+            value: &(&self.value / &step).round(mode) * &step,
+            unit: self.unit.clone(),
+        })
+    }
+
+    /// The largest quantity that both are whole multiples of.
+    pub fn gcd(&self, other: &Number) -> Result<Number, String> {
+        self.check_units(other)?;
+        let value = self
+            .value
+            .gcd(&other.value)
+            .ok_or_else(|| "Arguments must be exact rational numbers".to_string())?;
+        Ok(Number {
+            value,
+            unit: self.unit.clone(),
+        })
+    }
+
+    /// The smallest quantity that is a whole multiple of both.
+    pub fn lcm(&self, other: &Number) -> Result<Number, String> {
+        let gcd = self.gcd(other)?;
+        if gcd.value == Numeric::zero() {
+            return Ok(gcd);
+        }
+        Ok(Number {
+            value: &(&self.value * &other.value).abs() / &gcd.value,
+            unit: self.unit.clone(),
+        })
+    }
+
+    /// The remainder after division by `other`, which takes the sign of
+    /// `other`, so that `mod(-7, 3)` is 2.
+    pub fn modulo(&self, other: &Number) -> Result<Number, String> {
+        self.check_units(other)?;
+        if other.value == Numeric::zero() {
+            return Err("Division by zero".to_string());
+        }
+        let quotient = (&self.value / &other.value).round(Rounding::Floor);
+        Ok(Number {
+            value: &self.value - &(&quotient * &other.value),
+            unit: self.unit.clone(),
+        })
+    }
+
+    /// The quotient rounded towards negative infinity, so that
+    /// `a // b * b + a mod b` is `a`.
+    pub fn floor_div(&self, other: &Number) -> Result<Number, String> {
+        let quotient = (self / other).ok_or_else(|| "Division by zero".to_string())?;
+        Ok(Number {
+            value: quotient.value.round(Rounding::Floor),
+            unit: quotient.unit,
+        })
+    }
+
+    fn to_count(&self, what: &str) -> Result<u64, String> {
+        let count = match self.value.to_int() {
+            Some(count)
+                if self.dimless()
+                    && count >= 0
+                    && self.value.round(Rounding::Trunc) == self.value =>
+            {
+                count as u64
+            }
+            _ => {
+                return Err(format!(
+                    "{} is only defined for non-negative integers",
+                    what
+                ))
+            }
+        };
+        Ok(count)
+    }
+
+    pub fn factorial(&self) -> Result<Number, String> {
+        let n = self.to_count("Factorial")?;
+        falling_factorial(n, n, "Factorial").map(Number::new)
+    }
+
+    /// The number of ways to choose `other` items out of `self`,
+    /// ignoring order.
+    pub fn choose(&self, other: &Number) -> Result<Number, String> {
+        let n = self.to_count("nCr")?;
+        let k = other.to_count("nCr")?;
+        if k > n {
+            return Ok(Number::new(Numeric::zero()));
+        }
+        let k = k.min(n - k);
+        let numer = falling_factorial(n, k, "nCr")?;
+        let denom = falling_factorial(k, k, "nCr")?;
+        Ok(Number::new(&numer / &denom))
+    }
+
+    /// The number of ordered arrangements of `other` items out of `self`.
+    pub fn permute(&self, other: &Number) -> Result<Number, String> {
+        let n = self.to_count("nPr")?;
+        let k = other.to_count("nPr")?;
+        if k > n {
+            return Ok(Number::new(Numeric::zero()));
+        }
+        falling_factorial(n, k, "nPr").map(Number::new)
+    }
+
     pub fn numeric_value(&self, base: u8, digits: Digits) -> (Option<String>, Option<String>) {
         self.value.string_repr(base, digits)
     }
@@ -844,7 +1050,7 @@ impl Number {
         String::from_utf8(out).unwrap()
     }
 
-    fn pretty_unit(&self, context: &Context) -> Quantity {
+    pub(crate) fn pretty_unit(&self, context: &Context) -> Quantity {
         let pretty = crate::factorize::fast_decompose(self, &context.reverse);
         pretty
             .into_iter()
@@ -947,7 +1153,7 @@ impl<'a, 'b> Div<&'b Number> for &'a Number {
 
     #[allow(clippy::suspicious_arithmetic_impl)]
     fn div(self, other: &Number) -> Self::Output {
-        if other.value == Numeric::zero() || other.value == Numeric::Float(0.0) {
+        if other.value.is_zero() {
             None
         } else {
             self * &other.invert()
@@ -5,27 +5,208 @@
 use std::cmp::{Ordering, PartialOrd};
 use std::ops::{Add, Div, Mul, Neg, Sub};
 
+use num::bigint::BigInt as NumInt;
+use num::rational::BigRational as NumRat;
+use std::convert::TryFrom;
+
+use crate::bigfloat::{BigFloat, DEFAULT_DIGITS};
 use crate::bigint::BigInt;
 use crate::bigrat::BigRat;
+use crate::symbolic::Symbolic;
 
 /// Number type.
 #[derive(Clone, PartialEq, Debug, Serialize)]
 #[serde(into = "NumericParts")]
 pub enum Numeric {
+    /// Machine-sized rational fraction, kept in lowest terms with a
+    /// positive denominator. Rationals that fit are always stored this
+    /// way, and arithmetic that overflows is promoted to `Rational`.
+    Small(i64, i64),
     /// Arbitrary-precision rational fraction.
     Rational(BigRat),
     /// Machine floats.
     Float(f64),
-    // /// Machine ints.
-    // Int(i64),
+    /// Arbitrary-precision binary floats, produced by functions like
+    /// `sqrt` and `sin` when given exact inputs.
+    BigFloat(Box<BigFloat>),
+    /// Exact multiples of square roots and powers of pi, like
+    /// `sqrt(2)/2` and `3 pi`.
+    Symbolic(Box<Symbolic>),
+    /// Complex number made of a real and an imaginary part, neither of
+    /// which is complex. The imaginary part is never zero; use
+    /// `Numeric::complex` to build one.
+    Complex(Box<Numeric>, Box<Numeric>),
 }
 
 /// Parity represents the result of coercing a pair of `Numeric`s into
 /// having the same underlying representation. This is done calling
 /// `Numeric::parity`.
 enum Parity {
+    /// Numerators and denominators of the left, then of the right.
+    Small(i64, i64, i64, i64),
     Rational(BigRat, BigRat),
     Float(f64, f64),
+    BigFloat(BigFloat, BigFloat),
+    Symbolic(Symbolic, Symbolic),
+    /// Real and imaginary parts of the left, then of the right.
+    Complex(Numeric, Numeric, Numeric, Numeric),
+}
+
+/// The number of bits that symbolic numbers are approximated to when
+/// they have to be compared or rounded.
+fn default_bits() -> u64 {
+    BigFloat::bits_for_digits(DEFAULT_DIGITS)
+}
+
+/// Builds a rational from a fraction computed in `i128`, reducing it
+/// and promoting it to a `BigRat` if it doesn't fit in an `i64`.
+fn small_ratio(numer: i128, denom: i128) -> Numeric {
+    // Same as BigRat.
+    assert!(denom != 0, "denominator == 0");
+    let (mut a, mut b) = (numer.abs(), denom.abs());
+    while b != 0 {
+        let t = a % b;
+        a = b;
+        b = t;
+    }
+    let sign = if denom < 0 { -1 } else { 1 };
+    let (numer, denom) = (sign * numer / a, sign * denom / a);
+    match (i64::try_from(numer), i64::try_from(denom)) {
+        (Ok(numer), Ok(denom)) => Numeric::Small(numer, denom),
+        _ => Numeric::Rational(BigRat::from(NumRat::new_raw(
+            NumInt::from(numer),
+            NumInt::from(denom),
+        ))),
+    }
+}
+
+/// `base^exp` as a fraction.
+fn pow_in_base(base: u8, exp: i64) -> BigRat {
+    let power = BigInt::from(base as u64).pow(exp.unsigned_abs() as u32);
+    if exp < 0 {
+        BigRat::ratio(&BigInt::one(), &power)
+    } else {
+        BigRat::ratio(&power, &BigInt::one())
+    }
+}
+
+/// Finds `exp` such that `base^exp <= value < base^(exp + 1)`, for a
+/// positive value.
+fn exponent_in_base(value: &BigRat, base: u8) -> i64 {
+    // Starts from an estimate that can be off by one.
+    let int = value.trunc();
+    let mut exp = if int != BigRat::zero() {
+        int.numer().size_in_base(base) as i64 - 1
+    } else {
+        let recip = &BigRat::one() / value;
+        -(recip.trunc().numer().size_in_base(base) as i64)
+    };
+    while pow_in_base(base, exp) > *value {
+        exp -= 1;
+    }
+    while pow_in_base(base, exp + 1) <= *value {
+        exp += 1;
+    }
+    exp
+}
+
+/// Formats a non-negative number rounded to `figs` significant
+/// figures, with ties rounded away from zero. Returns (is_exact, repr).
+fn sig_figs_string(value: &BigRat, base: u8, figs: u64) -> (bool, String) {
+    if *value == BigRat::zero() {
+        return (true, "0".to_owned());
+    }
+    let pow = |exp: i64| pow_in_base(base, exp);
+    let mut exp = exponent_in_base(value, base);
+    let figs = figs as i64;
+    let ulp = pow(exp + 1 - figs);
+    let mut mantissa = (value / &ulp).round();
+    let exact = &mantissa * &ulp == *value;
+    // Rounding up can carry into another digit, as in 9.99 -> 10.0.
+    if mantissa == pow(figs) {
+        mantissa = pow(figs - 1);
+        exp += 1;
+    }
+    let digits = mantissa.numer().inner().to_str_radix(base as u32);
+
+    let leading = if exp < 0 { -exp - 1 } else { exp + 1 };
+    let repr = if leading > 9 * 10 / base as i64 {
+        // scientific notation
+        let (first, rest) = digits.split_at(1);
+        if rest.is_empty() {
+            format!("{}e{}", first, exp)
+        } else {
+            format!("{}.{}e{}", first, rest, exp)
+        }
+    } else if exp < 0 {
+        format!("0.{}{}", "0".repeat(leading as usize), digits)
+    } else if exp + 1 >= figs {
+        format!("{}{}", digits, "0".repeat((exp + 1 - figs) as usize))
+    } else {
+        let (int, frac) = digits.split_at(exp as usize + 1);
+        format!("{}.{}", int, frac)
+    };
+    (exact, repr)
+}
+
+/// The largest denominator picked for `Digits::Fraction(None)` when
+/// the number isn't already a fraction. Matches the largest fraction
+/// `string_repr` will show.
+const MAX_FRACTION_DENOM: u64 = 1000;
+
+/// Finds the fraction closest to `value` with a denominator of at
+/// most `max_denom`, using the convergents of its continued fraction.
+fn best_approximation(value: &BigRat, max_denom: u64) -> BigRat {
+    let max_denom = BigInt::from(max_denom);
+    let (mut p0, mut q0) = (BigInt::zero(), BigInt::one());
+    let (mut p1, mut q1) = (BigInt::one(), BigInt::zero());
+    let mut rest = value.clone();
+    loop {
+        let term = rest.floor();
+        let a = term.numer();
+        let q2 = &q0 + &(&a * &q1);
+        if q2 > max_denom {
+            break;
+        }
+        let p2 = &p0 + &(&a * &p1);
+        p0 = std::mem::replace(&mut p1, p2);
+        q0 = std::mem::replace(&mut q1, q2);
+        let frac = &rest - &term;
+        if frac == BigRat::zero() {
+            return BigRat::ratio(&p1, &q1);
+        }
+        rest = &BigRat::one() / &frac;
+    }
+    // The best approximation can also be a semiconvergent, which
+    // takes as large a partial step towards the next convergent as
+    // the denominator allows.
+    let k = &(&max_denom - &q0) / &q1;
+    let best = BigRat::ratio(&p1, &q1);
+    let semi = BigRat::ratio(&(&p0 + &(&k * &p1)), &(&q0 + &(&k * &q1)));
+    if (&semi - value).abs() < (&best - value).abs() {
+        semi
+    } else {
+        best
+    }
+}
+
+/// Writes a non-negative fraction as a mixed number, like `3 3/16`.
+fn mixed_number_string(value: &BigRat, base: u8) -> String {
+    let radix = |int: BigInt| int.inner().to_str_radix(base as u32);
+    let whole = value.trunc();
+    let frac = value - &whole;
+    if frac == BigRat::zero() {
+        radix(whole.numer())
+    } else if whole == BigRat::zero() {
+        format!("{}/{}", radix(frac.numer()), radix(frac.denom()))
+    } else {
+        format!(
+            "{} {}/{}",
+            radix(whole.numer()),
+            radix(frac.numer()),
+            radix(frac.denom())
+        )
+    }
 }
 
 /// Used when converting to string representation to choose desired
@@ -35,6 +216,21 @@ pub enum Digits {
     Default,
     FullInt,
     Digits(u64),
+    /// Rounded to a number of significant figures.
+    SigFigs(u64),
+    /// Written as a mixed number like `3 3/16`, rounded to the nearest
+    /// multiple of one over the given denominator if there is one.
+    Fraction(Option<u64>),
+}
+
+/// How `Numeric::round` picks a whole number.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Rounding {
+    Floor,
+    Ceil,
+    /// Ties are rounded away from zero.
+    Nearest,
+    Trunc,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -48,64 +244,483 @@ pub struct NumericParts {
 
 impl Numeric {
     pub fn one() -> Numeric {
-        Numeric::Rational(BigRat::one())
-        //Num::Int(1)
+        Numeric::Small(1, 1)
     }
 
     pub fn zero() -> Numeric {
-        Numeric::Rational(BigRat::zero())
-        //Num::Int(0)
+        Numeric::Small(0, 1)
+    }
+
+    /// Whether the number is an exact rational, however it's stored.
+    pub fn is_rational(&self) -> bool {
+        match *self {
+            Numeric::Small(_, _) | Numeric::Rational(_) => true,
+            _ => false,
+        }
+    }
+
+    /// The exact value of the number, if it's rational.
+    pub fn to_big_rat(&self) -> Option<BigRat> {
+        match *self {
+            Numeric::Small(numer, denom) => Some(BigRat::small_ratio(numer, denom)),
+            Numeric::Rational(ref rational) => Some(rational.clone()),
+            _ => None,
+        }
+    }
+
+    /// Builds a complex number, which is simplified to a real number if
+    /// the imaginary part is zero.
+    pub fn complex(re: Numeric, im: Numeric) -> Numeric {
+        if im.is_zero() {
+            re
+        } else {
+            Numeric::Complex(Box::new(re), Box::new(im))
+        }
+    }
+
+    /// The imaginary unit.
+    pub fn i() -> Numeric {
+        Numeric::complex(Numeric::zero(), Numeric::one())
+    }
+
+    /// Builds a complex number from its magnitude and angle. Parts
+    /// which are only non-zero because of rounding error are dropped,
+    /// so that `exp(i pi)` is -1.
+    pub fn from_polar(r: f64, theta: f64) -> Numeric {
+        let cleanup = |x: f64| {
+            if x.abs() <= r.abs() * 4.0 * f64::EPSILON {
+                Numeric::zero()
+            } else {
+                Numeric::Float(x)
+            }
+        };
+        Numeric::complex(cleanup(r * theta.cos()), cleanup(r * theta.sin()))
+    }
+
+    pub fn is_zero(&self) -> bool {
+        match *self {
+            Numeric::Small(numer, _) => numer == 0,
+            Numeric::Rational(ref rational) => *rational == BigRat::zero(),
+            Numeric::Float(f) => f == 0.0,
+            Numeric::BigFloat(ref f) => f.is_zero(),
+            Numeric::Symbolic(_) | Numeric::Complex(_, _) => false,
+        }
+    }
+
+    /// Whether the number is neither infinite nor NaN, which only
+    /// machine floats can be.
+    pub fn is_finite(&self) -> bool {
+        match *self {
+            Numeric::Float(f) => f.is_finite(),
+            Numeric::Complex(ref re, ref im) => re.is_finite() && im.is_finite(),
+            _ => true,
+        }
+    }
+
+    pub fn is_complex(&self) -> bool {
+        match *self {
+            Numeric::Complex(_, _) => true,
+            _ => false,
+        }
+    }
+
+    /// The real part of the number.
+    pub fn re(&self) -> Numeric {
+        match *self {
+            Numeric::Complex(ref re, _) => (**re).clone(),
+            ref real => real.clone(),
+        }
+    }
+
+    /// The imaginary part of the number, which is zero for real
+    /// numbers.
+    pub fn im(&self) -> Numeric {
+        match *self {
+            Numeric::Complex(_, ref im) => (**im).clone(),
+            _ => Numeric::zero(),
+        }
+    }
+
+    /// The complex conjugate.
+    pub fn conj(&self) -> Numeric {
+        match *self {
+            Numeric::Complex(ref re, ref im) => Numeric::complex((**re).clone(), -&**im),
+            ref real => real.clone(),
+        }
+    }
+
+    /// The angle of the number in the complex plane, in radians.
+    pub fn arg(&self) -> Numeric {
+        match *self {
+            Numeric::Complex(ref re, ref im) => Numeric::Float(im.to_f64().atan2(re.to_f64())),
+            ref real if *real < Numeric::zero() => Numeric::Float(std::f64::consts::PI),
+            _ => Numeric::zero(),
+        }
     }
 
     pub fn abs(&self) -> Numeric {
         match *self {
+            Numeric::Small(numer, denom) => small_ratio(i128::from(numer).abs(), i128::from(denom)),
             Numeric::Rational(ref rational) => Numeric::Rational(rational.abs()),
             Numeric::Float(f) => Numeric::Float(f.abs()),
+            Numeric::BigFloat(ref f) => Numeric::BigFloat(Box::new(f.abs())),
+            Numeric::Symbolic(ref s) => Numeric::Symbolic(Box::new(s.abs())),
+            Numeric::Complex(ref re, ref im) => Numeric::Float(re.to_f64().hypot(im.to_f64())),
+        }
+    }
+
+    /// Rounds to a whole number. Complex numbers have both parts
+    /// rounded.
+    pub fn round(&self, mode: Rounding) -> Numeric {
+        let round_rational = |rational: &BigRat| match mode {
+            Rounding::Floor => rational.floor(),
+            Rounding::Ceil => rational.ceil(),
+            Rounding::Nearest => rational.round(),
+            Rounding::Trunc => rational.trunc(),
+        };
+        match *self {
+            Numeric::Small(_, 1) => self.clone(),
+            Numeric::Small(numer, denom) => {
+                Numeric::from(round_rational(&BigRat::small_ratio(numer, denom)))
+            }
+            Numeric::Rational(ref rational) => Numeric::from(round_rational(rational)),
+            Numeric::Float(f) => Numeric::Float(match mode {
+                Rounding::Floor => f.floor(),
+                Rounding::Ceil => f.ceil(),
+                Rounding::Nearest => f.round(),
+                Rounding::Trunc => f.trunc(),
+            }),
+            Numeric::BigFloat(ref f) => Numeric::BigFloat(Box::new(BigFloat::from_rat(
+                &round_rational(&f.to_rat()),
+                f.precision(),
+            ))),
+            Numeric::Symbolic(ref s) => {
+                Numeric::from(round_rational(&s.to_big_float(default_bits()).to_rat()))
+            }
+            Numeric::Complex(ref re, ref im) => Numeric::complex(re.round(mode), im.round(mode)),
+        }
+    }
+
+    /// Rounds to the nearest multiple of `1/denom`, with ties rounded
+    /// away from zero. Without a denominator, exact fractions are kept
+    /// as they are, and anything else becomes the closest fraction
+    /// with a small denominator. Only the real part of complex numbers
+    /// is used.
+    pub fn round_to_fraction(&self, denom: Option<u64>) -> Numeric {
+        let (numer, den) = self.to_rational();
+        let value = BigRat::ratio(&numer, &den);
+        match denom {
+            Some(denom) => {
+                let denom = BigRat::ratio(&BigInt::from(denom), &BigInt::one());
+                Numeric::from(&(&value * &denom).round() / &denom)
+            }
+            None if self.is_rational() => self.clone(),
+            None => self.closest_fraction(MAX_FRACTION_DENOM),
+        }
+    }
+
+    /// The fraction closest to the number with a denominator of at
+    /// most `max_denom`. Only the real part of complex numbers is used.
+    pub fn closest_fraction(&self, max_denom: u64) -> Numeric {
+        let (numer, denom) = self.to_rational();
+        Numeric::from(best_approximation(
+            &BigRat::ratio(&numer, &denom),
+            max_denom,
+        ))
+    }
+
+    /// The terms of the continued fraction of the number's exact value,
+    /// like `[3, 7, 16]` for 355/113, with at most `max_terms` of them.
+    /// Also returns whether any were cut off. Only the real part of
+    /// complex numbers is used.
+    pub fn continued_fraction(&self, max_terms: usize) -> (Vec<BigInt>, bool) {
+        let (numer, denom) = self.to_rational();
+        let mut rest = BigRat::ratio(&numer, &denom);
+        let mut terms = vec![];
+        loop {
+            if terms.len() == max_terms {
+                return (terms, true);
+            }
+            let term = rest.floor();
+            let frac = &rest - &term;
+            terms.push(term.numer());
+            if frac == BigRat::zero() {
+                return (terms, false);
+            }
+            rest = &BigRat::one() / &frac;
+        }
+    }
+
+    /// How far `rounded` is from the number, with an explicit sign, as
+    /// in `+0.0175`. `None` if they're the same.
+    pub fn rounding_error_string(&self, rounded: &Numeric, base: u8) -> Option<String> {
+        if self.is_complex() {
+            return None;
+        }
+        let error = rounded - self;
+        if error.is_zero() {
+            return None;
+        }
+        let (_exact, repr) = error.to_string(base, Digits::Default);
+        if error < Numeric::zero() {
+            Some(repr)
+        } else {
+            Some(format!("+{}", repr))
+        }
+    }
+
+    /// The largest number that both numbers are whole multiples of.
+    /// Only exact rationals have one.
+    pub fn gcd(&self, other: &Numeric) -> Option<Numeric> {
+        match (self.to_big_rat(), other.to_big_rat()) {
+            (Some(left), Some(right)) => {
+                let numer = (&left.numer() * &right.denom()).gcd(&(&right.numer() * &left.denom()));
+                let denom = &left.denom() * &right.denom();
+                Some(Numeric::from(BigRat::ratio(&numer, &denom)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Converts to a big float with the given number of bits, unless
+    /// the number is a machine float or complex.
+    fn to_big_float(&self, precision: u64) -> Option<BigFloat> {
+        match *self {
+            Numeric::Small(numer, denom) => Some(BigFloat::from_rat(
+                &BigRat::small_ratio(numer, denom),
+                precision,
+            )),
+            Numeric::Rational(ref rational) => Some(BigFloat::from_rat(rational, precision)),
+            Numeric::BigFloat(ref f) => Some((**f).clone()),
+            Numeric::Symbolic(ref s) => Some(s.to_big_float(precision)),
+            _ => None,
+        }
+    }
+
+    /// Evaluates a real function to the given number of bits. Machine
+    /// floats, and inputs the big float version rejects (like the log
+    /// of zero), use the `f64` version instead so that they give NaN or
+    /// infinity.
+    pub fn real_func(
+        &self,
+        precision: u64,
+        big: impl Fn(&BigFloat, u64) -> Option<BigFloat>,
+        float: impl Fn(f64) -> f64,
+    ) -> Numeric {
+        match self
+            .to_big_float(precision)
+            .and_then(|x| big(&x, precision))
+        {
+            Some(result) => Numeric::BigFloat(Box::new(result)),
+            None => Numeric::Float(float(self.to_f64())),
+        }
+    }
+
+    /// Like `real_func`, for functions of two arguments.
+    pub fn real_func2(
+        &self,
+        other: &Numeric,
+        precision: u64,
+        big: impl Fn(&BigFloat, &BigFloat, u64) -> Option<BigFloat>,
+        float: impl Fn(f64, f64) -> f64,
+    ) -> Numeric {
+        let result = match (self.to_big_float(precision), other.to_big_float(precision)) {
+            (Some(x), Some(y)) => big(&x, &y, precision),
+            _ => None,
+        };
+        match result {
+            Some(result) => Numeric::BigFloat(Box::new(result)),
+            None => Numeric::Float(float(self.to_f64(), other.to_f64())),
+        }
+    }
+
+    /// Computes `e^x` to the given number of bits.
+    pub fn exp(&self, precision: u64) -> Numeric {
+        match *self {
+            Numeric::Complex(ref re, ref im) => Numeric::from_polar(re.to_f64().exp(), im.to_f64()),
+            ref real => real.real_func(precision, BigFloat::exp, f64::exp),
+        }
+    }
+
+    /// Computes the principal natural logarithm to the given number of
+    /// bits, which is complex for negative numbers.
+    pub fn ln(&self, precision: u64) -> Numeric {
+        match *self {
+            Numeric::Complex(_, _) => {
+                Numeric::complex(Numeric::Float(self.abs().to_f64().ln()), self.arg())
+            }
+            ref real if *real < Numeric::zero() => Numeric::complex(
+                (-real).real_func(precision, BigFloat::ln, f64::ln),
+                Numeric::Float(std::f64::consts::PI),
+            ),
+            ref real => real.real_func(precision, BigFloat::ln, f64::ln),
         }
     }
 
+    /// Computes the nth root to the given number of bits, giving the
+    /// principal value for negative and complex numbers.
+    pub fn root(&self, n: u32, precision: u64) -> Numeric {
+        let exact = match *self {
+            Numeric::Symbolic(ref s) if !s.is_negative() => s.root(n),
+            ref rational if *rational >= Numeric::zero() => rational
+                .to_big_rat()
+                .and_then(|rational| Symbolic::root_rational(&rational, n)),
+            _ => None,
+        };
+        if let Some(exact) = exact {
+            exact
+        } else if *self >= Numeric::zero() {
+            self.real_func(precision, |x, p| x.root(n, p), |x| x.powf(1.0 / n as f64))
+        } else {
+            self.powf(1.0 / n as f64)
+        }
+    }
+
+    /// Raises the number to a real power, to the given number of bits
+    /// for positive bases.
+    pub fn pow_real(&self, exp: &Numeric, precision: u64) -> Numeric {
+        if *self > Numeric::zero() && !exp.is_complex() {
+            self.real_func2(exp, precision, BigFloat::pow, f64::powf)
+        } else {
+            self.powf(exp.to_f64())
+        }
+    }
+
+    /// Raises the number to a real power, giving the principal value
+    /// for negative and complex numbers.
+    pub fn powf(&self, exp: f64) -> Numeric {
+        match *self {
+            Numeric::Complex(_, _) => {
+                Numeric::from_polar(self.abs().to_f64().powf(exp), self.arg().to_f64() * exp)
+            }
+            ref real if *real < Numeric::zero() => {
+                // Avoid rounding error in the real part of square roots.
+                if exp == 0.5 {
+                    Numeric::complex(Numeric::zero(), Numeric::Float((-real.to_f64()).sqrt()))
+                } else {
+                    Numeric::from_polar((-real.to_f64()).powf(exp), std::f64::consts::PI * exp)
+                }
+            }
+            ref real => Numeric::Float(real.to_f64().powf(exp)),
+        }
+    }
+
+    /// Splits a number into its real and imaginary parts.
+    fn parts(&self) -> (Numeric, Numeric) {
+        (self.re(), self.im())
+    }
+
     /// Converts a pair of numbers to have the same underlying
-    /// representation. If either is a float, both will become floats.
-    /// If both are rationals, then they are returned as is.
+    /// representation. If either is complex, both are split into their
+    /// real and imaginary parts. Otherwise, if either is a float, both
+    /// will become floats. If both are small rationals, then they are
+    /// returned as is, other rationals are promoted to `BigRat`, and if
+    /// either is symbolic then both are.
     fn parity(&self, other: &Numeric) -> Parity {
         match (self, other) {
+            (&Numeric::Complex(_, _), _) | (_, &Numeric::Complex(_, _)) => {
+                let (a, b) = self.parts();
+                let (c, d) = other.parts();
+                Parity::Complex(a, b, c, d)
+            }
             (&Numeric::Float(left), right) => Parity::Float(left, right.into()),
             (left, &Numeric::Float(right)) => Parity::Float(left.into(), right),
-            (&Numeric::Rational(ref left), &Numeric::Rational(ref right)) => {
-                Parity::Rational(left.clone(), right.clone())
+            (Numeric::BigFloat(left), right) => {
+                Parity::BigFloat((**left).clone(), right.big_float_like(left))
+            }
+            (left, Numeric::BigFloat(right)) => {
+                Parity::BigFloat(left.big_float_like(right), (**right).clone())
             }
+            (&Numeric::Small(a, b), &Numeric::Small(c, d)) => Parity::Small(a, b, c, d),
+            (left, right) if left.is_rational() && right.is_rational() => {
+                Parity::Rational(left.to_big_rat().unwrap(), right.to_big_rat().unwrap())
+            }
+            (left, right) => Parity::Symbolic(left.to_symbolic(), right.to_symbolic()),
+        }
+    }
+
+    /// Converts a real number to a big float with the same precision as
+    /// `other`.
+    fn big_float_like(&self, other: &BigFloat) -> BigFloat {
+        self.to_big_float(other.precision())
+            .expect("Only real numbers have a big float parity")
+    }
+
+    /// Converts an exact real number to a symbolic one.
+    fn to_symbolic(&self) -> Symbolic {
+        match *self {
+            Numeric::Small(numer, denom) => Symbolic::from(BigRat::small_ratio(numer, denom)),
+            Numeric::Rational(ref rational) => Symbolic::from(rational.clone()),
+            Numeric::Symbolic(ref s) => (**s).clone(),
+            _ => unreachable!("Only exact numbers have a symbolic parity"),
         }
     }
 
     pub fn div_rem(&self, other: &Numeric) -> (Numeric, Numeric) {
         match self.parity(other) {
+            Parity::Small(a, b, c, d) => {
+                // a/b = q (c/d) + r, where q = trunc(ad / bc).
+                let (a, b, c, d) = (a as i128, b as i128, c as i128, d as i128);
+                let div = (a * d) / (b * c);
+                (small_ratio(div, 1), small_ratio(a * d - div * b * c, b * d))
+            }
             Parity::Rational(left, right) => {
                 let div = &left / &right;
                 let floor = &div.numer() / &div.denom();
                 let rem = &left - &(&right * &BigRat::ratio(&floor, &BigInt::one()));
                 (
-                    Numeric::Rational(BigRat::ratio(&floor, &BigInt::one())),
-                    Numeric::Rational(rem),
+                    Numeric::from(BigRat::ratio(&floor, &BigInt::one())),
+                    Numeric::from(rem),
                 )
             }
             Parity::Float(left, right) => {
                 (Numeric::Float(left / right), Numeric::Float(left % right))
             }
+            Parity::BigFloat(left, right) => {
+                let precision = std::cmp::max(left.precision(), right.precision());
+                let (left, right) = (left.to_rat(), right.to_rat());
+                let whole = (&left / &right).trunc();
+                let rem = &left - &(&right * &whole);
+                (
+                    Numeric::from(whole),
+                    Numeric::BigFloat(Box::new(BigFloat::from_rat(&rem, precision))),
+                )
+            }
+            Parity::Symbolic(left, right) => {
+                let precision = default_bits();
+                Numeric::BigFloat(Box::new(left.to_big_float(precision)))
+                    .div_rem(&Numeric::BigFloat(Box::new(right.to_big_float(precision))))
+            }
+            // Complex numbers aren't ordered, so there is no remainder.
+            Parity::Complex(_, _, _, _) => (self / other, Numeric::zero()),
         }
     }
 
+    /// Converts the number to a fraction. Only the real part of complex
+    /// numbers is used.
     pub fn to_rational(&self) -> (BigInt, BigInt) {
         match *self {
+            Numeric::Small(numer, denom) => (BigInt::from(numer), BigInt::from(denom)),
             Numeric::Rational(ref rational) => (rational.numer(), rational.denom()),
             Numeric::Float(x) => {
                 let rational = BigRat::from(x);
                 (rational.numer(), rational.denom())
             }
+            Numeric::BigFloat(ref f) => {
+                let rational = f.to_rat();
+                (rational.numer(), rational.denom())
+            }
+            Numeric::Symbolic(ref s) => {
+                let rational = s.to_big_float(default_bits()).to_rat();
+                (rational.numer(), rational.denom())
+            }
+            Numeric::Complex(ref re, _) => re.to_rational(),
         }
     }
 
     pub fn to_int(&self) -> Option<i64> {
         match *self {
+            Numeric::Small(numer, denom) => Some(numer / denom),
             Numeric::Rational(ref rational) => (&rational.numer() / &rational.denom()).as_int(),
             Numeric::Float(f) => {
                 if !f.is_nan() && !f.is_infinite() && f.abs() < i64::max_value() as f64 {
@@ -114,6 +729,12 @@ impl Numeric {
                     None
                 }
             }
+            Numeric::BigFloat(ref f) => (&f.to_rat().numer() / &f.to_rat().denom()).as_int(),
+            Numeric::Symbolic(ref s) => {
+                let rational = s.to_big_float(default_bits()).to_rat();
+                (&rational.numer() / &rational.denom()).as_int()
+            }
+            Numeric::Complex(_, _) => None,
         }
     }
 
@@ -126,6 +747,20 @@ impl Numeric {
         use std::char::from_digit;
         use std::num::FpCategory;
 
+        if let Numeric::Complex(ref re, ref im) = *self {
+            let negative = **im < Numeric::zero();
+            let (re_exact, re) = re.to_string(base, digits);
+            let (im_exact, im) = im.abs().to_string(base, digits);
+            let im = if im == "1" { String::new() } else { im };
+            let repr = match (re == "0", negative) {
+                (true, false) => format!("{}i", im),
+                (true, true) => format!("-{}i", im),
+                (false, false) => format!("{} + {}i", re, im),
+                (false, true) => format!("{} - {}i", re, im),
+            };
+            return (re_exact && im_exact, repr);
+        }
+
         if let Numeric::Float(value) = *self {
             match value.classify() {
                 FpCategory::Nan => return (false, "NaN".to_owned()),
@@ -137,13 +772,44 @@ impl Numeric {
             }
         }
 
+        if let Digits::Fraction(denom) = digits {
+            let rounded = self.round_to_fraction(denom);
+            let exact = self.is_rational() && rounded == *self;
+            let (numer, den) = rounded.abs().to_rational();
+            let repr = mixed_number_string(&BigRat::ratio(&numer, &den), base);
+            return (
+                exact,
+                if rounded < Numeric::zero() {
+                    format!("-{}", repr)
+                } else {
+                    repr
+                },
+            );
+        }
+
         let sign = *self < Numeric::zero();
-        let rational = self.abs();
-        let (num, den) = rational.to_rational();
-        let rational = match rational {
+        let rational = match self.abs() {
+            Numeric::Small(numer, denom) => BigRat::small_ratio(numer, denom),
             Numeric::Rational(rational) => rational,
             Numeric::Float(f) => BigRat::from(f),
+            Numeric::BigFloat(f) => f.to_decimal(),
+            // Shown to as many digits as asked for, on top of the usual
+            // precision so that the last digit is right.
+            Numeric::Symbolic(s) => {
+                let extra = match digits {
+                    Digits::Digits(n) | Digits::SigFigs(n) => n,
+                    _ => 0,
+                };
+                s.to_big_float(BigFloat::bits_for_digits(DEFAULT_DIGITS + extra))
+                    .to_decimal()
+            }
+            Numeric::Complex(_, _) => unreachable!("Absolute values are never complex"),
         };
+        if let Digits::SigFigs(figs) = digits {
+            let (exact, repr) = sig_figs_string(&rational, base, figs);
+            return (exact, if sign { format!("-{}", repr) } else { repr });
+        }
+        let (num, den) = (rational.numer(), rational.denom());
         let intdigits = (&num / &den).size_in_base(base) as u32;
         let mut buf = String::new();
         if sign {
@@ -171,6 +837,9 @@ impl Numeric {
             let ndigits = match digits {
                 Digits::Default | Digits::FullInt => 6,
                 Digits::Digits(n) => intdigits as i32 + n as i32,
+                Digits::SigFigs(_) | Digits::Fraction(_) => {
+                    unreachable!("Significant figures and fractions are handled above")
+                }
             };
             let bail = (exact && (placed_ints || use_sci))
                 || (n as i32 - zeros as i32 > ndigits && use_sci)
@@ -213,16 +882,54 @@ impl Numeric {
         }
     }
 
+    /// Like `to_string`, but in engineering notation, where the
+    /// exponent is a multiple of 3, as in `470e-9`. Returns (is_exact,
+    /// repr).
+    pub fn to_eng_string(&self, digits: Digits) -> (bool, String) {
+        if self.is_zero() || self.is_complex() {
+            return self.to_string(10, digits);
+        }
+        let (numer, denom) = self.abs().to_rational();
+        let mut exp = exponent_in_base(&BigRat::ratio(&numer, &denom), 10).div_euclid(3) * 3;
+        loop {
+            let mantissa = self * &Numeric::from(pow_in_base(10, -exp));
+            let (exact, repr) = mantissa.to_string(10, digits);
+            // Rounding to significant figures can carry into a fourth
+            // digit, as in 999.9 -> 1000.
+            let int_digits = repr
+                .trim_start_matches('-')
+                .split('.')
+                .next()
+                .unwrap()
+                .len();
+            if int_digits > 3 {
+                exp += 3;
+                continue;
+            }
+            return match exp {
+                0 => (exact, repr),
+                exp => (exact, format!("{}e{}", repr, exp)),
+            };
+        }
+    }
+
     pub fn string_repr(&self, base: u8, digits: Digits) -> (Option<String>, Option<String>) {
         match *self {
-            Numeric::Rational(ref rational) => {
-                let num = rational.numer();
-                let den = rational.denom();
+            Numeric::Small(_, _) | Numeric::Rational(_) => {
+                let (num, den) = self.to_rational();
 
                 match self.to_string(base, digits) {
                     (true, v) => (Some(v), None),
                     (false, v) => {
-                        if den > BigInt::from(1_000u64) || num > BigInt::from(1_000_000u64) {
+                        // Whole numbers are only inexact when rounded to
+                        // significant figures, and aren't worth showing
+                        // as a fraction. Neither is a value that was
+                        // already rounded to a fraction.
+                        if den == BigInt::one()
+                            || matches!(digits, Digits::Fraction(_))
+                            || den > BigInt::from(1_000u64)
+                            || num > BigInt::from(1_000_000u64)
+                        {
                             (None, Some(v))
                         } else {
                             (Some(format!("{}/{}", num, den)), Some(v))
@@ -230,34 +937,80 @@ impl Numeric {
                     }
                 }
             }
-            Numeric::Float(_f) => (None, Some(self.to_string(base, digits).1)),
+            Numeric::Float(_) | Numeric::BigFloat(_) => {
+                (None, Some(self.to_string(base, digits).1))
+            }
+            Numeric::Symbolic(ref s) => {
+                let approx = self.to_string(base, digits).1;
+                if base == 10 && s.is_readable() {
+                    (Some(s.to_string()), Some(approx))
+                } else {
+                    (None, Some(approx))
+                }
+            }
+            Numeric::Complex(ref re, ref im) => {
+                let rational = re.is_rational() && im.is_rational();
+                match self.to_string(base, digits) {
+                    (true, v) if rational => (Some(v), None),
+                    (_, v) => (None, Some(v)),
+                }
+            }
+        }
+    }
+
+    /// Like `string_repr`, but writes the number in polar form as a
+    /// magnitude and an angle in degrees, like `5 ∠ 53.13010°`.
+    pub fn polar_repr(&self, base: u8, digits: Digits) -> (Option<String>, Option<String>) {
+        let angle = match self.arg() {
+            Numeric::Float(radians) => Numeric::Float(radians.to_degrees()),
+            exact => exact,
+        };
+        let (magnitude_exact, magnitude) = self.abs().to_string(base, digits);
+        let (angle_exact, angle) = angle.to_string(base, digits);
+        let repr = format!("{} ∠ {}°", magnitude, angle);
+        match *self {
+            Numeric::Small(_, _) | Numeric::Rational(_) if magnitude_exact && angle_exact => {
+                (Some(repr), None)
+            }
+            _ => (None, Some(repr)),
         }
     }
 }
 
 impl From<BigRat> for Numeric {
     fn from(rat: BigRat) -> Numeric {
-        Numeric::Rational(rat)
+        match (rat.numer().as_int(), rat.denom().as_int()) {
+            (Some(numer), Some(denom)) => Numeric::Small(numer, denom),
+            _ => Numeric::Rational(rat),
+        }
     }
 }
 
 impl From<BigInt> for Numeric {
     fn from(int: BigInt) -> Numeric {
-        Numeric::Rational(BigRat::ratio(&int, &BigInt::one()))
+        Numeric::from(BigRat::ratio(&int, &BigInt::one()))
     }
 }
 
 impl From<i64> for Numeric {
     fn from(i: i64) -> Numeric {
-        Numeric::from(BigInt::from(i))
+        Numeric::Small(i, 1)
     }
 }
 
 impl<'a> From<&'a Numeric> for f64 {
     fn from(value: &'a Numeric) -> f64 {
         match value {
+            // Exact in an f64, so dividing rounds the same way as BigRat.
+            Numeric::Small(numer, denom) if numer.unsigned_abs() < 1 << 53 && *denom < 1 << 53 => {
+                *numer as f64 / *denom as f64
+            }
+            Numeric::Small(numer, denom) => BigRat::small_ratio(*numer, *denom).as_float(),
             Numeric::Rational(ref rational) => rational.as_float(),
             Numeric::Float(f) => *f,
+            Numeric::BigFloat(ref f) => f.to_f64(),
+            Numeric::Symbolic(ref s) => s.to_big_float(64).to_f64(),
+            Numeric::Complex(_, _) => f64::NAN,
         }
     }
 }
@@ -277,40 +1030,112 @@ impl From<Numeric> for NumericParts {
 
 impl PartialOrd for Numeric {
     fn partial_cmp(&self, other: &Numeric) -> Option<Ordering> {
+        // Compared exactly, rather than rounding the rational.
+        match (self, other) {
+            (Numeric::BigFloat(left), right) if right.is_rational() => {
+                return left.to_rat().partial_cmp(&right.to_big_rat().unwrap())
+            }
+            (left, Numeric::BigFloat(right)) if left.is_rational() => {
+                return left.to_big_rat().unwrap().partial_cmp(&right.to_rat())
+            }
+            _ => (),
+        }
         match self.parity(other) {
+            Parity::Small(a, b, c, d) => {
+                (a as i128 * d as i128).partial_cmp(&(c as i128 * b as i128))
+            }
             Parity::Rational(left, right) => left.partial_cmp(&right),
             Parity::Float(left, right) => left.partial_cmp(&right),
+            Parity::BigFloat(left, right) => left.partial_cmp(&right),
+            Parity::Symbolic(left, right) if left == right => Some(Ordering::Equal),
+            Parity::Symbolic(left, right) => {
+                let precision = default_bits();
+                left.to_big_float(precision)
+                    .partial_cmp(&right.to_big_float(precision))
+            }
+            // Complex numbers are only comparable for equality.
+            Parity::Complex(_, _, _, _) if self == other => Some(Ordering::Equal),
+            Parity::Complex(_, _, _, _) => None,
         }
     }
 }
 
 macro_rules! num_binop {
-    ($what:ident, $func:ident) => {
+    (
+        $what:ident,
+        $func:ident,
+        |$sa:ident, $sb:ident, $sc:ident, $sd:ident| $small:expr,
+        |$a:ident, $b:ident, $c:ident, $d:ident| $complex:expr
+    ) => {
         impl<'a, 'b> $what<&'b Numeric> for &'a Numeric {
             type Output = Numeric;
 
             fn $func(self, other: &'b Numeric) -> Numeric {
                 match self.parity(other) {
-                    Parity::Rational(left, right) => Numeric::Rational(left.$func(&right)),
+                    Parity::Small($sa, $sb, $sc, $sd) => {
+                        let ($sa, $sb, $sc, $sd) =
+                            ($sa as i128, $sb as i128, $sc as i128, $sd as i128);
+                        $small
+                    }
+                    Parity::Rational(left, right) => Numeric::from(left.$func(&right)),
                     Parity::Float(left, right) => Numeric::Float(left.$func(&right)),
+                    Parity::BigFloat(left, right) => {
+                        Numeric::BigFloat(Box::new(left.$func(&right)))
+                    }
+                    Parity::Symbolic(left, right) => left.$func(&right),
+                    Parity::Complex($a, $b, $c, $d) => $complex,
                 }
             }
         }
     };
 }
 
-num_binop!(Add, add);
-num_binop!(Sub, sub);
-num_binop!(Mul, mul);
-num_binop!(Div, div);
+// Products of two i64s always fit in an i128, so small rationals
+// can't overflow before being reduced.
+num_binop!(
+    Add,
+    add,
+    |a, b, c, d| small_ratio(a * d + c * b, b * d),
+    |a, b, c, d| Numeric::complex(&a + &c, &b + &d)
+);
+num_binop!(
+    Sub,
+    sub,
+    |a, b, c, d| small_ratio(a * d - c * b, b * d),
+    |a, b, c, d| Numeric::complex(&a - &c, &b - &d)
+);
+// (a + bi)(c + di) = (ac - bd) + (ad + bc)i
+num_binop!(
+    Mul,
+    mul,
+    |a, b, c, d| small_ratio(a * c, b * d),
+    |a, b, c, d| Numeric::complex(&(&a * &c) - &(&b * &d), &(&a * &d) + &(&b * &c))
+);
+// (a + bi) / (c + di) = ((ac + bd) + (bc - ad)i) / (c^2 + d^2)
+num_binop!(
+    Div,
+    div,
+    |a, b, c, d| small_ratio(a * d, b * c),
+    |a, b, c, d| {
+        let denom = &(&c * &c) + &(&d * &d);
+        Numeric::complex(
+            &(&(&a * &c) + &(&b * &d)) / &denom,
+            &(&(&b * &c) - &(&a * &d)) / &denom,
+        )
+    }
+);
 
 impl<'a> Neg for &'a Numeric {
     type Output = Numeric;
 
     fn neg(self) -> Numeric {
         match *self {
-            Numeric::Rational(ref rational) => Numeric::Rational(-rational),
+            Numeric::Small(numer, denom) => small_ratio(-i128::from(numer), i128::from(denom)),
+            Numeric::Rational(ref rational) => Numeric::from(-rational),
             Numeric::Float(f) => Numeric::Float(-f),
+            Numeric::BigFloat(ref f) => Numeric::BigFloat(Box::new(-&**f)),
+            Numeric::Symbolic(ref s) => Numeric::Symbolic(Box::new(-&**s)),
+            Numeric::Complex(ref re, ref im) => Numeric::complex(-&**re, -&**im),
         }
     }
 }
@@ -79,6 +79,40 @@ pub struct UnitListReply {
     pub list: Vec<NumberParts>,
 }
 
+/// A list of numbers like `[1, 2.5, 7] mi`, shown one element per row.
+#[derive(Debug, Clone, Serialize)]
+pub struct ListReply {
+    pub items: Vec<NumberParts>,
+    /// Set if every element has the same quantity, in which case it is
+    /// left out of the elements.
+    pub quantity: Option<String>,
+}
+
+/// A number written as a fraction, from `-> fraction max 1000`, or as
+/// a continued fraction, from `-> continued fraction`.
+#[derive(Debug, Clone, Serialize)]
+pub struct FractionReply {
+    /// The fraction, like `355/113`, or the exact value the continued
+    /// fraction was made from.
+    pub numer: String,
+    pub denom: String,
+    /// Terms of the continued fraction, if asked for.
+    pub terms: Option<Vec<String>>,
+    /// Set if the continued fraction was cut off.
+    pub truncated: bool,
+    /// How far the fraction is from the value, with an explicit sign,
+    /// if it isn't exact.
+    pub error: Option<String>,
+    /// Unit and quantity of the value.
+    pub rest: NumberParts,
+}
+
+/// The result of a comparison, like `3 ft < 1 m`.
+#[derive(Debug, Clone, Serialize)]
+pub struct BoolReply {
+    pub value: bool,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct DurationReply {
     pub raw: NumberParts,
@@ -89,6 +123,9 @@ pub struct DurationReply {
     pub hours: NumberParts,
     pub minutes: NumberParts,
     pub seconds: NumberParts,
+    /// The duration in ISO 8601 format, when asked for with
+    /// `-> iso8601 duration`, shown instead of the parts.
+    pub formatted: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -96,6 +133,14 @@ pub struct SearchReply {
     pub results: Vec<NumberParts>,
 }
 
+/// Reports a session variable being bound with `name = expr`, or removed
+/// with `unset name` (in which case `value` is `None`).
+#[derive(Debug, Clone, Serialize)]
+pub struct BindingReply {
+    pub name: String,
+    pub value: Option<QueryReply>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct PropertyReply {
     pub name: String,
@@ -124,6 +169,9 @@ pub struct DateReply {
     pub human: Option<String>,
     pub string: String,
     pub rfc3339: String,
+    /// The date in the format asked for by a conversion like
+    /// `-> unix`, shown instead of `string`.
+    pub formatted: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -140,7 +188,11 @@ pub enum QueryReply {
     Factorize(FactorizeReply),
     UnitsFor(UnitsForReply),
     UnitList(UnitListReply),
+    List(ListReply),
+    Fraction(FractionReply),
+    Bool(BoolReply),
     Search(SearchReply),
+    Binding(Box<BindingReply>),
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -229,19 +281,30 @@ impl ExprReply {
                 Expr::UnaryOp(ref unaryop) => match unaryop.op {
                     UnaryOpType::Positive => {
                         literal!("+");
-                        recurse(expr, parts, Precedence::Plus)
+                        recurse(&unaryop.expr, parts, Precedence::Plus)
                     }
                     UnaryOpType::Negative => {
                         literal!("-");
-                        recurse(expr, parts, Precedence::Plus)
+                        recurse(&unaryop.expr, parts, Precedence::Plus)
                     }
-                    UnaryOpType::Degree(ref suffix) => {
-                        if prec < Precedence::Mul {
+                    UnaryOpType::Factorial => {
+                        if matches!(*unaryop.expr, Expr::UnaryOp(ref inner) if inner.op != UnaryOpType::Factorial)
+                        {
+                            literal!("(");
+                            recurse(&unaryop.expr, parts, Precedence::Equals);
+                            literal!(")");
+                        } else {
+                            recurse(&unaryop.expr, parts, Precedence::Term);
+                        }
+                        literal!("!")
+                    }
+                    UnaryOpType::Not => {
+                        if prec < Precedence::Not {
                             literal!("(");
                         }
-                        recurse(&unaryop.expr, parts, Precedence::Mul);
-                        literal!(suffix.to_string());
-                        if prec < Precedence::Mul {
+                        literal!("not ");
+                        recurse(&unaryop.expr, parts, Precedence::Not);
+                        if prec < Precedence::Not {
                             literal!(")");
                         }
                     }
@@ -263,6 +326,29 @@ impl ExprReply {
                         literal!(")");
                     }
                 }
+                Expr::Interval {
+                    ref lower,
+                    ref upper,
+                } => {
+                    literal!("[");
+                    recurse(lower, parts, Precedence::Equals);
+                    literal!(",");
+                    recurse(upper, parts, Precedence::Equals);
+                    literal!("]")
+                }
+                Expr::List { ref items } => {
+                    literal!("[");
+                    for (i, item) in items.iter().enumerate() {
+                        if i != 0 {
+                            literal!(",");
+                        }
+                        recurse(item, parts, Precedence::Equals);
+                    }
+                    if items.len() <= 2 {
+                        literal!(",");
+                    }
+                    literal!("]")
+                }
                 Expr::Error { ref message } => parts.push(ExprParts::Error {
                     message: message.to_owned(),
                 }),
@@ -302,7 +388,11 @@ impl Display for QueryReply {
             QueryReply::Factorize(ref v) => write!(fmt, "{}", v),
             QueryReply::UnitsFor(ref v) => write!(fmt, "{}", v),
             QueryReply::UnitList(ref v) => write!(fmt, "{}", v),
+            QueryReply::List(ref v) => write!(fmt, "{}", v),
+            QueryReply::Fraction(ref v) => write!(fmt, "{}", v),
+            QueryReply::Bool(ref v) => write!(fmt, "{}", v),
             QueryReply::Search(ref v) => write!(fmt, "{}", v),
+            QueryReply::Binding(ref v) => write!(fmt, "{}", v),
         }
     }
 }
@@ -333,6 +423,22 @@ impl Display for ConformanceError {
     }
 }
 
+impl ListReply {
+    pub fn new(mut items: Vec<NumberParts>) -> ListReply {
+        let quantity = items.first().and_then(|x| x.quantity.clone());
+        let shared = items.iter().all(|x| x.quantity == quantity);
+        if shared {
+            for item in &mut items {
+                item.quantity = None;
+            }
+        }
+        ListReply {
+            items,
+            quantity: if shared { quantity } else { None },
+        }
+    }
+}
+
 impl DateReply {
     pub fn new<Tz>(ctx: &crate::context::Context, date: DateTime<Tz>) -> DateReply
     where
@@ -351,13 +457,24 @@ impl DateReply {
             second: date.second() as i32,
             nanosecond: date.nanosecond() as i32,
             human: ctx.humanize(date),
+            formatted: None,
+        }
+    }
+
+    /// Shows the date as the given string, without the humanized form,
+    /// so that it can be pasted into other programs.
+    pub fn with_format(self, formatted: String) -> DateReply {
+        DateReply {
+            formatted: Some(formatted),
+            human: None,
+            ..self
         }
     }
 }
 
 impl Display for DateReply {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
-        write!(fmt, "{}", self.string)?;
+        write!(fmt, "{}", self.formatted.as_ref().unwrap_or(&self.string))?;
         if let Some(ref human) = self.human {
             write!(fmt, " ({})", human)?;
         }
@@ -408,6 +525,15 @@ impl Display for DefReply {
     }
 }
 
+impl Display for BindingReply {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+        match self.value {
+            Some(ref value) => write!(fmt, "{} = {}", self.name, value),
+            None => write!(fmt, "Unset {}", self.name),
+        }
+    }
+}
+
 impl Display for ConversionReply {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
         write!(fmt, "{}", self.value)
@@ -463,6 +589,9 @@ impl Display for UnitsForReply {
 
 impl Display for DurationReply {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+        if let Some(ref formatted) = self.formatted {
+            return write!(fmt, "{}", formatted);
+        }
         let res = [
             &self.years,
             &self.months,
@@ -505,6 +634,67 @@ impl Display for UnitListReply {
     }
 }
 
+impl Display for ListReply {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+        write!(
+            fmt,
+            "{}",
+            self.items
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )?;
+        if let Some(ref quantity) = self.quantity {
+            write!(fmt, " ({})", quantity)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl FractionReply {
+    /// The fraction or the continued fraction, without the unit.
+    fn value_string(&self) -> String {
+        match self.terms {
+            Some(ref terms) => {
+                let mut out = format!("[{}", terms[0]);
+                if terms.len() > 1 {
+                    out.push_str("; ");
+                    out.push_str(&terms[1..].join(", "));
+                }
+                if self.truncated {
+                    out.push_str(", ...");
+                }
+                out.push(']');
+                out
+            }
+            None if self.denom == "1" => self.numer.clone(),
+            None => format!("{}/{}", self.numer, self.denom),
+        }
+    }
+}
+
+impl Display for FractionReply {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+        write!(fmt, "{}", self.value_string())?;
+        if let Some(ref error) = self.error {
+            write!(fmt, " (error {})", error)?;
+        }
+        let rest = self.rest.format("u w");
+        if !rest.is_empty() {
+            write!(fmt, " {}", rest)?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for BoolReply {
+    fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
+        write!(fmt, "{}", self.value)
+    }
+}
+
 impl Display for SearchReply {
     fn fmt(&self, fmt: &mut Formatter<'_>) -> FmtResult {
         write!(
@@ -531,14 +721,20 @@ impl<'a> TokenFmt<'a> for QueryReply {
             QueryReply::Factorize(reply) => reply.to_spans(),
             QueryReply::UnitsFor(reply) => reply.to_spans(),
             QueryReply::UnitList(reply) => reply.to_spans(),
+            QueryReply::List(reply) => reply.to_spans(),
+            QueryReply::Fraction(reply) => reply.to_spans(),
+            QueryReply::Bool(reply) => reply.to_spans(),
             QueryReply::Search(reply) => reply.to_spans(),
+            QueryReply::Binding(reply) => reply.to_spans(),
         }
     }
 }
 
 impl<'a> TokenFmt<'a> for DateReply {
     fn to_spans(&'a self) -> Vec<Span<'a>> {
-        if let Some(ref human) = self.human {
+        if let Some(ref formatted) = self.formatted {
+            vec![Span::date_time(formatted)]
+        } else if let Some(ref human) = self.human {
             vec![
                 Span::date_time(&self.string),
                 Span::plain(" ("),
@@ -567,6 +763,19 @@ impl<'a> TokenFmt<'a> for SubstanceReply {
     }
 }
 
+impl<'a> TokenFmt<'a> for BindingReply {
+    fn to_spans(&'a self) -> Vec<Span<'a>> {
+        match self.value {
+            Some(ref value) => vec![
+                Span::unit(&self.name),
+                Span::plain(" = "),
+                Span::child(value),
+            ],
+            None => vec![Span::plain("Unset "), Span::unit(&self.name)],
+        }
+    }
+}
+
 impl<'a> TokenFmt<'a> for PropertyReply {
     fn to_spans(&'a self) -> Vec<Span<'a>> {
         let mut tokens = vec![
@@ -584,6 +793,9 @@ impl<'a> TokenFmt<'a> for PropertyReply {
 
 impl<'a> TokenFmt<'a> for DurationReply {
     fn to_spans(&'a self) -> Vec<Span<'a>> {
+        if let Some(ref formatted) = self.formatted {
+            return vec![Span::number(formatted)];
+        }
         let parts = [
             &self.years,
             &self.months,
@@ -714,6 +926,42 @@ impl<'a> TokenFmt<'a> for UnitListReply {
     }
 }
 
+impl<'a> TokenFmt<'a> for ListReply {
+    fn to_spans(&'a self) -> Vec<Span<'a>> {
+        let mut tokens = vec![Span::list_begin("")];
+        tokens.extend(join(
+            self.items.iter().map(|num| Span::child(num)),
+            Span::list_sep(", "),
+        ));
+        if let Some(ref quantity) = self.quantity {
+            tokens.push(Span::plain(" ("));
+            tokens.push(Span::quantity(quantity));
+            tokens.push(Span::plain(")"));
+        }
+        tokens
+    }
+}
+
+impl<'a> TokenFmt<'a> for FractionReply {
+    fn to_spans(&'a self) -> Vec<Span<'a>> {
+        let mut tokens = vec![Span::number(self.value_string())];
+        if let Some(ref error) = self.error {
+            tokens.push(Span::plain(" (error "));
+            tokens.push(Span::number(error));
+            tokens.push(Span::plain(")"));
+        }
+        tokens.push(Span::plain(" "));
+        tokens.extend(self.rest.token_format("u w").to_spans());
+        tokens
+    }
+}
+
+impl<'a> TokenFmt<'a> for BoolReply {
+    fn to_spans(&'a self) -> Vec<Span<'a>> {
+        vec![Span::plain(if self.value { "true" } else { "false" })]
+    }
+}
+
 impl<'a> TokenFmt<'a> for SearchReply {
     fn to_spans(&'a self) -> Vec<Span<'a>> {
         once(Span::list_begin("Search results: "))
@@ -0,0 +1,503 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::ast::{Expr, ScaleDef, ScaleRange};
+use crate::context::Context;
+use crate::interval::Interval;
+use crate::number::Number;
+use crate::numeric::{Digits, Numeric};
+use crate::text_query::{parse_expr, TokenIterator};
+use crate::uncertain::Uncertain;
+use crate::value::{Show, Value};
+use std::collections::BTreeMap;
+
+/// A nonlinear unit such as `°C` or `dBm`. Readings on these scales
+/// can't just be multiplied by a unit, so they are converted to and
+/// from the linear quantity they describe instead.
+#[derive(Debug, Clone)]
+pub struct Scale {
+    /// Shown after converted values, like `°C`.
+    pub symbol: String,
+    pub kind: ScaleKind,
+}
+
+#[derive(Debug, Clone)]
+pub enum ScaleKind {
+    /// `reference * base^(reading / divisor)`, used by decibels and
+    /// other logarithmic units.
+    Logarithmic {
+        reference: Expr,
+        base: Numeric,
+        divisor: Numeric,
+    },
+    /// Defined in a units file with a forward and an inverse
+    /// expression, like the temperature scales.
+    Defined(Box<ScaleDef>),
+    /// Interpolated between the points of a table, like wire gauges.
+    /// Outputs are multiplied by `unit`.
+    Table {
+        unit: Expr,
+        points: Vec<(Numeric, Numeric)>,
+    },
+}
+
+fn eval_number(ctx: &Context, expr: &Expr) -> Result<Number, String> {
+    match ctx.eval(expr) {
+        Ok(Value::Number(num)) => Ok(num),
+        Ok(value) => Err(format!("Expected number, got: <{}>", value.show(ctx))),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+fn eval_in_frame(
+    ctx: &Context,
+    scale: &str,
+    name: &str,
+    value: &Number,
+    body: &Expr,
+) -> Result<Number, String> {
+    let mut frame = BTreeMap::new();
+    frame.insert(name.to_owned(), Value::Number(value.clone()));
+    match ctx.eval_in_frame(scale, frame, body) {
+        Ok(Value::Number(num)) => Ok(num),
+        Ok(value) => Err(format!("Expected number, got: <{}>", value.show(ctx))),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+/// The dimensions of readings and of quantities.
+fn def_units(ctx: &Context, def: &ScaleDef) -> Result<(Number, Number), String> {
+    match def.units {
+        Some(ref units) => Ok((
+            eval_number(ctx, &units.input)?,
+            eval_number(ctx, &units.output)?,
+        )),
+        None => Ok((Number::one(), Number::one())),
+    }
+}
+
+fn describe(ctx: &Context, unit: &Number) -> String {
+    if unit.dimless() {
+        "dimensionless".to_owned()
+    } else {
+        ctx.describe_unit(unit).1
+    }
+}
+
+/// Finds `n` such that `base^n == value` when both are exact, so that
+/// `100 mW -> dBm` gives exactly 20.
+fn exact_log(value: &Numeric, base: &Numeric) -> Option<i64> {
+    if *value == Numeric::one() {
+        return Some(0);
+    }
+    if !value.is_rational() || !base.is_rational() || *base <= Numeric::one() {
+        return None;
+    }
+    let (value, sign) = if *value < Numeric::one() {
+        (&Numeric::one() / value, -1)
+    } else {
+        (value.clone(), 1)
+    };
+    let mut power = Numeric::one();
+    let mut n = 0;
+    while power < value {
+        power = &power * base;
+        n += 1;
+    }
+    if power == value {
+        Some(sign * n)
+    } else {
+        None
+    }
+}
+
+/// The segment of a table that contains `value`, as an index into
+/// `points` and the fraction of the way along it. With `inverse`,
+/// `value` is an output instead of a reading.
+fn find_segment(
+    points: &[(Numeric, Numeric)],
+    value: &Numeric,
+    inverse: bool,
+) -> Option<(usize, Numeric)> {
+    let pick = |point: &(Numeric, Numeric)| {
+        if inverse {
+            point.1.clone()
+        } else {
+            point.0.clone()
+        }
+    };
+    points.windows(2).enumerate().find_map(|(i, pair)| {
+        let (start, end) = (pick(&pair[0]), pick(&pair[1]));
+        let inside = (start <= *value && *value <= end) || (end <= *value && *value <= start);
+        if !inside {
+            None
+        } else if start == end {
+            Some((i, Numeric::zero()))
+        } else {
+            Some((i, &(value - &start) / &(&end - &start)))
+        }
+    })
+}
+
+/// Linear interpolation between `start` and `end`.
+fn lerp(start: &Numeric, end: &Numeric, fraction: &Numeric) -> Numeric {
+    start + &(fraction * &(end - start))
+}
+
+/// The smallest and largest readings or outputs of a table.
+fn table_bounds(points: &[(Numeric, Numeric)], inverse: bool) -> (Numeric, Numeric) {
+    let mut values = points.iter().map(|point| {
+        if inverse {
+            point.1.clone()
+        } else {
+            point.0.clone()
+        }
+    });
+    let first = values.next().unwrap();
+    values.fold((first.clone(), first), |(min, max), x| {
+        if x < min {
+            (x, max)
+        } else if x > max {
+            (min, x)
+        } else {
+            (min, max)
+        }
+    })
+}
+
+impl Scale {
+    /// The unit that readings are measured in: the size of one degree,
+    /// or the reference level of a logarithmic scale.
+    pub fn unit(&self, ctx: &Context) -> Result<Number, String> {
+        match self.kind {
+            ScaleKind::Logarithmic { ref reference, .. } => eval_number(ctx, reference),
+            ScaleKind::Defined(ref def) => def_units(ctx, def).map(|(_, output)| output),
+            ScaleKind::Table { ref unit, .. } => eval_number(ctx, unit),
+        }
+    }
+
+    /// Reports a reading or quantity outside of a table.
+    fn table_error(
+        &self,
+        ctx: &Context,
+        points: &[(Numeric, Numeric)],
+        inverse: bool,
+        value: &Number,
+    ) -> String {
+        let (min, max) = table_bounds(points, inverse);
+        let bounds = if inverse {
+            let unit = self.unit(ctx).unwrap_or_else(|_| Number::one());
+            let show = |value: Numeric| {
+                Number {
+                    value: &value * &unit.value,
+                    unit: unit.unit.clone(),
+                }
+                .show(ctx)
+            };
+            format!("quantities from <{}> to <{}>", show(min), show(max))
+        } else {
+            let show = |value: Numeric| value.to_string(10, Digits::Default).1;
+            format!("readings from {} to {}", show(min), show(max))
+        };
+        format!(
+            "{} is only defined for {}, got: <{}>",
+            self.symbol,
+            bounds,
+            value.show(ctx)
+        )
+    }
+
+    fn check_range(
+        &self,
+        ctx: &Context,
+        range: &Option<ScaleRange>,
+        value: &Number,
+        what: &str,
+    ) -> Result<(), String> {
+        let range = match *range {
+            Some(ref range) => range,
+            None => return Ok(()),
+        };
+        let above = match range.lower {
+            Some(ref bound) => {
+                let limit = eval_number(ctx, &bound.value)?.value;
+                value.value > limit || bound.inclusive && value.value == limit
+            }
+            None => true,
+        };
+        let below = match range.upper {
+            Some(ref bound) => {
+                let limit = eval_number(ctx, &bound.value)?.value;
+                value.value < limit || bound.inclusive && value.value == limit
+            }
+            None => true,
+        };
+        if above && below {
+            Ok(())
+        } else {
+            Err(format!(
+                "{} is only defined for {} in {}, got: <{}>",
+                self.symbol,
+                what,
+                range,
+                value.show(ctx)
+            ))
+        }
+    }
+
+    /// Converts a reading, like the 20 in `20 dBm`, into the quantity
+    /// it stands for.
+    pub fn to_linear(&self, ctx: &Context, reading: &Number) -> Result<Number, String> {
+        match self.kind {
+            ScaleKind::Logarithmic { .. } if !reading.dimless() => Err(format!(
+                "Expected dimensionless, got: <{}>",
+                reading.show(ctx)
+            )),
+            ScaleKind::Logarithmic {
+                ref reference,
+                ref base,
+                ref divisor,
+            } => {
+                let exp = Number::new(&reading.value / divisor);
+                let ratio = Number::new(base.clone()).pow(&exp, ctx.working_precision())?;
+                (&ratio * &eval_number(ctx, reference)?)
+                    .ok_or_else(|| "Bug: Mul should not fail".to_string())
+            }
+            ScaleKind::Defined(ref def) => {
+                let (input, output) = def_units(ctx, def)?;
+                if reading.unit != input.unit {
+                    return Err(format!(
+                        "Expected {}, got: <{}>",
+                        describe(ctx, &input),
+                        reading.show(ctx)
+                    ));
+                }
+                self.check_range(ctx, &def.domain, reading, "readings")?;
+                let value = eval_in_frame(ctx, &self.symbol, &def.param, reading, &def.forward)?;
+                if value.unit != output.unit {
+                    return Err(format!(
+                        "{} gave <{}>, expected {}",
+                        self.symbol,
+                        value.show(ctx),
+                        describe(ctx, &output)
+                    ));
+                }
+                Ok(value)
+            }
+            ScaleKind::Table { .. } if !reading.dimless() => Err(format!(
+                "Expected dimensionless, got: <{}>",
+                reading.show(ctx)
+            )),
+            ScaleKind::Table {
+                ref unit,
+                ref points,
+            } => {
+                let (i, fraction) = find_segment(points, &reading.value, false)
+                    .ok_or_else(|| self.table_error(ctx, points, false, reading))?;
+                let output = lerp(&points[i].1, &points[i + 1].1, &fraction);
+                (&Number::new(output) * &eval_number(ctx, unit)?)
+                    .ok_or_else(|| "Bug: Mul should not fail".to_string())
+            }
+        }
+    }
+
+    /// Converts a quantity into a reading on this scale. The quantity
+    /// must already conform to `unit()`.
+    pub fn from_linear(&self, ctx: &Context, value: &Number) -> Result<Number, String> {
+        match self.kind {
+            ScaleKind::Logarithmic {
+                ref reference,
+                ref base,
+                ref divisor,
+            } => {
+                let ratio = (value / &eval_number(ctx, reference)?)
+                    .ok_or_else(|| "Division by zero".to_string())?;
+                if ratio.value.is_complex() || ratio.value <= Numeric::zero() {
+                    return Err(format!(
+                        "{} is only defined for positive values, got: <{}>",
+                        self.symbol,
+                        value.show(ctx)
+                    ));
+                }
+                let log = match exact_log(&ratio.value, base) {
+                    Some(n) => Numeric::from(n),
+                    None => Numeric::Float(ratio.value.to_f64().ln() / base.to_f64().ln()),
+                };
+                Ok(Number::new(&log * divisor))
+            }
+            ScaleKind::Defined(ref def) => {
+                let (input, _) = def_units(ctx, def)?;
+                self.check_range(ctx, &def.range, value, "quantities")?;
+                let reading = eval_in_frame(ctx, &self.symbol, &self.symbol, value, &def.inverse)?;
+                if reading.unit != input.unit {
+                    return Err(format!(
+                        "The inverse of {} gave <{}>, expected {}",
+                        self.symbol,
+                        reading.show(ctx),
+                        describe(ctx, &input)
+                    ));
+                }
+                Ok(reading)
+            }
+            ScaleKind::Table {
+                ref unit,
+                ref points,
+            } => {
+                let output = (value / &eval_number(ctx, unit)?)
+                    .ok_or_else(|| "Division by zero".to_string())?;
+                let (i, fraction) = find_segment(points, &output.value, true)
+                    .ok_or_else(|| self.table_error(ctx, points, true, value))?;
+                Ok(Number::new(lerp(&points[i].0, &points[i + 1].0, &fraction)))
+            }
+        }
+    }
+
+    /// How fast the linear quantity changes per step of the reading,
+    /// at the given linear value. Used to propagate uncertainties.
+    pub fn slope(&self, ctx: &Context, linear: &Number) -> Result<Numeric, String> {
+        match self.kind {
+            ScaleKind::Logarithmic {
+                ref base,
+                ref divisor,
+                ..
+            } => Ok(&linear.value * &Numeric::Float(base.to_f64().ln() / divisor.to_f64())),
+            ScaleKind::Defined(_) => {
+                // A central difference, which is exact for linear
+                // definitions like the temperature scales.
+                let reading = self.from_linear(ctx, linear)?;
+                let size = if reading.value.abs() > Numeric::one() {
+                    reading.value.abs()
+                } else {
+                    Numeric::one()
+                };
+                let step = &size / &Numeric::from(1_000_000);
+                let at = |value: Numeric| {
+                    self.to_linear(
+                        ctx,
+                        &Number {
+                            value,
+                            unit: reading.unit.clone(),
+                        },
+                    )
+                };
+                let ahead = at(&reading.value + &step)?;
+                let behind = at(&reading.value - &step)?;
+                Ok(&(&ahead.value - &behind.value) / &(&step * &Numeric::from(2)))
+            }
+            ScaleKind::Table {
+                ref unit,
+                ref points,
+            } => {
+                let reading = self.from_linear(ctx, linear)?;
+                let (i, _) = find_segment(points, &reading.value, false)
+                    .ok_or_else(|| self.table_error(ctx, points, false, &reading))?;
+                let (start, end) = (&points[i], &points[i + 1]);
+                let slope = &(&end.1 - &start.1) / &(&end.0 - &start.0);
+                Ok(&slope * &eval_number(ctx, unit)?.value)
+            }
+        }
+    }
+
+    /// Applies `to_linear` to a number, an uncertain number, an
+    /// interval or a list.
+    pub fn value_to_linear(&self, ctx: &Context, reading: &Value) -> Result<Value, String> {
+        match *reading {
+            Value::Number(ref num) => self.to_linear(ctx, num).map(Value::Number),
+            Value::Uncertain(ref num) => {
+                let value = self.to_linear(ctx, &num.value)?;
+                let error = Number {
+                    value: &num.error * &self.slope(ctx, &value)?.abs(),
+                    unit: value.unit.clone(),
+                };
+                Uncertain::new(value, error).map(Value::Uncertain)
+            }
+            Value::Interval(ref num) => Interval::from_endpoints(
+                self.to_linear(ctx, &num.lower)?,
+                self.to_linear(ctx, &num.upper)?,
+            )
+            .map(Value::Interval),
+            Value::List(ref list) => list
+                .iter()
+                .map(|num| self.to_linear(ctx, num))
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::List),
+            _ => Err(format!(
+                "Expected number, got: <{}> {}",
+                reading.show(ctx),
+                self.symbol
+            )),
+        }
+    }
+}
+
+fn parse(input: &str) -> Expr {
+    let mut iter = TokenIterator::new(input).peekable();
+    parse_expr(&mut iter)
+}
+
+/// The logarithmic units that are always available, by name. The first
+/// name of each is the symbol used in output. Other nonlinear units,
+/// like the temperature scales, come from the units files.
+pub fn builtin() -> BTreeMap<String, Scale> {
+    // Decibels and bels measure power ratios, so amplitudes like
+    // voltage are squared first.
+    let log = |reference: &str, base: Numeric, divisor: Numeric| ScaleKind::Logarithmic {
+        reference: parse(reference),
+        base,
+        divisor,
+    };
+    let decibel = |reference: &str| log(reference, Numeric::from(10), Numeric::from(10));
+    let field_decibel = |reference: &str| log(reference, Numeric::from(10), Numeric::from(20));
+    let half = &Numeric::one() / &Numeric::from(2);
+
+    let table = vec![
+        (
+            vec!["bel", "bels"],
+            log("1", Numeric::from(10), Numeric::one()),
+        ),
+        (vec!["dB", "decibel", "decibels"], decibel("1")),
+        // A neper is the natural log of an amplitude ratio, so that
+        // 1 Np is about 8.686 dB.
+        (
+            vec!["Np", "neper", "nepers"],
+            log("1", Numeric::Float(std::f64::consts::E), half),
+        ),
+        (vec!["dBW"], decibel("W")),
+        (vec!["dBk"], decibel("kW")),
+        (vec!["dBm", "dBmW"], decibel("mW")),
+        (vec!["dBf"], decibel("fW")),
+        (vec!["dBJ"], decibel("J")),
+        (vec!["dBV"], field_decibel("V")),
+        (vec!["dBmV"], field_decibel("mV")),
+        (vec!["dBuV", "dBµV"], field_decibel("microV")),
+        // The voltage that dissipates 1 mW in a 600 ohm load.
+        (vec!["dBu"], field_decibel("sqrt(mW 600 ohm)")),
+        (vec!["dBSPL"], field_decibel("20 microPa")),
+        (vec!["dBSIL"], decibel("1e-12 W/m^2")),
+        (vec!["dBSWL"], decibel("1e-12 W")),
+        // Not called pH, which is picohenry.
+        (
+            vec!["pHscale"],
+            log("mol/liter", Numeric::from(10), Numeric::from(-1)),
+        ),
+        // Astronomical magnitudes, where 5 magnitudes brighter is 100
+        // times the flux.
+        (
+            vec!["mag", "magnitude", "magnitudes"],
+            log("1", Numeric::from(100), Numeric::from(-5)),
+        ),
+    ];
+
+    let mut scales = BTreeMap::new();
+    for (names, kind) in table {
+        let scale = Scale {
+            symbol: names[0].to_owned(),
+            kind,
+        };
+        for name in names {
+            scales.insert(name.to_owned(), scale.clone());
+        }
+    }
+    scales
+}
@@ -6,6 +6,7 @@ use crate::{
     context::Context,
     number::{Dimension, NumberParts},
     reply::SearchReply,
+    value::Value,
 };
 use std::collections::BinaryHeap;
 use std::{
@@ -72,6 +73,14 @@ pub fn search<'a>(ctx: &'a Context, query: &str, num_results: usize) -> Vec<&'a
         for k in ctx.substances.keys() {
             scan(&**k);
         }
+        for k in ctx.functions.keys() {
+            scan(k);
+        }
+        for k in ctx.variables.keys() {
+            if !ctx.units.contains_key(k) && !ctx.substances.contains_key(k) {
+                scan(k);
+            }
+        }
     }
     results
         .into_sorted_vec()
@@ -85,9 +94,17 @@ pub fn query(ctx: &Context, query: &str, num_results: usize) -> SearchReply {
         results: search(ctx, query, num_results)
             .into_iter()
             .map(|name| {
-                let parts = ctx
-                    .lookup(name)
-                    .map(|x| x.to_parts(ctx))
+                let parts = match ctx.variables.get(name) {
+                    Some(Value::Number(ref num)) => Some(num.to_parts(ctx)),
+                    Some(_) => Some(NumberParts::default()),
+                    None if ctx.functions.contains_key(name) => Some(NumberParts {
+                        quantity: Some("function".to_owned()),
+                        ..Default::default()
+                    }),
+                    None => None,
+                };
+                let parts = parts
+                    .or_else(|| ctx.lookup(name).map(|x| x.to_parts(ctx)))
                     .or_else(|| {
                         if ctx.substances.get(name).is_some() {
                             Some(NumberParts {
@@ -0,0 +1,349 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use num::bigint::BigInt as NumInt;
+use num::integer::Integer;
+use num::rational::BigRational as NumRat;
+use num::traits::{One, Signed, ToPrimitive};
+use std::fmt;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+use crate::bigfloat::{BigFloat, DEFAULT_DIGITS};
+use crate::bigrat::BigRat;
+use crate::numeric::Numeric;
+
+/// Radicals are only kept symbolic when the number under the root is
+/// below this, so that finding its square factors stays fast.
+const MAX_RADICAND: u64 = 1_000_000_000_000;
+
+/// An exact number of the form `coeff * sqrt(radicand) * pi^pi_power`,
+/// like `3 pi` or `sqrt(2)/2`. These stay exact through
+/// multiplication, division and integer powers, so that `sqrt(2)^2`
+/// is exactly 2, and are only turned into decimals when shown. Even
+/// powers are rational however large they get, so `sqrt(2)^100000` is
+/// exactly `2^50000`, even though it's shown rounded like any other
+/// long number.
+///
+/// Only one radical and one power of pi can be kept, so sums like
+/// `sqrt(2) + 1` become big floats, and so does anything computed
+/// from them, such as `(sqrt(2) + 1)^2`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Symbolic {
+    coeff: BigRat,
+    /// Always square-free, and 1 when there is no radical.
+    radicand: NumInt,
+    pi_power: i64,
+}
+
+/// Splits `n` into `s^2 r` where `r` is square-free.
+fn split_square(mut n: u64) -> (u64, u64) {
+    let (mut square, mut radicand) = (1, 1);
+    let mut d = 2;
+    while d * d * d <= n {
+        let mut count = 0;
+        while n.is_multiple_of(d) {
+            n /= d;
+            count += 1;
+        }
+        square *= d.pow(count / 2);
+        radicand *= d.pow(count % 2);
+        d += 1;
+    }
+    // What's left has no factors up to its cube root, so it is a
+    // prime, a product of two distinct primes, or a prime squared.
+    let root = (n as f64).sqrt().round() as u64;
+    if root * root == n {
+        (square * root, radicand)
+    } else {
+        (square, radicand * n)
+    }
+}
+
+/// The exact nth root of a non-negative integer, if it has one.
+fn exact_root(n: &NumInt, degree: u32) -> Option<NumInt> {
+    let root = n.nth_root(degree);
+    if root.pow(degree) == *n {
+        Some(root)
+    } else {
+        None
+    }
+}
+
+impl Symbolic {
+    /// Builds `coeff sqrt(radicand) pi^pi_power` from a square-free
+    /// radicand, which is simplified to a rational when possible.
+    fn build(coeff: BigRat, radicand: NumInt, pi_power: i64) -> Numeric {
+        if coeff == BigRat::zero() || radicand.is_one() && pi_power == 0 {
+            Numeric::from(coeff)
+        } else {
+            Numeric::Symbolic(Box::new(Symbolic {
+                coeff,
+                radicand,
+                pi_power,
+            }))
+        }
+    }
+
+    pub fn pi() -> Numeric {
+        Symbolic::build(BigRat::one(), NumInt::one(), 1)
+    }
+
+    /// The nth root of a non-negative fraction, when it is rational or
+    /// a small enough square root.
+    pub fn root_rational(value: &BigRat, degree: u32) -> Option<Numeric> {
+        let numer = value.numer().into_inner();
+        let denom = value.denom().into_inner();
+        if let (Some(numer), Some(denom)) = (exact_root(&numer, degree), exact_root(&denom, degree))
+        {
+            return Some(Numeric::from(BigRat::from(NumRat::new(numer, denom))));
+        }
+        if degree != 2 {
+            return None;
+        }
+        // sqrt(a/b) = sqrt(ab)/b
+        let product = (&numer * &denom).to_u64().filter(|&n| n <= MAX_RADICAND)?;
+        let (square, radicand) = split_square(product);
+        let coeff = BigRat::from(NumRat::new(NumInt::from(square), denom));
+        Some(Symbolic::build(coeff, NumInt::from(radicand), 0))
+    }
+
+    /// The nth root, which is only exact for square roots of rational
+    /// multiples of even powers of pi.
+    pub fn root(&self, degree: u32) -> Option<Numeric> {
+        if degree != 2 || !self.radicand.is_one() || self.pi_power % 2 != 0 {
+            return None;
+        }
+        let root = Symbolic::root_rational(&self.coeff, 2)?;
+        Some(&root * &Symbolic::build(BigRat::one(), NumInt::one(), self.pi_power / 2))
+    }
+
+    pub fn powi(&self, exp: i32) -> Numeric {
+        if exp < 0 {
+            return self.recip().powi(-exp);
+        }
+        let exp = exp as u32;
+        let coeff = self.coeff.clone().into_inner().pow(exp as i32);
+        // sqrt(r)^n is r^(n/2), times another sqrt(r) if n is odd.
+        let coeff = coeff * NumRat::from_integer(self.radicand.pow(exp / 2));
+        let radicand = if exp % 2 == 1 {
+            self.radicand.clone()
+        } else {
+            NumInt::one()
+        };
+        Symbolic::build(BigRat::from(coeff), radicand, self.pi_power * exp as i64)
+    }
+
+    /// `1 / (c sqrt(r) pi^k)` is `1/(c r) sqrt(r) pi^-k`.
+    fn recip(&self) -> Symbolic {
+        let denom = &self.coeff * &BigRat::from(NumRat::from_integer(self.radicand.clone()));
+        Symbolic {
+            coeff: &BigRat::one() / &denom,
+            radicand: self.radicand.clone(),
+            pi_power: -self.pi_power,
+        }
+    }
+
+    pub fn is_negative(&self) -> bool {
+        self.coeff < BigRat::zero()
+    }
+
+    pub fn abs(&self) -> Symbolic {
+        Symbolic {
+            coeff: self.coeff.abs(),
+            ..self.clone()
+        }
+    }
+
+    /// Approximates the number with the given number of bits.
+    pub fn to_big_float(&self, precision: u64) -> BigFloat {
+        let work = precision + 16;
+        let mut result = BigFloat::from_rat(&self.coeff, work);
+        if !self.radicand.is_one() {
+            let radicand = BigFloat::from_rat(
+                &BigRat::from(NumRat::from_integer(self.radicand.clone())),
+                work,
+            );
+            let root = radicand.sqrt(work).expect("Radicands are positive");
+            result = &result * &root;
+        }
+        let pi = BigFloat::pi(work);
+        for _ in 0..self.pi_power.abs() {
+            result = if self.pi_power > 0 {
+                &result * &pi
+            } else {
+                &result / &pi
+            };
+        }
+        result.with_precision(precision)
+    }
+
+    /// Whether the coefficient is small enough to be worth showing, in
+    /// the same way as exact fractions are.
+    pub fn is_readable(&self) -> bool {
+        self.coeff.numer().into_inner().abs() <= NumInt::from(1_000_000)
+            && self.coeff.denom().into_inner() <= NumInt::from(1_000)
+    }
+}
+
+impl From<BigRat> for Symbolic {
+    fn from(coeff: BigRat) -> Symbolic {
+        Symbolic {
+            coeff,
+            radicand: NumInt::one(),
+            pi_power: 0,
+        }
+    }
+}
+
+impl fmt::Display for Symbolic {
+    fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let pi = |power: i64| {
+            if power == 1 {
+                "pi".to_owned()
+            } else {
+                format!("pi^{}", power)
+            }
+        };
+        let numer = self.coeff.numer().into_inner();
+        let denom = self.coeff.denom().into_inner();
+        let mut top = vec![];
+        let mut bottom = vec![];
+        if !numer.abs().is_one() || self.radicand.is_one() && self.pi_power <= 0 {
+            top.push(numer.abs().to_string());
+        }
+        if !self.radicand.is_one() {
+            top.push(format!("sqrt({})", self.radicand));
+        }
+        if self.pi_power > 0 {
+            top.push(pi(self.pi_power));
+        }
+        if !denom.is_one() {
+            bottom.push(denom.to_string());
+        }
+        if self.pi_power < 0 {
+            bottom.push(pi(-self.pi_power));
+        }
+        if numer.is_negative() {
+            write!(fmt, "-")?;
+        }
+        write!(fmt, "{}", top.join(" "))?;
+        match bottom.len() {
+            0 => Ok(()),
+            1 => write!(fmt, "/{}", bottom[0]),
+            _ => write!(fmt, "/({})", bottom.join(" ")),
+        }
+    }
+}
+
+/// Sums of different radicals or powers of pi can't be kept exact, so
+/// they become floats.
+fn collapse(
+    left: &Symbolic,
+    right: &Symbolic,
+    op: fn(&BigFloat, &BigFloat) -> BigFloat,
+) -> Numeric {
+    let precision = BigFloat::bits_for_digits(DEFAULT_DIGITS);
+    Numeric::BigFloat(Box::new(op(
+        &left.to_big_float(precision),
+        &right.to_big_float(precision),
+    )))
+}
+
+impl<'a> Add for &'a Symbolic {
+    type Output = Numeric;
+
+    fn add(self, rhs: &'a Symbolic) -> Numeric {
+        if self.radicand == rhs.radicand && self.pi_power == rhs.pi_power {
+            Symbolic::build(
+                &self.coeff + &rhs.coeff,
+                self.radicand.clone(),
+                self.pi_power,
+            )
+        } else {
+            collapse(self, rhs, |a, b| a + b)
+        }
+    }
+}
+
+impl<'a> Sub for &'a Symbolic {
+    type Output = Numeric;
+
+    fn sub(self, rhs: &'a Symbolic) -> Numeric {
+        if self.radicand == rhs.radicand && self.pi_power == rhs.pi_power {
+            Symbolic::build(
+                &self.coeff - &rhs.coeff,
+                self.radicand.clone(),
+                self.pi_power,
+            )
+        } else {
+            collapse(self, rhs, |a, b| a - b)
+        }
+    }
+}
+
+impl<'a> Mul for &'a Symbolic {
+    type Output = Numeric;
+
+    fn mul(self, rhs: &'a Symbolic) -> Numeric {
+        // sqrt(ga) sqrt(gb) = g sqrt(ab), where ab is square-free
+        // because both radicands are.
+        let common = self.radicand.gcd(&rhs.radicand);
+        let radicand = &self.radicand * &rhs.radicand / (&common * &common);
+        let coeff = &(&self.coeff * &rhs.coeff) * &BigRat::from(NumRat::from_integer(common));
+        Symbolic::build(coeff, radicand, self.pi_power + rhs.pi_power)
+    }
+}
+
+impl<'a> Div for &'a Symbolic {
+    type Output = Numeric;
+
+    fn div(self, rhs: &'a Symbolic) -> Numeric {
+        Mul::mul(self, &rhs.recip())
+    }
+}
+
+impl<'a> Neg for &'a Symbolic {
+    type Output = Symbolic;
+
+    fn neg(self) -> Symbolic {
+        Symbolic {
+            coeff: -&self.coeff,
+            ..self.clone()
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{split_square, Symbolic};
+    use crate::bigrat::BigRat;
+    use crate::numeric::Numeric;
+
+    #[test]
+    fn test_split_square() {
+        assert_eq!(split_square(12), (2, 3));
+        assert_eq!(split_square(49), (7, 1));
+        assert_eq!(split_square(2 * 3 * 5 * 7 * 7), (7, 30));
+        assert_eq!(split_square(1_000_003 * 1_000_003), (1_000_003, 1));
+    }
+
+    #[test]
+    fn test_display() {
+        let show = |value: Numeric| match value {
+            Numeric::Symbolic(ref s) => s.to_string(),
+            other => panic!("Expected symbolic, got {:?}", other),
+        };
+        let sqrt = |n, d| Symbolic::root_rational(&BigRat::small_ratio(n, d), 2).unwrap();
+        let pi = Symbolic::pi();
+        assert_eq!(show(sqrt(8, 1)), "2 sqrt(2)");
+        assert_eq!(show(sqrt(1, 2)), "sqrt(2)/2");
+        assert_eq!(show(&pi / &Numeric::from(180)), "pi/180");
+        assert_eq!(
+            show(&Numeric::one() / &(&pi * &Numeric::from(2))),
+            "1/(2 pi)"
+        );
+        assert_eq!(show(&(&pi * &pi) * &Numeric::from(-3)), "-3 pi^2");
+        assert_eq!(&sqrt(2, 1) * &sqrt(2, 1), Numeric::from(2));
+    }
+}
@@ -10,11 +10,17 @@ use chrono_tz::Tz;
 use std::iter::Peekable;
 use std::str::Chars;
 
+/// The parts of an ISO 8601 duration, as the integer and fractional
+/// parts of each number with the unit it's in.
+pub type DurationParts = Vec<(String, Option<String>, &'static str)>;
+
 #[derive(Debug, Clone)]
 pub enum Token {
     Newline,
     Comment(usize),
     Ident(String),
+    /// An identifier immediately followed by `(`.
+    Call(String),
     Decimal(String, Option<String>, Option<String>),
     Hex(String),
     Oct(String),
@@ -28,15 +34,27 @@ pub enum Token {
     Eof,
     LPar,
     RPar,
+    LBracket,
+    RBracket,
     Plus,
     Minus,
+    PlusMinus,
     Asterisk,
     DashArrow,
     Colon,
     Date(Vec<DateToken>),
+    /// An ISO 8601 duration like `PT1H30M`.
+    Duration(DurationParts),
     Comma,
-    Degree(Degree),
     Percent,
+    Bang,
+    DoubleSlash,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    DoubleEquals,
+    NotEquals,
     Error(String),
 }
 
@@ -44,6 +62,7 @@ fn describe(token: &Token) -> String {
     match *token {
         Token::Newline | Token::Comment(_) => "\\n".to_owned(),
         Token::Ident(_) => "ident".to_owned(),
+        Token::Call(_) => "function call".to_owned(),
         Token::Decimal(_, _, _) => "number".to_owned(),
         Token::Hex(_) => "hex".to_owned(),
         Token::Oct(_) => "octal".to_owned(),
@@ -57,44 +76,96 @@ fn describe(token: &Token) -> String {
         Token::Eof => "eof".to_owned(),
         Token::LPar => "`(`".to_owned(),
         Token::RPar => "`)`".to_owned(),
+        Token::LBracket => "`[`".to_owned(),
+        Token::RBracket => "`]`".to_owned(),
         Token::Plus => "`+`".to_owned(),
         Token::Minus => "`-`".to_owned(),
+        Token::PlusMinus => "`±`".to_owned(),
         Token::Asterisk => "`*`".to_owned(),
         Token::DashArrow => "`->`".to_owned(),
         Token::Colon => "`:`".to_owned(),
         Token::Date(_) => "date literal".to_owned(),
+        Token::Duration(_) => "duration literal".to_owned(),
         Token::Comma => "`,`".to_owned(),
         Token::Percent => "%".to_owned(),
-        Token::Degree(ref deg) => format!("`{}`", deg),
+        Token::Bang => "`!`".to_owned(),
+        Token::DoubleSlash => "`//`".to_owned(),
+        Token::Less => "`<`".to_owned(),
+        Token::LessEqual => "`<=`".to_owned(),
+        Token::Greater => "`>`".to_owned(),
+        Token::GreaterEqual => "`>=`".to_owned(),
+        Token::DoubleEquals => "`==`".to_owned(),
+        Token::NotEquals => "`!=`".to_owned(),
         Token::Error(ref e) => format!("<{}>", e),
     }
 }
 
+/// The second field records whether the last token ended a value,
+/// which is what tells `a // b` apart from a line comment.
 #[derive(Clone)]
-pub struct TokenIterator<'a>(Peekable<Chars<'a>>);
+pub struct TokenIterator<'a>(Peekable<Chars<'a>>, bool);
 
 impl<'a> TokenIterator<'a> {
     pub fn new(input: &'a str) -> TokenIterator<'a> {
-        TokenIterator(input.chars().peekable())
+        TokenIterator(input.chars().peekable(), false)
     }
-}
 
-impl<'a> Iterator for TokenIterator<'a> {
-    type Item = Token;
+    /// `//` is floor division only when it follows a value and is
+    /// followed by a number or a bracket, so that `1 // note` and
+    /// `1 // *3` remain comments.
+    fn is_floor_div(&self) -> bool {
+        if !self.1 {
+            return false;
+        }
+        let mut lookahead = self.0.clone();
+        lookahead.next();
+        while let Some(&c) = lookahead.peek() {
+            if c == ' ' || c == '\t' {
+                lookahead.next();
+            } else {
+                return c.is_ascii_digit() || ".-([".contains(c);
+            }
+        }
+        false
+    }
 
-    fn next(&mut self) -> Option<Token> {
+    /// Picks `with` if the next char is `=`, which it consumes.
+    fn followed_by_equals(&mut self, without: Token, with: Token) -> Token {
+        if self.0.peek() == Some(&'=') {
+            self.0.next();
+            with
+        } else {
+            without
+        }
+    }
+
+    fn next_token(&mut self) -> Option<Token> {
         if self.0.peek().is_none() {
             return Some(Token::Eof);
         }
         let res = match self.0.next().unwrap() {
-            ' ' | '\t' => return self.next(),
+            ' ' | '\t' => return self.next_token(),
             '\n' => Token::Newline,
             '(' => Token::LPar,
             ')' => Token::RPar,
-            '+' => Token::Plus,
+            '[' => Token::LBracket,
+            ']' => Token::RBracket,
+            '+' => {
+                let mut lookahead = self.0.clone();
+                if lookahead.next() == Some('/') && lookahead.next() == Some('-') {
+                    self.0 = lookahead;
+                    Token::PlusMinus
+                } else {
+                    Token::Plus
+                }
+            }
+            '±' => Token::PlusMinus,
             ';' => Token::Semicolon,
             '%' => Token::Percent,
-            '=' => Token::Equals,
+            '!' => self.followed_by_equals(Token::Bang, Token::NotEquals),
+            '=' => self.followed_by_equals(Token::Equals, Token::DoubleEquals),
+            '<' => self.followed_by_equals(Token::Less, Token::LessEqual),
+            '>' => self.followed_by_equals(Token::Greater, Token::GreaterEqual),
             '^' => Token::Caret,
             ',' => Token::Comma,
             // U+2215 ∕ DIVISION SLASH
@@ -118,14 +189,18 @@ impl<'a> Iterator for TokenIterator<'a> {
                 _ => Token::Minus,
             },
             '\u{2212}' => Token::Minus,
-            '/' => match self.0.peek() {
-                Some(&'/') => loop {
+            '/' => match self.0.peek().cloned() {
+                Some('/') if self.is_floor_div() => {
+                    self.0.next();
+                    Token::DoubleSlash
+                }
+                Some('/') => loop {
                     match self.0.next() {
                         None | Some('\n') => return Some(Token::Comment(1)),
                         _ => (),
                     }
                 },
-                Some(&'*') => {
+                Some('*') => {
                     let mut lines = 0;
                     loop {
                         if let Some(&'\n') = self.0.peek() {
@@ -413,6 +488,12 @@ impl<'a> Iterator for TokenIterator<'a> {
                 Token::Ident(buf)
             }
             x => {
+                if x == 'P' {
+                    if let Some((parts, rest)) = iso_duration(self.0.clone()) {
+                        self.0 = rest;
+                        return Some(Token::Duration(parts));
+                    }
+                }
                 let mut buf = String::new();
                 buf.push(x);
                 while let Some(c) = self.0.peek().cloned() {
@@ -423,18 +504,10 @@ impl<'a> Iterator for TokenIterator<'a> {
                     }
                 }
                 match &*buf {
-                    "degC" | "°C" | "celsius" | "℃" => Token::Degree(Degree::Celsius),
-                    "degF" | "°F" | "fahrenheit" | "℉" => Token::Degree(Degree::Fahrenheit),
-                    "degRé" | "°Ré" | "degRe" | "°Re" | "réaumur" | "reaumur" => {
-                        Token::Degree(Degree::Reaumur)
-                    }
-                    "degRø" | "°Rø" | "degRo" | "°Ro" | "rømer" | "romer" => {
-                        Token::Degree(Degree::Romer)
-                    }
-                    "degDe" | "°De" | "delisle" => Token::Degree(Degree::Delisle),
-                    "degN" | "°N" | "degnewton" => Token::Degree(Degree::Newton),
                     "per" => Token::Slash,
                     "to" | "in" => Token::DashArrow,
+                    // `f(x)` is a call, while `kg (m/s)` is juxtaposition.
+                    _ if self.0.peek() == Some(&'(') => Token::Call(buf),
                     _ => Token::Ident(buf),
                 }
             }
@@ -443,6 +516,84 @@ impl<'a> Iterator for TokenIterator<'a> {
     }
 }
 
+/// Reads the rest of an ISO 8601 duration after its `P`, like
+/// `1Y2M3DT4H5M6.5S` or `2W`, returning its parts and the iterator
+/// after it. Returns `None` if the text isn't one, so that it can be
+/// read as an identifier instead.
+fn iso_duration(mut iter: Peekable<Chars<'_>>) -> Option<(DurationParts, Peekable<Chars<'_>>)> {
+    const DATE: &[(char, &str)] = &[('Y', "year"), ('M', "month"), ('W', "week"), ('D', "day")];
+    const TIME: &[(char, &str)] = &[('H', "hour"), ('M', "minute"), ('S', "second")];
+
+    let mut parts = vec![];
+    let mut units = DATE;
+    let mut date_parts = None;
+    loop {
+        match iter.peek().cloned() {
+            Some('T') if date_parts.is_none() => {
+                iter.next();
+                units = TIME;
+                date_parts = Some(parts.len());
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let mut integer = String::new();
+                while let Some(c) = iter.peek().cloned().filter(char::is_ascii_digit) {
+                    iter.next();
+                    integer.push(c);
+                }
+                let frac = if let Some('.') | Some(',') = iter.peek() {
+                    iter.next();
+                    let mut frac = String::new();
+                    while let Some(c) = iter.peek().cloned().filter(char::is_ascii_digit) {
+                        iter.next();
+                        frac.push(c);
+                    }
+                    Some(frac)
+                } else {
+                    None
+                };
+                let designator = iter.next()?;
+                let index = units.iter().position(|&(d, _)| d == designator)?;
+                parts.push((integer, frac, units[index].1));
+                units = &units[index + 1..];
+            }
+            _ => break,
+        }
+    }
+    // `T` has to be followed by a time, and the duration can't run on
+    // into an identifier.
+    if parts.is_empty()
+        || date_parts == Some(parts.len())
+        || matches!(iter.peek(), Some(&c) if c.is_alphanumeric() || c == '_' || c == '$')
+    {
+        return None;
+    }
+    Some((parts, iter))
+}
+
+impl<'a> Iterator for TokenIterator<'a> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        let res = self.next_token();
+        self.1 = matches!(
+            res,
+            Some(Token::Decimal(_, _, _))
+                | Some(Token::Hex(_))
+                | Some(Token::Oct(_))
+                | Some(Token::Bin(_))
+                | Some(Token::Ident(_))
+                | Some(Token::Quote(_))
+                | Some(Token::Date(_))
+                | Some(Token::Duration(_))
+                | Some(Token::RPar)
+                | Some(Token::RBracket)
+                | Some(Token::Percent)
+                | Some(Token::Bang)
+        );
+        res
+    }
+}
+
 pub type Iter<'a> = Peekable<TokenIterator<'a>>;
 
 fn attr_from_name(name: &str) -> Option<&'static str> {
@@ -498,15 +649,24 @@ fn parse_function(iter: &mut Iter<'_>, func: Function) -> Expr {
 fn parse_radix(num: &str, base: u32, description: &str) -> Expr {
     BigInt::from_str_radix(num, base)
         .map(|x| BigRat::ratio(&x, &BigInt::one()))
-        .map(Numeric::Rational)
+        .map(Numeric::from)
         .map(Expr::new_const)
         .unwrap_or_else(|_| Expr::new_error(format!("Failed to parse {}", description)))
 }
 
+/// Functions which share their name with a unit, like `min`. These are
+/// only parsed as functions when immediately followed by `(`.
+fn is_unit_name(func: &Function) -> bool {
+    match *func {
+        Function::Min => true,
+        _ => false,
+    }
+}
+
 fn parse_term(iter: &mut Iter<'_>) -> Expr {
     match iter.next().unwrap() {
         Token::Ident(ref id) => {
-            if let Some(func) = Function::from_name(id) {
+            if let Some(func) = Function::from_name(id).filter(|func| !is_unit_name(func)) {
                 parse_function(iter, func)
             } else if let Some(attr) = attr_from_name(id) {
                 match iter.peek().cloned().unwrap() {
@@ -529,6 +689,10 @@ fn parse_term(iter: &mut Iter<'_>) -> Expr {
                 }
             }
         }
+        Token::Call(ref id) => match Function::from_name(id) {
+            Some(func) => parse_function(iter, func),
+            None => parse_function(iter, Function::Custom(id.clone())),
+        },
         Token::Quote(string) => Expr::Quote { string },
         Token::Decimal(num, frac, exp) => crate::number::Number::from_parts(
             &*num,
@@ -537,11 +701,24 @@ fn parse_term(iter: &mut Iter<'_>) -> Expr {
         )
         .map(Expr::new_const)
         .unwrap_or_else(Expr::new_error),
+        Token::Duration(parts) => parts
+            .into_iter()
+            .map(|(integer, frac, unit)| {
+                crate::number::Number::from_parts(&integer, frac.as_deref(), None).map(|value| {
+                    Expr::new_mul(vec![
+                        Expr::new_const(value),
+                        Expr::new_unit(unit.to_owned()),
+                    ])
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map(|terms| terms.into_iter().reduce(Expr::new_add).unwrap())
+            .unwrap_or_else(Expr::new_error),
         Token::Hex(num) => parse_radix(&*num, 16, "hex"),
         Token::Oct(num) => parse_radix(&*num, 8, "octal"),
         Token::Bin(num) => parse_radix(&*num, 2, "binary"),
-        Token::Plus => Expr::new_plus(parse_term(iter)),
-        Token::Minus => Expr::new_negate(parse_term(iter)),
+        Token::Plus => Expr::new_plus(parse_factorial(iter)),
+        Token::Minus => Expr::new_negate(parse_factorial(iter)),
         Token::LPar => {
             let res = parse_expr(iter);
             match iter.next().unwrap() {
@@ -549,6 +726,35 @@ fn parse_term(iter: &mut Iter<'_>) -> Expr {
                 x => Expr::new_error(format!("Expected `)`, got {}", describe(&x))),
             }
         }
+        Token::LBracket => {
+            // `[a, b]` is an interval. Anything else is a list, with a
+            // trailing comma for lists of one or two elements.
+            let mut items = vec![parse_expr(iter)];
+            let mut trailing = false;
+            loop {
+                match iter.next().unwrap() {
+                    Token::Comma => (),
+                    Token::RBracket if items.len() > 1 => break,
+                    x if items.len() > 1 => {
+                        return Expr::new_error(format!("Expected `]`, got {}", describe(&x)))
+                    }
+                    x => return Expr::new_error(format!("Expected `,`, got {}", describe(&x))),
+                }
+                if let Some(Token::RBracket) = iter.peek() {
+                    iter.next();
+                    trailing = true;
+                    break;
+                }
+                items.push(parse_expr(iter));
+            }
+            if items.len() == 2 && !trailing {
+                let upper = items.pop().unwrap();
+                let lower = items.pop().unwrap();
+                Expr::new_interval(lower, upper)
+            } else {
+                Expr::List { items }
+            }
+        }
         Token::Percent => Expr::new_unit("percent".to_owned()),
         Token::Date(tokens) => Expr::Date { tokens },
         Token::Comment(_) => parse_term(iter),
@@ -556,13 +762,32 @@ fn parse_term(iter: &mut Iter<'_>) -> Expr {
     }
 }
 
+/// Sign prefixes bind looser than `!`, so `-3!` is `-(3!)`.
+fn parse_factorial(iter: &mut Iter<'_>) -> Expr {
+    let mut left = parse_term(iter);
+    while let Some(&Token::Bang) = iter.peek() {
+        iter.next();
+        left = Expr::new_factorial(left);
+    }
+    left
+}
+
+/// Percent signs, which can be followed by `of` to take a percentage
+/// of something, as in `20% of 80 kg`.
 fn parse_suffix(iter: &mut Iter<'_>) -> Expr {
-    let left = parse_term(iter);
+    let left = parse_factorial(iter);
     match *iter.peek().unwrap() {
         Token::Percent => {
             let mut left = left;
             while let Some(&Token::Percent) = iter.peek() {
                 iter.next();
+                if let Some(Token::Ident(ref s)) = iter.peek() {
+                    if s == "of" {
+                        iter.next();
+                        let of = Expr::new_of("percent", parse_juxt(iter));
+                        return Expr::new_mul(vec![left, of]);
+                    }
+                }
                 left = Expr::new_mul(vec![left, Expr::new_unit("percent".to_owned())]);
             }
             left
@@ -607,12 +832,25 @@ fn parse_juxt(iter: &mut Iter<'_>) -> Expr {
             | Token::Minus
             | Token::DashArrow
             | Token::RPar
+            | Token::RBracket
             | Token::Newline
             | Token::Comment(_)
+            | Token::DoubleSlash
+            | Token::Less
+            | Token::LessEqual
+            | Token::Greater
+            | Token::GreaterEqual
+            | Token::DoubleEquals
+            | Token::NotEquals
+            | Token::PlusMinus
             | Token::Eof => break,
-            Token::Degree(deg) => {
-                iter.next();
-                terms = vec![Expr::new_suffix(deg, Expr::new_mul(terms))]
+            Token::Ident(ref name)
+                if binop_from_name(name).is_some()
+                    || name == "and"
+                    || name == "or"
+                    || name == "nearest" =>
+            {
+                break
             }
             _ => terms.push(parse_frac(iter)),
         }
@@ -624,25 +862,40 @@ fn parse_juxt(iter: &mut Iter<'_>) -> Expr {
     }
 }
 
+/// Operators spelled as words, which share the precedence of `/`.
+fn binop_from_name(name: &str) -> Option<BinOpType> {
+    match name {
+        "mod" => Some(BinOpType::Mod),
+        "nCr" => Some(BinOpType::Choose),
+        "nPr" => Some(BinOpType::Permute),
+        _ => None,
+    }
+}
+
 fn parse_div(iter: &mut Iter<'_>) -> Expr {
     let mut terms = vec![parse_juxt(iter)];
     loop {
-        match iter.peek().cloned().unwrap() {
-            Token::Slash => {
-                iter.next();
-                let left = if terms.len() == 1 {
-                    terms.pop().unwrap()
-                } else {
-                    Expr::new_mul(terms.drain(..).collect())
-                };
-                terms = vec![Expr::new_frac(left, parse_juxt(iter))];
-            }
+        let op = match iter.peek().cloned().unwrap() {
+            Token::Slash => BinOpType::Frac,
+            Token::DoubleSlash => BinOpType::FloorDiv,
+            Token::Ident(ref name) => match binop_from_name(name) {
+                Some(op) => op,
+                None => break,
+            },
             Token::Asterisk => {
                 iter.next();
                 terms.push(parse_juxt(iter));
+                continue;
             }
             _ => break,
-        }
+        };
+        iter.next();
+        let left = if terms.len() == 1 {
+            terms.pop().unwrap()
+        } else {
+            Expr::new_mul(std::mem::take(&mut terms))
+        };
+        terms = vec![Expr::new_bin(op, left, parse_juxt(iter))];
     }
     if terms.len() == 1 {
         terms.pop().unwrap()
@@ -651,31 +904,145 @@ fn parse_div(iter: &mut Iter<'_>) -> Expr {
     }
 }
 
-fn parse_add(iter: &mut Iter<'_>) -> Expr {
+/// Whether an expression is a plain number, like `5` or `-0.2`.
+fn is_number(expr: &Expr) -> bool {
+    match *expr {
+        Expr::Const { .. } => true,
+        Expr::UnaryOp(ref unaryop) => {
+            matches!(unaryop.op, UnaryOpType::Negative | UnaryOpType::Positive)
+                && is_number(&unaryop.expr)
+        }
+        _ => false,
+    }
+}
+
+/// Whether an expression is a number followed by units, like `2 cm`.
+fn is_quantity(exprs: &[Expr]) -> bool {
+    is_number(&exprs[0]) && !exprs[1..].iter().any(is_number)
+}
+
+/// A bare number on one side of `±` is in the units written on the
+/// other, so `5 ± 0.2 m` and `5 m ± 0.2` are both `(5 ± 0.2) m`.
+fn new_plus_minus(left: Expr, right: Expr) -> Expr {
+    match (left, right) {
+        (value, Expr::Mul { mut exprs }) if is_number(&value) && is_quantity(&exprs) => {
+            let error = exprs.remove(0);
+            exprs.insert(0, Expr::new_plus_minus(value, error));
+            Expr::new_mul(exprs)
+        }
+        (Expr::Mul { mut exprs }, error) if is_number(&error) && is_quantity(&exprs) => {
+            let value = exprs.remove(0);
+            exprs.insert(0, Expr::new_plus_minus(value, error));
+            Expr::new_mul(exprs)
+        }
+        (left, right) => Expr::new_plus_minus(left, right),
+    }
+}
+
+/// `±` binds looser than multiplication, so that `5 m ± 2 cm` is
+/// `(5 m) ± (2 cm)`.
+fn parse_plus_minus(iter: &mut Iter<'_>) -> Expr {
     let mut left = parse_div(iter);
+    while let Token::PlusMinus = *iter.peek().unwrap() {
+        iter.next();
+        let right = parse_div(iter);
+        left = new_plus_minus(left, right);
+    }
+    left
+}
+
+/// Whether an expression is a number of percent, like `15%`.
+fn is_percentage(expr: &Expr) -> bool {
+    match *expr {
+        Expr::Mul { ref exprs } => matches!(
+            exprs.last(),
+            Some(Expr::Unit { ref name }) if name == "percent"
+        ),
+        _ => false,
+    }
+}
+
+/// Adding or subtracting a percentage is a relative change, so
+/// `50 USD + 15%` is `50 USD (1 + 15%)`.
+fn parse_add(iter: &mut Iter<'_>) -> Expr {
+    let mut left = parse_plus_minus(iter);
     loop {
-        match *iter.peek().unwrap() {
-            Token::Plus => {
-                iter.next();
-                let right = parse_div(iter);
-                left = Expr::new_add(left, right)
-            }
-            Token::Minus => {
-                iter.next();
-                let right = parse_div(iter);
-                left = Expr::new_sub(left, right)
-            }
+        let op = match *iter.peek().unwrap() {
+            Token::Plus => BinOpType::Add,
+            Token::Minus => BinOpType::Sub,
             _ => return left,
+        };
+        iter.next();
+        let right = parse_plus_minus(iter);
+        left = if is_percentage(&right) {
+            let factor = Expr::new_bin(op, Expr::from(1), right);
+            Expr::new_mul(vec![left, factor])
+        } else {
+            Expr::new_bin(op, left, right)
+        };
+    }
+}
+
+fn parse_cmp(iter: &mut Iter<'_>) -> Expr {
+    let mut left = parse_add(iter);
+    loop {
+        let op = match *iter.peek().unwrap() {
+            Token::Less => BinOpType::Less,
+            Token::LessEqual => BinOpType::LessEqual,
+            Token::Greater => BinOpType::Greater,
+            Token::GreaterEqual => BinOpType::GreaterEqual,
+            Token::DoubleEquals => BinOpType::Equal,
+            Token::NotEquals => BinOpType::NotEqual,
+            _ => return left,
+        };
+        iter.next();
+        let right = parse_add(iter);
+        left = Expr::new_bin(op, left, right);
+    }
+}
+
+fn parse_not(iter: &mut Iter<'_>) -> Expr {
+    match *iter.peek().unwrap() {
+        Token::Ident(ref name) if name == "not" => {
+            iter.next();
+            Expr::new_unary(UnaryOpType::Not, parse_not(iter))
         }
+        _ => parse_cmp(iter),
     }
 }
 
+fn parse_and(iter: &mut Iter<'_>) -> Expr {
+    let mut left = parse_not(iter);
+    while let Token::Ident(ref name) = *iter.peek().unwrap() {
+        if name != "and" {
+            break;
+        }
+        iter.next();
+        let right = parse_not(iter);
+        left = Expr::new_bin(BinOpType::And, left, right);
+    }
+    left
+}
+
+fn parse_or(iter: &mut Iter<'_>) -> Expr {
+    let mut left = parse_and(iter);
+    while let Token::Ident(ref name) = *iter.peek().unwrap() {
+        if name != "or" {
+            break;
+        }
+        iter.next();
+        let right = parse_and(iter);
+        left = Expr::new_bin(BinOpType::Or, left, right);
+    }
+    left
+}
+
 fn parse_eq(iter: &mut Iter<'_>) -> Expr {
-    let left = parse_add(iter);
+    let left = parse_or(iter);
     match iter.peek().cloned().unwrap() {
         Token::Equals => {
             iter.next();
-            let right = parse_add(iter);
+            let right = parse_or(iter);
             Expr::new_equals(left, right)
         }
         _ => left,
@@ -690,6 +1057,12 @@ pub fn parse_unitlist(iter: &mut Iter<'_>) -> Option<Vec<String>> {
     let mut expecting_term = true;
     let mut res = vec![];
     loop {
+        // Left for `parse_nearest` and the `fraction` check after it.
+        if let Some(Token::Ident(ref s)) = iter.peek() {
+            if (s == "nearest" || s == "fraction" || s == "fractions") && !expecting_term {
+                break;
+            }
+        }
         match iter.next().unwrap() {
             Token::Ident(ref ident) if expecting_term => {
                 res.push(ident.clone());
@@ -732,6 +1105,128 @@ pub fn parse_offset(iter: &mut Iter<'_>) -> Option<i64> {
     Some(sign * (i64::from_str(&*hour).unwrap() * 3600 + i64::from_str(&*min).unwrap() * 60))
 }
 
+/// Parses the left side of a function definition, `name(a, b: quantity)`.
+fn parse_signature(iter: &mut Iter<'_>) -> Option<(String, Vec<FunctionParam>)> {
+    let name = match iter.next().unwrap() {
+        Token::Ident(name) | Token::Call(name) => name,
+        _ => return None,
+    };
+    if Function::from_name(&name).is_some() {
+        return None;
+    }
+    match iter.next().unwrap() {
+        Token::LPar => (),
+        _ => return None,
+    }
+    let mut params = vec![];
+    loop {
+        let name = match iter.next().unwrap() {
+            Token::RPar if params.is_empty() => break,
+            Token::Ident(name) => name,
+            _ => return None,
+        };
+        let quantity = match iter.peek().cloned().unwrap() {
+            Token::Colon => {
+                iter.next();
+                match iter.next().unwrap() {
+                    Token::Ident(quantity) => Some(quantity),
+                    _ => return None,
+                }
+            }
+            _ => None,
+        };
+        params.push(FunctionParam { name, quantity });
+        match iter.next().unwrap() {
+            Token::Comma => (),
+            Token::RPar => break,
+            _ => return None,
+        }
+    }
+    Some((name, params))
+}
+
+/// Consumes `n sig figs`, or one of its spellings like `n significant
+/// figures`, returning whether it was there.
+fn parse_sig_figs(iter: &mut Iter<'_>) -> bool {
+    iter.next();
+    let first = match iter.next() {
+        Some(Token::Ident(s)) => s,
+        _ => return false,
+    };
+    match &*first {
+        "sigfig" | "sigfigs" => true,
+        "sig" | "significant" => matches!(
+            iter.next(),
+            Some(Token::Ident(ref s)) if s == "fig" || s == "figs" || s == "figure" || s == "figures" || s == "digits"
+        ),
+        _ => false,
+    }
+}
+
+/// Parses `nearest 1/N` after the target of a conversion, which
+/// rounds the result to a mixed number. Returns `Ok(None)` if it isn't
+/// there.
+fn parse_nearest(iter: &mut Iter<'_>) -> Result<Option<u64>, String> {
+    match iter.peek() {
+        Some(Token::Ident(ref s)) if s == "nearest" => {
+            iter.next();
+        }
+        _ => return Ok(None),
+    }
+    match (iter.next(), iter.next(), iter.next()) {
+        (
+            Some(Token::Decimal(ref one, None, None)),
+            Some(Token::Slash),
+            Some(Token::Decimal(ref denom, None, None)),
+        ) if one == "1" => match u64::from_str_radix(denom, 10) {
+            Ok(0) => Err("Cannot round to the nearest 1/0".to_owned()),
+            Ok(v) => Ok(Some(v)),
+            Err(e) => Err(format!("Failed to parse denominator: {}", e)),
+        },
+        _ => Err("Expected a fraction like 1/16 after `nearest`".to_owned()),
+    }
+}
+
+/// Parses the base a conversion is shown in, like `hex` or `base 3`.
+fn parse_base(iter: &mut Iter<'_>) -> Result<Option<u8>, String> {
+    match iter.peek().cloned().unwrap() {
+        Token::Ident(ref s) if s == "base" => {
+            iter.next();
+            match iter.next() {
+                Some(Token::Decimal(int, None, None)) => match u64::from_str_radix(&int, 10) {
+                    Ok(v @ 2..=36) => Ok(Some(v as u8)),
+                    Ok(v) => Err(format!("Unsupported base {}, must be from 2 to 36", v)),
+                    Err(e) => Err(format!("Failed to parse base: {}", e)),
+                },
+                Some(x) => Err(format!("Expected decimal base, got {}", describe(&x))),
+                None => Err("Expected decimal base, got eof".to_string()),
+            }
+        }
+        Token::Ident(ref s) if s == "hex" || s == "hexadecimal" || s == "base16" => {
+            iter.next();
+            Ok(Some(16))
+        }
+        Token::Ident(ref s) if s == "oct" || s == "octal" || s == "base8" => {
+            iter.next();
+            Ok(Some(8))
+        }
+        Token::Ident(ref s) if s == "bin" || s == "binary" || s == "base2" => {
+            iter.next();
+            Ok(Some(2))
+        }
+        _ => Ok(None),
+    }
+}
+
+/// Checks that nothing is left after a conversion, so that trailing
+/// words aren't silently dropped.
+fn parse_end(iter: &mut Iter<'_>) -> Result<(), String> {
+    match *iter.peek().unwrap() {
+        Token::Eof | Token::Newline | Token::Comment(_) => Ok(()),
+        ref x => Err(format!("Expected end of conversion, got {}", describe(x))),
+    }
+}
+
 pub fn parse_query(iter: &mut Iter<'_>) -> Query {
     match iter.peek().cloned() {
         Some(Token::Ident(ref s)) if s == "factorize" => {
@@ -753,8 +1248,37 @@ pub fn parse_query(iter: &mut Iter<'_>) -> Query {
                 return Query::Search(s.clone());
             }
         }
+        Some(Token::Ident(ref s)) if s == "unset" => {
+            iter.next();
+            if let Some(Token::Ident(ref s)) = iter.peek().cloned() {
+                iter.next();
+                return match iter.peek().cloned().unwrap() {
+                    Token::Eof => Query::Unset(s.clone()),
+                    x => Query::Error(format!(
+                        "Expected eof after variable name, got {}",
+                        describe(&x)
+                    )),
+                };
+            }
+            return Query::Error("Expected variable name after unset".to_owned());
+        }
         _ => (),
     }
+    let mut copy = iter.clone();
+    if let Some((name, params)) = parse_signature(&mut copy) {
+        if let Some(Token::Equals) = copy.peek() {
+            copy.next();
+            *iter = copy;
+            let body = parse_or(iter);
+            return Query::Function(
+                name,
+                FunctionDef {
+                    params,
+                    body: ExprString(body),
+                },
+            );
+        }
+    }
     let left = parse_eq(iter);
     match iter.peek().cloned().unwrap() {
         Token::DashArrow => {
@@ -763,9 +1287,52 @@ pub fn parse_query(iter: &mut Iter<'_>) -> Query {
             let mut copy = iter.clone();
             if let Some(res) = parse_unitlist(&mut copy) {
                 *iter = copy;
-                return Query::Convert(left, Conversion::List(res), None, Digits::Default);
+                let fraction = match iter.peek() {
+                    Some(Token::Ident(ref s)) if s == "fraction" || s == "fractions" => {
+                        iter.next();
+                        true
+                    }
+                    _ => false,
+                };
+                let digits = match parse_nearest(iter) {
+                    Ok(Some(denom)) => Digits::Fraction(Some(denom)),
+                    Ok(None) if fraction => Digits::Fraction(None),
+                    Ok(None) => Digits::Default,
+                    Err(e) => return Query::Error(e),
+                };
+                if let Err(e) = parse_end(iter) {
+                    return Query::Error(e);
+                }
+                return Query::Convert(left, Conversion::List(res), None, digits);
             }
+            let mut copy = iter.clone();
             let digits = match iter.peek().cloned().unwrap() {
+                Token::Decimal(ref int, None, None) if parse_sig_figs(&mut copy) => {
+                    *iter = copy;
+                    match u64::from_str_radix(int, 10) {
+                        Ok(0) => {
+                            return Query::Error(
+                                "Significant figures must be at least 1".to_owned(),
+                            )
+                        }
+                        Ok(v) => Digits::SigFigs(v),
+                        Err(e) => {
+                            return Query::Error(format!(
+                                "Failed to parse significant figures: {}",
+                                e
+                            ))
+                        }
+                    }
+                }
+                // `fraction max n` is a conversion rather than a way of
+                // writing the number.
+                Token::Ident(ref s)
+                    if (s == "fraction" || s == "fractions")
+                        && !matches!(copy.nth(1), Some(Token::Ident(ref s)) if s == "max") =>
+                {
+                    iter.next();
+                    Digits::Fraction(None)
+                }
                 Token::Ident(ref s) if s == "digits" => {
                     iter.next();
                     match iter.peek().cloned() {
@@ -783,63 +1350,177 @@ pub fn parse_query(iter: &mut Iter<'_>) -> Query {
                 }
                 _ => Digits::Default,
             };
-            let base = match iter.peek().cloned().unwrap() {
-                Token::Ident(ref s) if s == "base" => {
+            let base = match parse_base(iter) {
+                Ok(base) => base,
+                Err(e) => return Query::Error(e),
+            };
+            let right = match iter.peek().cloned().unwrap() {
+                Token::Eof => Conversion::None,
+                Token::Ident(ref s) if s == "nearest" => Conversion::None,
+                Token::Plus | Token::Minus => {
+                    let mut old = iter.clone();
+                    if let Some(off) = parse_offset(iter) {
+                        Conversion::Offset(off)
+                    } else {
+                        Conversion::Expr(parse_eq(&mut old))
+                    }
+                }
+                Token::Ident(ref s) if s == "polar" => {
+                    iter.next();
+                    Conversion::Polar
+                }
+                Token::Ident(ref s) if s == "rectangular" => {
+                    iter.next();
+                    Conversion::Rectangular
+                }
+                Token::Ident(ref s) if s == "eng" => {
+                    iter.next();
+                    match iter.peek().unwrap() {
+                        Token::Eof => Conversion::Eng(None),
+                        _ => Conversion::Eng(Some(parse_eq(iter))),
+                    }
+                }
+                Token::Ident(ref s) if s == "fraction" || s == "fractions" => {
+                    iter.next();
                     iter.next();
                     match iter.next() {
                         Some(Token::Decimal(int, None, None)) => {
-                            match u64::from_str_radix(&*int, 10) {
-                                Ok(v @ 2..=36) => Some(v as u8),
-                                Ok(v) => {
-                                    return Query::Error(format!(
-                                        "Unsupported base {}, must be from 2 to 36",
-                                        v
-                                    ))
+                            match u64::from_str_radix(&int, 10) {
+                                Ok(0) => {
+                                    return Query::Error(
+                                        "Maximum denominator must be at least 1".to_owned(),
+                                    )
                                 }
+                                Ok(v) => Conversion::Fraction(v),
                                 Err(e) => {
-                                    return Query::Error(format!("Failed to parse base: {}", e))
+                                    return Query::Error(format!(
+                                        "Failed to parse maximum denominator: {}",
+                                        e
+                                    ))
                                 }
                             }
                         }
                         Some(x) => {
                             return Query::Error(format!(
-                                "Expected decimal base, got {}",
+                                "Expected maximum denominator, got {}",
                                 describe(&x)
                             ))
                         }
-                        None => return Query::Error("Expected decimal base, got eof".to_string()),
+                        None => {
+                            return Query::Error("Expected maximum denominator, got eof".to_owned())
+                        }
                     }
                 }
-                Token::Ident(ref s) if s == "hex" || s == "hexadecimal" || s == "base16" => {
+                Token::Ident(ref s) if s == "continued" => {
                     iter.next();
-                    Some(16)
+                    match iter.next() {
+                        Some(Token::Ident(ref s)) if s == "fraction" || s == "fractions" => {
+                            Conversion::ContinuedFraction
+                        }
+                        Some(x) => {
+                            return Query::Error(format!(
+                                "Expected `fraction` after `continued`, got {}",
+                                describe(&x)
+                            ))
+                        }
+                        None => {
+                            return Query::Error(
+                                "Expected `fraction` after `continued`, got eof".to_owned(),
+                            )
+                        }
+                    }
                 }
-                Token::Ident(ref s) if s == "oct" || s == "octal" || s == "base8" => {
+                Token::Ident(ref s) if s == "iso8601" => {
                     iter.next();
-                    Some(8)
+                    match iter.peek().unwrap() {
+                        Token::Ident(ref s) if s == "duration" => {
+                            iter.next();
+                            Conversion::Iso8601Duration
+                        }
+                        _ => Conversion::Iso8601,
+                    }
                 }
-                Token::Ident(ref s) if s == "bin" || s == "binary" || s == "base2" => {
+                Token::Ident(ref s) if s == "rfc2822" => {
                     iter.next();
-                    Some(2)
+                    Conversion::Rfc2822
                 }
-                _ => None,
-            };
-            let right = match iter.peek().cloned().unwrap() {
-                Token::Eof => Conversion::None,
-                Token::Degree(deg) => Conversion::Degree(deg),
-                Token::Plus | Token::Minus => {
-                    let mut old = iter.clone();
-                    if let Some(off) = parse_offset(iter) {
-                        Conversion::Offset(off)
-                    } else {
-                        Conversion::Expr(parse_eq(&mut old))
+                Token::Ident(ref s) if s == "unix" => {
+                    iter.next();
+                    Conversion::Unix
+                }
+                Token::Ident(ref s) if s == "unixms" => {
+                    iter.next();
+                    Conversion::UnixMs
+                }
+                Token::Ident(ref s) if s == "strftime" => {
+                    iter.next();
+                    match iter.next().unwrap() {
+                        // Double quotes make an identifier.
+                        Token::Quote(format) | Token::Ident(format) => Conversion::Strftime(format),
+                        x => {
+                            return Query::Error(format!(
+                                "Expected format string after `strftime`, got {}",
+                                describe(&x)
+                            ))
+                        }
                     }
                 }
-                Token::Ident(ref s) if Tz::from_str(s).is_ok() => Conversion::Timezone(
-                    Tz::from_str(s).expect("Running from_str a second time failed"),
-                ),
+                Token::Ident(ref s) if s == "auto" => {
+                    iter.next();
+                    let binary = match iter.peek().unwrap() {
+                        Token::Ident(ref s) if s == "binary" => {
+                            iter.next();
+                            true
+                        }
+                        _ => false,
+                    };
+                    let unit = match iter.next().unwrap() {
+                        Token::Eof => None,
+                        Token::Ident(name) => Some(name),
+                        x => {
+                            return Query::Error(format!(
+                                "Expected unit name after `auto`, got {}",
+                                describe(&x)
+                            ))
+                        }
+                    };
+                    Conversion::Auto { binary, unit }
+                }
+                Token::Ident(ref s) if Tz::from_str(s).is_ok() => {
+                    iter.next();
+                    Conversion::Timezone(
+                        Tz::from_str(s).expect("Running from_str a second time failed"),
+                    )
+                }
                 _ => Conversion::Expr(parse_eq(iter)),
             };
+            let (digits, base) = match parse_nearest(iter) {
+                Ok(None) => (digits, base),
+                Ok(Some(denom)) if matches!(digits, Digits::Default | Digits::Fraction(None)) => {
+                    // The base can also come last, as in
+                    // `-> fraction nearest 1/16 hex`.
+                    let base = match (base, parse_base(iter)) {
+                        (_, Err(e)) => return Query::Error(e),
+                        (Some(_), Ok(Some(_))) => {
+                            return Query::Error("Expected only one base".to_owned())
+                        }
+                        (base, Ok(after)) => base.or(after),
+                    };
+                    if let Err(e) = parse_end(iter) {
+                        return Query::Error(e);
+                    }
+                    (Digits::Fraction(Some(denom)), base)
+                }
+                Ok(Some(_)) => {
+                    return Query::Error(
+                        "Cannot round to a fraction and a number of digits at once".to_owned(),
+                    )
+                }
+                Err(e) => return Query::Error(e),
+            };
+            if let Err(e) = parse_end(iter) {
+                return Query::Error(e);
+            }
             Query::Convert(left, right, base, digits)
         }
         _ => Query::Expr(left),
@@ -874,14 +1555,102 @@ mod test {
         assert_eq!(parse("a / b / c"), "(a / b) / c");
     }
 
+    #[test]
+    fn call_vs_juxt() {
+        assert_eq!(parse("f(x, y z)"), "f(x, y z)");
+        assert_eq!(parse("sqrt(x) y"), "sqrt(x) y");
+        assert_eq!(parse("kg (m / s)"), "kg (m / s)");
+        assert_eq!(parse("kg(m / s)"), "kg(m / s)");
+    }
+
+    #[test]
+    fn plus_minus_prec() {
+        assert_eq!(parse("5 ± 0.2 m"), "(5 ± 0.2) m");
+        assert_eq!(parse("5 +/- 0.2 m"), "(5 ± 0.2) m");
+        assert_eq!(parse("a ± b|c ± d"), "a ± b / c ± d");
+        assert_eq!(parse("5 m ± 2 cm"), "5 m ± 2 cm");
+        assert_eq!(parse("5 m ± 2"), "(5 ± 2) m");
+        assert_eq!(parse("a ± b + c"), "(a ± b) + c");
+        assert_eq!(parse("(a ± b)^2"), "(a ± b)^2");
+        assert_eq!(parse("a +/b"), "a + <error: Expected term, got `/`> b");
+    }
+
+    #[test]
+    fn interval() {
+        assert_eq!(parse("[a, b + c] kg"), "[a, b + c] kg");
+        assert_eq!(parse("[a b]"), "<error: Expected `,`, got `]`>");
+    }
+
+    #[test]
+    fn list() {
+        assert_eq!(parse("[a, b, c + d] kg"), "[a, b, c + d] kg");
+        assert_eq!(parse("[a, b,]"), "[a, b,]");
+        assert_eq!(parse("[a,]"), "[a,]");
+        assert_eq!(parse("[a, b, c"), "<error: Expected `]`, got eof>");
+    }
+
+    #[test]
+    fn iso8601_duration() {
+        assert_eq!(parse("PT1H30M"), "1 hour + 30 minute");
+        assert_eq!(parse("P1DT4H5.5S"), "(1 day + 4 hour) + 5.5 second");
+        assert_eq!(parse("2 P2W"), "2 (2 week)");
+        // Anything else starting with P is an identifier.
+        assert_eq!(parse("PT"), "PT");
+        assert_eq!(parse("P1H"), "P1H");
+        assert_eq!(parse("PT1H30Mx"), "PT1H30Mx");
+        assert_eq!(parse("P2DT"), "P2DT");
+    }
+
     #[test]
     fn suffix_prec() {
         assert_eq!(parse("a b °C + x y °F"), "a b °C + x y °F");
-        assert_eq!(parse("a b °C c"), "(a b °C) c");
+        // Nonlinear units are ordinary names until evaluation, where
+        // they apply to the terms before them.
+        assert_eq!(parse("a b °C c"), "a b °C c");
         assert_eq!(parse("a °C / x"), "a °C / x");
         assert_eq!(parse("a °C * x"), "(a °C) x");
     }
 
+    #[test]
+    fn integer_ops() {
+        assert_eq!(parse("a mod b"), "a mod b");
+        assert_eq!(parse("a b mod c d"), "a b mod c d");
+        assert_eq!(parse("a nCr b nPr c"), "(a nCr b) nPr c");
+        assert_eq!(parse("100 m // 3 m"), "100 m // 3 m");
+        assert_eq!(parse("a + b mod c"), "a + b mod c");
+        assert_eq!(parse("mod(a, b)"), "mod(a, b)");
+        assert_eq!(parse("1 // note"), "1");
+        assert_eq!(parse("// (note)"), "<error: Expected term, got eof>");
+    }
+
+    #[test]
+    fn factorial_prec() {
+        assert_eq!(parse("a!"), "a!");
+        assert_eq!(parse("-a!"), "-a!");
+        assert_eq!(parse("(-a)!"), "(-a)!");
+        assert_eq!(parse("(a b)!"), "(a b)!");
+        assert_eq!(parse("a!^b!"), "a!^b!");
+        assert_eq!(parse("a!!"), "a!!");
+        assert_eq!(parse("a! b"), "a! b");
+    }
+
+    #[test]
+    fn comparison_prec() {
+        assert_eq!(parse("a + b < c d"), "a + b < c d");
+        assert_eq!(parse("a <= b and c != d"), "a <= b and c != d");
+        assert_eq!(
+            parse("a > b or c >= d and e == f"),
+            "a > b or c >= d and e == f"
+        );
+        assert_eq!(parse("(a or b) and c"), "(a or b) and c");
+        assert_eq!(parse("not a < b and c"), "not a < b and c");
+        assert_eq!(parse("not (a and b)"), "not (a and b)");
+        assert_eq!(parse("(not a) + b"), "(not a) + b");
+        assert_eq!(parse("if(a < b, c, d)"), "if(a < b, c, d)");
+        assert_eq!(parse("a = b < c"), "a = b < c");
+        assert_eq!(parse("a! != b"), "a! != b");
+    }
+
     #[test]
     fn number_lex() {
         assert_eq!(
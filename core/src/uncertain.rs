@@ -0,0 +1,268 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::ast::Function;
+use crate::context::Context;
+use crate::number::{Number, NumberParts};
+use crate::numeric::{Digits, Numeric};
+use crate::value::Show;
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// A number with a standard uncertainty, like `5.0 ± 0.2 m`.
+///
+/// Uncertainties are propagated to first order, assuming that all
+/// inputs are independent: each input contributes its uncertainty
+/// times the partial derivative of the result with respect to it, and
+/// the contributions are added in quadrature.
+#[derive(Clone, Debug)]
+pub struct Uncertain {
+    pub value: Number,
+    /// The standard uncertainty, in the same units as `value`. Never
+    /// negative.
+    pub error: Numeric,
+}
+
+fn is_zero(value: &Numeric) -> bool {
+    value.to_f64() == 0.0
+}
+
+/// Adds two independent uncertainties, `sqrt(a^2 + b^2)`. The result
+/// stays exact if either is zero.
+fn quadrature(a: &Numeric, b: &Numeric) -> Numeric {
+    if is_zero(a) {
+        b.abs()
+    } else if is_zero(b) {
+        a.abs()
+    } else {
+        Numeric::Float(a.to_f64().hypot(b.to_f64()))
+    }
+}
+
+impl Uncertain {
+    pub fn new(value: Number, error: Number) -> Result<Uncertain, String> {
+        if value.unit != error.unit {
+            return Err("Uncertainty must have the same units as the value".to_string());
+        }
+        if value.value.is_complex() || error.value.is_complex() {
+            return Err("Uncertainties of complex numbers are not supported".to_string());
+        }
+        Ok(Uncertain {
+            value,
+            error: error.value.abs(),
+        })
+    }
+
+    /// A number with no uncertainty.
+    pub fn exact(value: Number) -> Uncertain {
+        Uncertain {
+            value,
+            error: Numeric::zero(),
+        }
+    }
+
+    pub fn pow(&self, exp: &Uncertain, precision: u64) -> Result<Uncertain, String> {
+        let value = self.value.pow(&exp.value, precision)?;
+        let result = value.value.abs();
+        let base = &self.value.value;
+        // d/da a^b = b a^(b - 1) = b f / a
+        let from_base = if is_zero(&self.error) {
+            Numeric::zero()
+        } else if is_zero(base) {
+            let b = exp.value.value.to_f64();
+            if b > 1.0 {
+                Numeric::zero()
+            } else if b == 1.0 {
+                self.error.clone()
+            } else {
+                return Err("Uncertainty is unbounded at zero".to_string());
+            }
+        } else {
+            &(&(&exp.value.value.abs() * &result) / &base.abs()) * &self.error
+        };
+        // d/db a^b = f ln a
+        let from_exp = if is_zero(&exp.error) {
+            Numeric::zero()
+        } else if !self.value.dimless() {
+            return Err("Uncertain exponents require a dimensionless base".to_string());
+        } else if *base <= Numeric::zero() {
+            return Err("Uncertain exponents require a positive base".to_string());
+        } else {
+            Numeric::Float((result.to_f64() * base.to_f64().ln() * exp.error.to_f64()).abs())
+        };
+        Ok(Uncertain {
+            value,
+            error: quadrature(&from_base, &from_exp),
+        })
+    }
+
+    pub fn error_string(error: &Numeric, base: u8, digits: Digits) -> String {
+        // Rounding the error to the same fraction as the value would
+        // hide it.
+        let digits = match digits {
+            Digits::Fraction(_) => Digits::Default,
+            digits => digits,
+        };
+        let (exact, approx) = error.string_repr(base, digits);
+        approx
+            .or(exact)
+            .expect("string_repr returned neither exact nor approx value")
+    }
+
+    pub fn to_parts(&self, context: &Context) -> NumberParts {
+        self.to_parts_in(context, 10, Digits::Default)
+    }
+
+    /// Like `to_parts`, but with both numbers shown in the given base
+    /// and number of digits.
+    pub fn to_parts_in(&self, context: &Context, base: u8, digits: Digits) -> NumberParts {
+        // The uncertainty is shown in the same (possibly prefixed)
+        // units as the value.
+        let pretty = self.value.prettify(context);
+        let error = if is_zero(&self.value.value) {
+            self.error.clone()
+        } else {
+            &(&self.error * &pretty.value) / &self.value.value
+        };
+        let (exact, approx) = pretty.numeric_value(base, digits);
+        NumberParts {
+            exact_value: exact,
+            approx_value: approx,
+            uncertainty: Some(Uncertain::error_string(&error.abs(), base, digits)),
+            ..self.value.to_parts(context)
+        }
+    }
+}
+
+/// Partial derivatives of a built-in function with respect to each of
+/// its arguments, for first-order uncertainty propagation. Arguments
+/// are in base units.
+pub fn derivatives(func: &Function, args: &[f64]) -> Option<Vec<f64>> {
+    let x = *args.first()?;
+    let d = match *func {
+        Function::Sqrt => 0.5 / x.sqrt(),
+        Function::Exp => x.exp(),
+        Function::Ln => 1.0 / x,
+        Function::Log2 => 1.0 / (x * std::f64::consts::LN_2),
+        Function::Log10 => 1.0 / (x * std::f64::consts::LN_10),
+        Function::Sin => x.cos(),
+        Function::Cos => -x.sin(),
+        Function::Tan => 1.0 / (x.cos() * x.cos()),
+        Function::Asin => 1.0 / (1.0 - x * x).sqrt(),
+        Function::Acos => -1.0 / (1.0 - x * x).sqrt(),
+        Function::Atan => 1.0 / (1.0 + x * x),
+        Function::Sinh => x.cosh(),
+        Function::Cosh => x.sinh(),
+        Function::Tanh => 1.0 - x.tanh() * x.tanh(),
+        Function::Asinh => 1.0 / (x * x + 1.0).sqrt(),
+        Function::Acosh => 1.0 / (x * x - 1.0).sqrt(),
+        Function::Atanh => 1.0 / (1.0 - x * x),
+        Function::Log => {
+            let b = *args.get(1)?;
+            return Some(vec![1.0 / (x * b.ln()), -x.ln() / (b * b.ln() * b.ln())]);
+        }
+        Function::Hypot => {
+            let y = *args.get(1)?;
+            let h = x.hypot(y);
+            return Some(vec![x / h, y / h]);
+        }
+        // atan2(x, y) is the angle of the point (y, x).
+        Function::Atan2 => {
+            let y = *args.get(1)?;
+            let r2 = x * x + y * y;
+            return Some(vec![y / r2, -x / r2]);
+        }
+        Function::Mod => {
+            let y = *args.get(1)?;
+            return Some(vec![1.0, -(x / y).floor()]);
+        }
+        Function::Re | Function::Conj => 1.0,
+        Function::Im | Function::Arg => 0.0,
+        Function::Abs => x.signum(),
+        Function::Sum
+        | Function::Mean
+        | Function::Min
+        | Function::Max
+        | Function::Norm
+        | Function::Dot
+        | Function::Cross
+        | Function::If
+        | Function::Round
+        | Function::Floor
+        | Function::Ceil
+        | Function::Trunc
+        | Function::Gcd
+        | Function::Lcm
+        | Function::Custom(_) => return None,
+    };
+    Some(vec![d])
+}
+
+impl Show for Uncertain {
+    fn show(&self, context: &Context) -> String {
+        self.to_parts(context).to_string()
+    }
+}
+
+impl<'a, 'b> Add<&'b Uncertain> for &'a Uncertain {
+    type Output = Option<Uncertain>;
+
+    fn add(self, other: &Uncertain) -> Self::Output {
+        Some(Uncertain {
+            value: (&self.value + &other.value)?,
+            error: quadrature(&self.error, &other.error),
+        })
+    }
+}
+
+impl<'a, 'b> Sub<&'b Uncertain> for &'a Uncertain {
+    type Output = Option<Uncertain>;
+
+    fn sub(self, other: &Uncertain) -> Self::Output {
+        Some(Uncertain {
+            value: (&self.value - &other.value)?,
+            error: quadrature(&self.error, &other.error),
+        })
+    }
+}
+
+impl<'a> Neg for &'a Uncertain {
+    type Output = Option<Uncertain>;
+
+    fn neg(self) -> Self::Output {
+        Some(Uncertain {
+            value: (-&self.value)?,
+            error: self.error.clone(),
+        })
+    }
+}
+
+impl<'a, 'b> Mul<&'b Uncertain> for &'a Uncertain {
+    type Output = Option<Uncertain>;
+
+    fn mul(self, other: &Uncertain) -> Self::Output {
+        // d(ab) = b da + a db
+        let left = &other.value.value.abs() * &self.error;
+        let right = &self.value.value.abs() * &other.error;
+        Some(Uncertain {
+            value: (&self.value * &other.value)?,
+            error: quadrature(&left, &right),
+        })
+    }
+}
+
+impl<'a, 'b> Div<&'b Uncertain> for &'a Uncertain {
+    type Output = Option<Uncertain>;
+
+    fn div(self, other: &Uncertain) -> Self::Output {
+        let value = (&self.value / &other.value)?;
+        // d(a/b) = da / b - a db / b^2
+        let denom = other.value.value.abs();
+        let left = &self.error / &denom;
+        let right = &(&value.value.abs() * &other.error) / &denom;
+        Some(Uncertain {
+            value,
+            error: quadrature(&left, &right),
+        })
+    }
+}
@@ -4,9 +4,13 @@
 
 use crate::context::Context;
 use crate::date;
-use crate::date::GenericDateTime;
+use crate::date::{CalendarDuration, DateSpan, GenericDateTime};
+use crate::interval::Interval;
 use crate::number::Number;
+use crate::numeric::Numeric;
+use crate::reply::ListReply;
 use crate::substance::Substance;
+use crate::uncertain::Uncertain;
 use chrono::{DateTime, FixedOffset};
 use chrono_tz::Tz;
 use std::ops::{Add, Div, Mul, Neg, Sub};
@@ -16,6 +20,17 @@ pub enum Value {
     Number(Number),
     DateTime(date::GenericDateTime),
     Substance(Substance),
+    Uncertain(Uncertain),
+    Interval(Interval),
+    List(Vec<Number>),
+    Bool(bool),
+    /// The result of subtracting two dates, which only lasts until it
+    /// is shown or used in another operation. `Context::eval` turns it
+    /// into a number of seconds.
+    DateSpan(DateSpan),
+    /// Months or years being added to or subtracted from a date, which
+    /// only lasts until that operation.
+    CalendarDuration(CalendarDuration),
 }
 
 pub trait Show {
@@ -58,25 +73,245 @@ impl Show for Value {
             Value::Number(ref num) => num.show(context),
             Value::DateTime(ref dt) => dt.show(context),
             Value::Substance(ref v) => v.show(context),
+            Value::Uncertain(ref v) => v.show(context),
+            Value::Interval(ref v) => v.show(context),
+            Value::List(ref v) => {
+                let reply = ListReply::new(v.iter().map(|x| x.to_parts(context)).collect());
+                let items = reply
+                    .items
+                    .iter()
+                    .map(|x| x.to_string())
+                    .collect::<Vec<_>>()
+                    .join(", ");
+                match reply.quantity {
+                    Some(quantity) => format!("[{}] ({})", items, quantity),
+                    None => format!("[{}]", items),
+                }
+            }
+            Value::Bool(value) => value.to_string(),
+            Value::DateSpan(ref span) => span.seconds.show(context),
+            Value::CalendarDuration(ref duration) => duration.seconds.show(context),
         }
     }
 }
 
 impl Value {
-    pub fn pow(&self, exp: &Value) -> Result<Value, String> {
+    /// Replaces the difference between two dates, or calendar months,
+    /// with the length in seconds.
+    pub fn forget_dates(self) -> Value {
+        match self {
+            Value::DateSpan(span) => Value::Number(span.seconds),
+            Value::CalendarDuration(duration) => Value::Number(duration.seconds),
+            value => value,
+        }
+    }
+
+    /// Raises to a power. Inexact results are computed to the given
+    /// number of bits.
+    pub fn pow(&self, exp: &Value, precision: u64) -> Result<Value, String> {
+        if let Some(result) = calendar_op(
+            self,
+            exp,
+            |_, _| None,
+            |left, right| left.pow(right, precision),
+        ) {
+            return result;
+        }
+        if let Some(pairs) = list_pairs(self, exp)? {
+            return elementwise(pairs, |left, right| left.pow(right, precision));
+        }
+        if let Some((left, right)) = uncertain_pair(self, exp)? {
+            return left.pow(&right, precision).map(Value::Uncertain);
+        }
         match (self, exp) {
+            (Value::Interval(left), Value::Number(right)) => {
+                left.pow(right, precision).map(Value::Interval)
+            }
+            (_, Value::Interval(_)) => Err("Interval exponents are not supported".to_string()),
             (&Value::Number(ref left), &Value::Number(ref right)) => {
-                left.pow(right).map(Value::Number)
+                left.pow(right, precision).map(Value::Number)
             }
             (_, _) => Err("Operation is not defined".to_string()),
         }
     }
+
+    pub fn modulo(&self, other: &Value) -> Result<Value, String> {
+        self.number_op(other, &Number::modulo)
+    }
+
+    pub fn floor_div(&self, other: &Value) -> Result<Value, String> {
+        self.number_op(other, &Number::floor_div)
+    }
+
+    pub fn choose(&self, other: &Value) -> Result<Value, String> {
+        self.number_op(other, &Number::choose)
+    }
+
+    pub fn permute(&self, other: &Value) -> Result<Value, String> {
+        self.number_op(other, &Number::permute)
+    }
+
+    pub fn factorial(&self) -> Result<Value, String> {
+        match *self {
+            Value::Number(ref num) => num.factorial().map(Value::Number),
+            Value::List(ref list) => list
+                .iter()
+                .map(Number::factorial)
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::List),
+            _ => Err("Operation is not defined".to_string()),
+        }
+    }
+
+    /// Applies an operation that only makes sense for plain numbers,
+    /// element-wise for lists.
+    fn number_op(
+        &self,
+        other: &Value,
+        func: &dyn Fn(&Number, &Number) -> Result<Number, String>,
+    ) -> Result<Value, String> {
+        if let Some(result) = calendar_op(
+            self,
+            other,
+            |_, _| None,
+            |left, right| left.number_op(right, func),
+        ) {
+            return result;
+        }
+        if let Some(pairs) = list_pairs(self, other)? {
+            return elementwise(pairs, func);
+        }
+        match (self, other) {
+            (Value::Number(left), Value::Number(right)) => func(left, right).map(Value::Number),
+            (_, _) => Err("Operation is not defined".to_string()),
+        }
+    }
+}
+
+/// Keeps the calendar months of an operand where `keep` can, as in
+/// `1 month + 2 days`, and otherwise forgets them so that `op` goes by
+/// the length in seconds. Returns `None` if neither operand has
+/// calendar months, or if they're being added to a date.
+fn calendar_op(
+    left: &Value,
+    right: &Value,
+    keep: impl Fn(&Value, &Value) -> Option<CalendarDuration>,
+    op: impl Fn(&Value, &Value) -> Result<Value, String>,
+) -> Option<Result<Value, String>> {
+    match (left, right) {
+        (Value::DateTime(_), _) | (_, Value::DateTime(_)) => None,
+        (Value::CalendarDuration(_), _) | (_, Value::CalendarDuration(_)) => {
+            Some(match keep(left, right) {
+                Some(duration) => Ok(Value::CalendarDuration(duration)),
+                None => op(&left.clone().forget_dates(), &right.clone().forget_dates()),
+            })
+        }
+        (_, _) => None,
+    }
+}
+
+/// Pairs up the elements of two lists for element-wise operations. A
+/// plain number is paired with every element of a list.
+fn list_pairs(left: &Value, right: &Value) -> Result<Option<Vec<(Number, Number)>>, String> {
+    Ok(match (left, right) {
+        (Value::List(left), Value::List(right)) => {
+            if left.len() != right.len() {
+                return Err(format!(
+                    "Lists must have the same length, got {} and {}",
+                    left.len(),
+                    right.len()
+                ));
+            }
+            Some(left.iter().cloned().zip(right.iter().cloned()).collect())
+        }
+        (Value::List(left), Value::Number(right)) => {
+            Some(left.iter().map(|x| (x.clone(), right.clone())).collect())
+        }
+        (Value::Number(left), Value::List(right)) => {
+            Some(right.iter().map(|x| (left.clone(), x.clone())).collect())
+        }
+        (Value::List(_), _) | (_, Value::List(_)) => {
+            return Err("Lists can only be combined with numbers and other lists".to_string())
+        }
+        (_, _) => None,
+    })
+}
+
+fn elementwise(
+    pairs: Vec<(Number, Number)>,
+    func: impl Fn(&Number, &Number) -> Result<Number, String>,
+) -> Result<Value, String> {
+    pairs
+        .iter()
+        .map(|(left, right)| func(left, right))
+        .collect::<Result<Vec<_>, _>>()
+        .map(Value::List)
+}
+
+/// Promotes both operands to uncertain numbers if at least one of them
+/// is uncertain and the other is a plain number.
+fn uncertain_pair(left: &Value, right: &Value) -> Result<Option<(Uncertain, Uncertain)>, String> {
+    let exact = |num: &Number| {
+        let zero = Number {
+            value: Numeric::zero(),
+            unit: num.unit.clone(),
+        };
+        Uncertain::new(num.clone(), zero)
+    };
+    Ok(match (left, right) {
+        (Value::Uncertain(left), Value::Uncertain(right)) => Some((left.clone(), right.clone())),
+        (Value::Uncertain(left), Value::Number(right)) => Some((left.clone(), exact(right)?)),
+        (Value::Number(left), Value::Uncertain(right)) => Some((exact(left)?, right.clone())),
+        (_, _) => None,
+    })
+}
+
+/// Promotes both operands to intervals if at least one of them is an
+/// interval and the other is a plain number.
+fn interval_pair(left: &Value, right: &Value) -> Result<Option<(Interval, Interval)>, String> {
+    let exact = |num: &Number| Interval::new(num.clone(), num.clone());
+    Ok(match (left, right) {
+        (Value::Interval(left), Value::Interval(right)) => Some((left.clone(), right.clone())),
+        (Value::Interval(left), Value::Number(right)) => Some((left.clone(), exact(right)?)),
+        (Value::Number(left), Value::Interval(right)) => Some((exact(left)?, right.clone())),
+        (_, _) => None,
+    })
 }
 
 impl<'a, 'b> Add<&'b Value> for &'a Value {
     type Output = Result<Value, String>;
 
     fn add(self, other: &Value) -> Result<Value, String> {
+        if let Some(result) = calendar_op(
+            self,
+            other,
+            |left, right| match (left, right) {
+                (Value::CalendarDuration(left), Value::CalendarDuration(right)) => left.add(right),
+                (Value::CalendarDuration(left), Value::Number(right))
+                | (Value::Number(right), Value::CalendarDuration(left)) => left.add_time(right),
+                (_, _) => None,
+            },
+            |left, right| left + right,
+        ) {
+            return result;
+        }
+        if let Some(pairs) = list_pairs(self, other)? {
+            return elementwise(pairs, |left, right| {
+                (left + right).ok_or_else(|| {
+                    "Addition of units with mismatched units is not meaningful".to_string()
+                })
+            });
+        }
+        if let Some((left, right)) = interval_pair(self, other)? {
+            return (&left + &right).map(Value::Interval);
+        }
+        if let Some((left, right)) = uncertain_pair(self, other)? {
+            return (&left + &right)
+                .ok_or_else(|| {
+                    "Addition of units with mismatched units is not meaningful".to_string()
+                })
+                .map(Value::Uncertain);
+        }
         match (self, other) {
             (&Value::Number(ref left), &Value::Number(ref right)) => (left + right)
                 .ok_or_else(|| {
@@ -96,6 +331,11 @@ impl<'a, 'b> Add<&'b Value> for &'a Value {
                 "Implementation error: value is out of range representable by datetime".to_string()
             })
             .map(Value::DateTime),
+            (&Value::DateTime(ref left), &Value::CalendarDuration(ref right))
+            | (&Value::CalendarDuration(ref right), &Value::DateTime(ref left)) => left
+                .add_months(right.months)
+                .ok_or_else(|| "Date is out of range".to_string())
+                .and_then(|date| &Value::DateTime(date) + &Value::Number(right.rest.clone())),
             (&Value::Substance(ref left), &Value::Substance(ref right)) => {
                 left.add(right).map(Value::Substance)
             }
@@ -108,6 +348,40 @@ impl<'a, 'b> Sub<&'b Value> for &'a Value {
     type Output = Result<Value, String>;
 
     fn sub(self, other: &Value) -> Result<Value, String> {
+        if let Some(result) = calendar_op(
+            self,
+            other,
+            |left, right| match (left, right) {
+                (Value::CalendarDuration(left), Value::CalendarDuration(right)) => {
+                    left.add(&right.neg()?)
+                }
+                (Value::CalendarDuration(left), Value::Number(right)) => left.add_time(&(-right)?),
+                (Value::Number(left), Value::CalendarDuration(right)) => {
+                    right.neg()?.add_time(left)
+                }
+                (_, _) => None,
+            },
+            |left, right| left - right,
+        ) {
+            return result;
+        }
+        if let Some(pairs) = list_pairs(self, other)? {
+            return elementwise(pairs, |left, right| {
+                (left - right).ok_or_else(|| {
+                    "Subtraction of units with mismatched units is not meaningful".to_string()
+                })
+            });
+        }
+        if let Some((left, right)) = interval_pair(self, other)? {
+            return (&left - &right).map(Value::Interval);
+        }
+        if let Some((left, right)) = uncertain_pair(self, other)? {
+            return (&left - &right)
+                .ok_or_else(|| {
+                    "Subtraction of units with mismatched units is not meaningful".to_string()
+                })
+                .map(Value::Uncertain);
+        }
         match (self, other) {
             (&Value::Number(ref left), &Value::Number(ref right)) => (left - right)
                 .ok_or_else(|| {
@@ -127,8 +401,14 @@ impl<'a, 'b> Sub<&'b Value> for &'a Value {
                 "Implementation error: value is out of range representable by datetime".to_string()
             })
             .map(Value::DateTime),
+            (Value::DateTime(left), Value::CalendarDuration(right)) => right
+                .months
+                .checked_neg()
+                .and_then(|months| left.add_months(months))
+                .ok_or_else(|| "Date is out of range".to_string())
+                .and_then(|date| &Value::DateTime(date) - &Value::Number(right.rest.clone())),
             (&Value::DateTime(ref left), &Value::DateTime(ref right)) => {
-                date::from_duration(&match (left, right) {
+                let seconds = date::from_duration(&match (left, right) {
                     (&GenericDateTime::Fixed(ref left), &GenericDateTime::Fixed(ref right)) => {
                         *left - *right
                     }
@@ -142,8 +422,12 @@ impl<'a, 'b> Sub<&'b Value> for &'a Value {
                     (&GenericDateTime::Timezone(ref left), &GenericDateTime::Fixed(ref right)) => {
                         left.with_timezone(right.offset()) - *right
                     }
-                })
-                .map(Value::Number)
+                })?;
+                Ok(Value::DateSpan(DateSpan {
+                    seconds,
+                    start: *right,
+                    end: *left,
+                }))
             }
             (_, _) => Err("Operation is not defined".to_string()),
         }
@@ -158,6 +442,22 @@ impl<'a> Neg for &'a Value {
             Value::Number(ref num) => (-num)
                 .ok_or_else(|| "Bug: Negation should not fail".to_string())
                 .map(Value::Number),
+            Value::Uncertain(ref num) => (-num)
+                .ok_or_else(|| "Bug: Negation should not fail".to_string())
+                .map(Value::Uncertain),
+            Value::Interval(ref num) => Ok(Value::Interval(-num)),
+            Value::CalendarDuration(ref duration) => match duration.neg() {
+                Some(duration) => Ok(Value::CalendarDuration(duration)),
+                None => -&self.clone().forget_dates(),
+            },
+            Value::List(ref list) => list
+                .iter()
+                .map(|x| {
+                    x.neg()
+                        .ok_or_else(|| "Bug: Negation should not fail".to_string())
+                })
+                .collect::<Result<Vec<_>, _>>()
+                .map(Value::List),
             _ => Err("Operation is not defined".to_string()),
         }
     }
@@ -167,6 +467,33 @@ impl<'a, 'b> Mul<&'b Value> for &'a Value {
     type Output = Result<Value, String>;
 
     fn mul(self, other: &Value) -> Result<Value, String> {
+        if let Some(result) = calendar_op(
+            self,
+            other,
+            |left, right| match (left, right) {
+                (Value::CalendarDuration(duration), Value::Number(factor))
+                | (Value::Number(factor), Value::CalendarDuration(duration)) => {
+                    duration.scale(factor)
+                }
+                (_, _) => None,
+            },
+            |left, right| left * right,
+        ) {
+            return result;
+        }
+        if let Some(pairs) = list_pairs(self, other)? {
+            return elementwise(pairs, |left, right| {
+                (left * right).ok_or_else(|| "Bug: Mul should not fail".to_string())
+            });
+        }
+        if let Some((left, right)) = interval_pair(self, other)? {
+            return (&left * &right).map(Value::Interval);
+        }
+        if let Some((left, right)) = uncertain_pair(self, other)? {
+            return (&left * &right)
+                .ok_or_else(|| "Bug: Mul should not fail".to_string())
+                .map(Value::Uncertain);
+        }
         match (self, other) {
             (&Value::Number(ref left), &Value::Number(ref right)) => (left * right)
                 .ok_or_else(|| "Bug: Mul should not fail".to_string())
@@ -184,6 +511,34 @@ impl<'a, 'b> Div<&'b Value> for &'a Value {
     type Output = Result<Value, String>;
 
     fn div(self, other: &Value) -> Result<Value, String> {
+        if let Some(result) = calendar_op(
+            self,
+            other,
+            |left, right| match (left, right) {
+                (Value::CalendarDuration(duration), Value::Number(divisor))
+                    if !divisor.value.is_zero() =>
+                {
+                    duration.scale(&divisor.invert())
+                }
+                (_, _) => None,
+            },
+            |left, right| left / right,
+        ) {
+            return result;
+        }
+        if let Some(pairs) = list_pairs(self, other)? {
+            return elementwise(pairs, |left, right| {
+                (left / right).ok_or_else(|| "Division by zero".to_string())
+            });
+        }
+        if let Some((left, right)) = interval_pair(self, other)? {
+            return (&left / &right).map(Value::Interval);
+        }
+        if let Some((left, right)) = uncertain_pair(self, other)? {
+            return (&left / &right)
+                .ok_or_else(|| "Division by zero".to_string())
+                .map(Value::Uncertain);
+        }
         match (self, other) {
             (&Value::Number(ref left), &Value::Number(ref right)) => (left / right)
                 .ok_or_else(|| "Division by zero".to_string())
@@ -3,7 +3,10 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use assert_json_diff::assert_json_eq;
-use rink_core::ast::{Def, DefEntry, Expr, ExprString, Property, Query};
+use rink_core::ast::{
+    Def, DefEntry, Expr, ExprString, FunctionDef, FunctionParam, Property, Query, ScaleBound,
+    ScaleDef, ScaleRange, ScaleUnits, TableDef, TablePoint,
+};
 use rink_core::*;
 use serde_json;
 use serde_json::{json, to_value};
@@ -175,4 +178,117 @@ fn check_defs() {
             }]
         })
     );
+
+    // Functions
+    assert_json_eq!(
+        serde_json::to_value(DefEntry::new(
+            "reynolds",
+            None,
+            None,
+            Def::Function(FunctionDef {
+                params: vec![
+                    FunctionParam {
+                        name: "v".to_owned(),
+                        quantity: Some("velocity".to_owned()),
+                    },
+                    FunctionParam {
+                        name: "L".to_owned(),
+                        quantity: None,
+                    },
+                ],
+                body: ExprString(expr("v L / nu")),
+            })
+        ))
+        .unwrap(),
+        json!({
+            "name": "reynolds",
+            "doc": null,
+            "category": null,
+            "type": "function",
+            "params": [
+                { "name": "v", "quantity": "velocity" },
+                { "name": "L", "quantity": null }
+            ],
+            "body": "v L / nu"
+        })
+    );
+
+    // Nonlinear units
+    assert_json_eq!(
+        serde_json::to_value(DefEntry::new(
+            "tempC",
+            None,
+            None,
+            Def::Scale(Box::new(ScaleDef {
+                param: "x".to_owned(),
+                forward: ExprString(expr("x K + zerocelsius")),
+                inverse: ExprString(expr("(tempC - zerocelsius) / K")),
+                units: Some(ScaleUnits {
+                    input: ExprString(expr("1")),
+                    output: ExprString(expr("K")),
+                }),
+                domain: Some(ScaleRange {
+                    lower: Some(ScaleBound {
+                        value: ExprString(expr("-273.15")),
+                        inclusive: true,
+                    }),
+                    upper: None,
+                }),
+                range: None,
+            }))
+        ))
+        .unwrap(),
+        json!({
+            "name": "tempC",
+            "doc": null,
+            "category": null,
+            "type": "scale",
+            "param": "x",
+            "forward": "x K + zerocelsius",
+            "inverse": "(tempC - zerocelsius) / K",
+            "units": { "input": "1", "output": "K" },
+            "domain": {
+                "lower": { "value": "-273.15", "inclusive": true },
+                "upper": null
+            },
+            "range": null
+        })
+    );
+
+    // Tables
+    let table = DefEntry::new(
+        "gauge",
+        None,
+        None,
+        Def::Table(TableDef {
+            unit: ExprString(expr("inch")),
+            points: vec![
+                TablePoint {
+                    input: ExprString(expr("0")),
+                    output: ExprString(expr("0.3249")),
+                },
+                TablePoint {
+                    input: ExprString(expr("1")),
+                    output: ExprString(expr("0.2893")),
+                },
+            ],
+        }),
+    );
+    let value = serde_json::to_value(&table).unwrap();
+    assert_json_eq!(
+        value,
+        json!({
+            "name": "gauge",
+            "doc": null,
+            "category": null,
+            "type": "table",
+            "unit": "inch",
+            "points": [
+                { "input": "0", "output": "0.3249" },
+                { "input": "1", "output": "0.2893" }
+            ]
+        })
+    );
+    let parsed: DefEntry = serde_json::from_value(value.clone()).unwrap();
+    assert_json_eq!(serde_json::to_value(&parsed).unwrap(), value);
 }
@@ -68,10 +68,7 @@ fn test_determinism() {
 
 #[test]
 fn test_sqrt_errors() {
-    test(
-        "sqrt -1",
-        "Complex numbers are not implemented: sqrt(-1 (dimensionless))",
-    );
+    test("sqrt -1", "approx. i (dimensionless)");
     test(
         "sqrt(2m)",
         "Result must have integer dimensions: sqrt(2 meter (length))",
@@ -355,18 +352,18 @@ fn temperature_with_dimension() {
 
 #[test]
 fn test_functions() {
-    test("exp(ln(10))", "approx. 10.00000 (dimensionless)");
+    test("exp(ln(10))", "approx. 10 (dimensionless)");
     test("log2(65536)", "approx. 16 (dimensionless)");
-    test("10^log10(123)", "approx. 123.0000 (dimensionless)");
+    test("10^log10(123)", "approx. 123 (dimensionless)");
     test("log(27, 3)", "approx. 3 (dimensionless)");
 
     test("sin(pi/2)", "approx. 1 (dimensionless)");
-    test("cos(asin(0.5) - pi/2)", "approx. 0.5000000 (dimensionless)");
-    test("atan(tan(0.42))", "approx. 0.4199999 (dimensionless)");
+    test("cos(asin(0.5) - pi/2)", "approx. 0.5 (dimensionless)");
+    test("atan(tan(0.42))", "approx. 0.42 (dimensionless)");
     test("acos(1)", "approx. 0 (dimensionless)");
     test("acosh(cosh(1))", "approx. 1 (dimensionless)");
-    test("asinh(sinh(0.123))", "approx. 0.1230000 (dimensionless)");
-    test("atanh(tanh(1.23))", "approx. 1.230000 (dimensionless)");
+    test("asinh(sinh(0.123))", "approx. 0.123 (dimensionless)");
+    test("atanh(tanh(1.23))", "approx. 1.23 (dimensionless)");
 
     test("hypot(3 m, 4 m)", "approx. 5 meter (length)");
     test("atan2(7, 6)", "approx. 0.8621700 (dimensionless)");
@@ -443,6 +440,7 @@ fn test_radix() {
 #[test]
 fn test_comments() {
     test("1 // *3", "1 (dimensionless)");
+    test("1 // three", "1 (dimensionless)");
     test("1 + /*2*/ 3", "4 (dimensionless)");
     test("1 + /*2", "Expected term, got <Expected `*/`, got EOF>");
 }
@@ -498,8 +496,17 @@ fn test_search() {
 fn test_digits() {
     test(
         "ln(1234) -> digits 100",
-        "approx. 7.11801620446533345187845043255947530269622802734375 (dimensionless)",
+        "approx. 7.11801620446533312341480380006836739278993505099911845482608609121344814458555101581856655829511726739 (dimensionless)",
+    );
+    test(
+        "sqrt(2) -> digits 50",
+        "sqrt(2), approx. 1.414213562373095048801688724209698078569671875376948 (dimensionless)",
     );
+    test(
+        "exp(1) -> digits 40",
+        "approx. 2.71828182845904523536028747135266249775724 (dimensionless)",
+    );
+    test("sin(10^30)", "approx. -0.09011690 (dimensionless)");
     test(
         "1/7 -> digits 50",
         "1/7, approx. 0.1428571428571428571428571428571428571428571428571428 (dimensionless)",
@@ -535,11 +542,237 @@ fn test_missing_base() {
     test("3 -> base", "Expected decimal base, got eof");
 }
 
+#[test]
+fn test_date_formats() {
+    test("#@1700000000# -> iso8601", "2023-11-14T22:13:20+00:00");
+    test(
+        "#@1700000000.25# -> iso8601",
+        "2023-11-14T22:13:20.250+00:00",
+    );
+    test(
+        "#@1700000000# -> rfc2822",
+        "Tue, 14 Nov 2023 22:13:20 +0000",
+    );
+    test("#@1700000000# -> unix", "1700000000");
+    test("#@1700000000.5# -> unixms", "1700000000500");
+    test(
+        "#2023-11-14 17:13:20 America/New_York# -> iso8601",
+        "2023-11-14T17:13:20-05:00",
+    );
+    test(
+        "#2023-11-14 17:13:20 America/New_York# -> unix",
+        "1700000000",
+    );
+    test(
+        "#@1700000000# -> strftime \"%Y-%m-%d %H:%M\"",
+        "2023-11-14 22:13",
+    );
+    test("#@1700000000# -> strftime '%A'", "Tuesday");
+    test(
+        "#@1700000000# -> strftime \"%Q\"",
+        "Invalid strftime format string: \"%Q\"",
+    );
+    test(
+        "#@1700000000# -> strftime",
+        "Expected format string after `strftime`, got eof",
+    );
+    test(
+        "#@1700000000# -> strftime \"%#z\"",
+        "Cannot format date as strftime \"%#z\", as %#z is only for parsing",
+    );
+    test(
+        "#@-99999999999# -> rfc2822",
+        "Cannot write the year -1199 as rfc2822, which only allows years 0 to 9999",
+    );
+    test("3 m -> unix", "Cannot convert <3 meter (length)> to unix");
+    test(
+        "#@1700000000# -> unix garbage",
+        "Expected end of conversion, got ident",
+    );
+    test(
+        "#@1700000000# -> rfc2822 1 2 3",
+        "Expected end of conversion, got number",
+    );
+}
+
+#[test]
+fn test_iso8601_duration() {
+    test("PT1H30M", "1 hour, 30 minute, 0 second (time)");
+    test("P2DT3H", "2 day, 3 hour, 0 second (time)");
+    test("PT1,5S", "1.5 second (time)");
+    test("PT1H -> min", "60 minute (time)");
+    test("#2021-01-31# + P1M", "2021-02-28 00:00:00 +00:00");
+    test("#2021-01-31# + P1M2D", "2021-03-02 00:00:00 +00:00");
+    test("#2021-03-31# - P1M2D", "2021-02-26 00:00:00 +00:00");
+    test(
+        "#2021-01-31# + P1Y2M10DT2H30M",
+        "2022-04-10 02:30:00 +00:00",
+    );
+    test(
+        "#2021-01-31# + (1 month + 2 days)",
+        "2021-03-02 00:00:00 +00:00",
+    );
+
+    test("90 min -> iso8601 duration", "PT1H30M");
+    test("-90 min -> iso8601 duration", "-PT1H30M");
+    test("P2DT3H -> iso8601 duration", "P2DT3H");
+    test("0 s -> iso8601 duration", "PT0S");
+    test("1.5 s -> iso8601 duration", "PT1.5S");
+    test("P1Y2M3DT4H5M6.5S -> iso8601 duration", "P1Y2M3DT4H5M6.5S");
+    test("P14M -> iso8601 duration", "P1Y2M");
+    test("2 years + 3 hours -> iso8601 duration", "P2YT3H");
+    test(
+        "-1 year + 2 days -> iso8601 duration",
+        "Cannot convert <approx. -31.38412 megasecond (time)> to iso8601 duration, \
+         as its calendar months and the rest have different signs",
+    );
+    test(
+        "#2021-03-04T10:30:00# - #2020-01-01# -> iso8601 duration",
+        "P1Y2M3DT10H30M",
+    );
+    test(
+        "3 m -> iso8601 duration",
+        "Cannot convert <3 meter (length)> to iso8601 duration",
+    );
+}
+
+#[test]
+fn test_timestamp_input() {
+    test("#@1700000000#", "2023-11-14 22:13:20 +00:00");
+    test("#unix 1700000000#", "2023-11-14 22:13:20 +00:00");
+    test("#unixms 1700000000123#", "2023-11-14 22:13:20.123 +00:00");
+    test("#@1700000000.5#", "2023-11-14 22:13:20.500 +00:00");
+    test("#@-1#", "1969-12-31 23:59:59 +00:00");
+    test("#@99999999999999999999#", "Timestamp is out of range");
+    test("#unix now#", "Expected a number for the timestamp");
+}
+
+#[test]
+fn test_relative_dates() {
+    test("#tomorrow 9am#", "2000-01-02 09:00:00 +00:00");
+    test("#yesterday#", "1999-12-31 00:00:00 +00:00");
+    test("#today 21:30#", "2000-01-01 21:30:00 +00:00");
+    test("#next friday#", "2000-01-07 00:00:00 +00:00");
+    test("#next saturday#", "2000-01-08 00:00:00 +00:00");
+    test("#last saturday 5:15pm#", "1999-12-25 17:15:00 +00:00");
+    test("#3 weeks ago#", "1999-12-11 00:00:00 +00:00");
+    test("#2 months from now#", "2000-03-01 00:00:00 +00:00");
+    test("#12 hours ago#", "1999-12-31 12:00:00 +00:00");
+    test("#start of week#", "1999-12-27 00:00:00 +00:00");
+    test("#start of month#", "2000-01-01 00:00:00 +00:00");
+    test("#tomorrow 9am America/New_York#", "2000-01-01 09:00:00 EST");
+    test(
+        "#tomorrow 9am# -> \"Asia/Tokyo\"",
+        "2000-01-02 18:00:00 JST",
+    );
+    test(
+        "#9999999999 years ago#",
+        "Most likely pattern `count relunit ago` failed: Relative date is out of range",
+    );
+    test(
+        "#next blursday#",
+        "Most likely pattern `reldir weekday[ hour12[:min[:sec]][ ]meridiem][ offset]` \
+         failed: Unknown weekday: blursday",
+    );
+}
+
 #[test]
 fn test_date_difference() {
     test_starts_with("now - (now - 3days)", "3 day, 0 second (time)");
 }
 
+#[test]
+fn test_calendar_duration() {
+    test(
+        "#2021-03-04# - #2020-01-01#",
+        "1 year, 2 month, 3 day, 0 second (time)",
+    );
+    test(
+        "#2020-01-01# - #2021-03-04#",
+        "-1 year, -2 month, -3 day, 0 second (time)",
+    );
+    test("#2021-03-31# - #2021-01-31#", "2 month, 0 second (time)");
+    test(
+        "#2021-03-01# - #2021-01-31#",
+        "1 month, 1 day, 0 second (time)",
+    );
+    test(
+        "#2021-02-28 12:30# - #2020-02-29 18:00#",
+        "11 month, 4 week, 1 day, 18 hour, 30 minute, 0 second (time)",
+    );
+    test("(#2021-03-04# - #2020-01-01#) / day", "428 (dimensionless)");
+    test_session(&[
+        (
+            "x = #2024-03-04# - #2020-01-01#",
+            "x = 4 year, 2 month, 3 day, 0 second (time)",
+        ),
+        ("x", "4 year, 2 month, 3 day, 0 second (time)"),
+        ("x -> iso8601 duration", "P4Y2M3D"),
+        ("x / day", "1524 (dimensionless)"),
+    ]);
+
+    let mut ctx = simple_context().unwrap();
+    ctx.use_humanize = false;
+    ctx.calendar_durations = false;
+    let res = eval(&mut ctx, "#2021-03-31# - #2021-01-31#").unwrap();
+    assert_eq!(res.to_string(), "8 week, 3 day, 0 second (time)");
+}
+
+#[test]
+fn test_calendar_date_math() {
+    test("#2021-01-31# + 1 month", "2021-02-28 00:00:00 +00:00");
+    test("#2020-02-29# + 1 year", "2021-02-28 00:00:00 +00:00");
+    test("#2021-03-31# - 1 month", "2021-02-28 00:00:00 +00:00");
+    test("#2021-01-31# + -2 months", "2020-11-30 00:00:00 +00:00");
+    test("1 month + #2021-01-31#", "2021-02-28 00:00:00 +00:00");
+    test(
+        "#2021-01-31 10:00# + 2 decades",
+        "2041-01-31 10:00:00 +00:00",
+    );
+    test(
+        "#2021-02-14 02:30 America/New_York# + 1 month",
+        "2021-03-14 03:30:00 EDT",
+    );
+    test(
+        "#2021-10-07 01:30 America/New_York# + 1 month",
+        "2021-11-07 01:30:00 EDT",
+    );
+    // Anything other than whole months keeps its fixed length.
+    test("#2021-01-31# + 30 day", "2021-03-02 00:00:00 +00:00");
+    test(
+        "#2021-01-31# + 1.5 month",
+        "2021-03-17 15:43:36.580800 +00:00",
+    );
+    test("#2021-01-31# + 2629746 s", "2021-03-02 10:29:06 +00:00");
+    test(
+        "#2021-03-13 12:00 America/New_York# + 1 day",
+        "2021-03-14 13:00:00 EDT",
+    );
+}
+
+#[test]
+fn test_calendar_months_in_values() {
+    test("#2024-01-31# + 1 month * 2", "2024-03-31 00:00:00 +00:00");
+    test("#2024-01-31# + 2 * 1 month", "2024-03-31 00:00:00 +00:00");
+    test(
+        "#2024-01-31# + (1 month - 1 day)",
+        "2024-02-28 00:00:00 +00:00",
+    );
+    test("#2024-01-31# - -1 month", "2024-02-29 00:00:00 +00:00");
+    test("#2024-01-31# + 1|2 year", "2024-07-31 00:00:00 +00:00");
+    test("#2000-02-29# + 2 centuries", "2200-02-28 00:00:00 +00:00");
+    test("#2000-02-29# - 1 millennia", "1000-02-28 00:00:00 +00:00");
+    test("1 year / month", "12 (dimensionless)");
+    test_session(&[
+        (
+            "step = 1 month",
+            "step = 4 week, 2 day, 10 hour, 29 minute, 3.831223 second (time)",
+        ),
+        ("#2024-01-31# + step", "2024-02-29 00:00:00 +00:00"),
+        ("#2024-01-31# + 2 step", "2024-03-31 00:00:00 +00:00"),
+    ]);
+}
+
 #[test]
 fn test_date_time_formats() {
     test_starts_with("#1970-01-01 10:30 GMT#", "1970-01-01 10:30:00 GMT");
@@ -632,13 +865,1085 @@ fn test_bad_floats() {
     test("log2(0)", "approx. -Inf (dimensionless)");
 
     // Sqrt
-    test(
-        "sqrt(-1)",
-        "Complex numbers are not implemented: sqrt(-1 (dimensionless))",
-    );
+    test("sqrt(-1)", "approx. i (dimensionless)");
 }
 
 #[test]
 fn test_large_floats() {
     test("5.2*10^15*300^(3/2)", "approx. 2.701999e19 (dimensionless)");
 }
+
+fn test_session(steps: &[(&str, &str)]) {
+    let mut ctx = simple_context().unwrap();
+    ctx.use_humanize = false;
+    for &(input, output) in steps {
+        let res = match eval(&mut ctx, input) {
+            Ok(v) => v.to_string(),
+            Err(v) => v.to_string(),
+        };
+        assert_eq!(res, output, "in session step {:?}", input);
+    }
+}
+
+#[test]
+fn test_variables() {
+    test_session(&[
+        ("x = 3 m", "x = 3 meter (length)"),
+        ("x^2", "9 meter^2 (area)"),
+        ("x -> cm", "300 centimeter (length)"),
+        ("y = x / 2 s", "y = 1.5 meter / second (velocity)"),
+        ("x = 5", "x = 5 (dimensionless)"),
+        ("y", "1.5 meter / second (velocity)"),
+        ("unset y", "Unset y"),
+        ("unset y", "No such variable: y"),
+        ("unset x y", "Expected eof after variable name, got ident"),
+        ("unset x)", "Expected eof after variable name, got `)`"),
+        ("now = 5", "Cannot assign to now"),
+    ]);
+}
+
+#[test]
+fn test_variable_shadowing() {
+    test_session(&[
+        ("foot = 2", "foot = 2 (dimensionless)"),
+        ("foot", "2 (dimensionless)"),
+        ("3 foot", "6 (dimensionless)"),
+        ("unset foot", "Unset foot"),
+        ("3 foot", "914.4 millimeter (length)"),
+    ]);
+}
+
+#[test]
+fn test_variable_search() {
+    test_session(&[
+        ("zzqvariable = 3 m", "zzqvariable = 3 meter (length)"),
+        ("search zzqvariable", "Search results: zzqvariable (length)"),
+    ]);
+}
+
+#[test]
+fn test_functions_user_defined() {
+    test_session(&[
+        (
+            "reynolds(rho, v: velocity, L: length, mu) = rho v L / mu",
+            "Definition: reynolds(rho, v: velocity, L: length, mu) = rho v L / mu",
+        ),
+        (
+            "reynolds(1000 kg/m^3, 2 m/s, 0.05 m, 0.001 Pa s)",
+            "100000 (dimensionless)",
+        ),
+        (
+            "reynolds(1000 kg/m^3, 2 m, 0.05 m, 0.001 Pa s)",
+            "Conformance error: 2 meter (length) != 1 meter / second (velocity)\n\
+             Suggestions: multiply left side by frequency, multiply right side by time",
+        ),
+        (
+            "reynolds(1, 2)",
+            "Argument number mismatch for reynolds: Expected 4, got 2",
+        ),
+        (
+            "reynolds",
+            "Definition: reynolds(rho, v: velocity, L: length, mu) = rho v L / mu",
+        ),
+        ("sq(x) = x^2", "Definition: sq(x) = x^2"),
+        ("sq(3 m) -> cm^2", "90000 centimeter^2 (area)"),
+        ("x = 5", "x = 5 (dimensionless)"),
+        ("sq(2) x", "20 (dimensionless)"),
+        (
+            "f(x: flavor) = x",
+            "No such unit flavor, did you mean flour?",
+        ),
+        ("loop(x) = loop(x)", "Definition: loop(x) = loop(x)"),
+        ("loop(1)", "Recursion limit reached while calling loop"),
+        ("unset sq", "Unset sq"),
+        ("kg(m/s)", "1 kilogram meter / second (impulse)"),
+    ]);
+}
+
+#[test]
+fn test_functions_search() {
+    test_session(&[
+        ("zzqfunction(x) = x", "Definition: zzqfunction(x) = x"),
+        (
+            "search zzqfunction",
+            "Search results: zzqfunction (function)",
+        ),
+    ]);
+}
+
+#[test]
+fn test_functions_from_units_file() {
+    let mut ctx = simple_context().unwrap();
+    ctx.load(gnu_units::parse_str(
+        "dragforce(rho, v: velocity, Cd, A: area) 1|2 rho v^2 Cd A\n",
+    ));
+    assert_eq!(
+        one_line(&mut ctx, "dragforce(1.2 kg/m^3, 10 m/s, 0.5, 2 m^2) -> N").unwrap(),
+        "60 newton (force)"
+    );
+}
+
+#[test]
+fn test_nonlinear_units_from_units_file() {
+    let mut ctx = simple_context().unwrap();
+    ctx.load(gnu_units::parse_str(
+        "wirearea(x) units=[1;m^2] domain=[0,) range=[0,) \\\n\
+         pi (x mm / 2)^2 ; (4 wirearea / pi)^(1|2) / mm\n\
+         wa wirearea\n",
+    ));
+    assert_eq!(
+        one_line(&mut ctx, "2 wirearea -> mm^2").unwrap(),
+        "pi, approx. 3.141592 millimeter^2 (area)"
+    );
+    assert_eq!(
+        one_line(&mut ctx, "wirearea(2) -> mm^2").unwrap(),
+        "pi, approx. 3.141592 millimeter^2 (area)"
+    );
+    assert_eq!(
+        one_line(&mut ctx, "pi mm^2 -> wa").unwrap(),
+        "2 wirearea (area)"
+    );
+    assert_eq!(
+        one_line(&mut ctx, "-1 wirearea").unwrap_err(),
+        "wirearea is only defined for readings in [0,), got: <-1 (dimensionless)>"
+    );
+    assert!(one_line(&mut ctx, "2 s -> wirearea")
+        .unwrap_err()
+        .starts_with("Conformance error: 2 second (time) != 1 meter^2 (area)"));
+}
+
+#[test]
+fn test_temperature_scales() {
+    test("20 °C -> °F", "68 °F (temperature)");
+    test("98.6 fahrenheit -> celsius", "37 °C (temperature)");
+    test("tempF(98.6) -> K", "310.15 kelvin (temperature)");
+    test("0 K -> °De", "559.725 °De (temperature)");
+    test(
+        "-300 °C",
+        "°C is only defined for readings in [-273.15,), got: <-300 (dimensionless)>",
+    );
+    test(
+        "-1 K -> °C",
+        "°C is only defined for quantities in [0,), got: <-1 kelvin (temperature)>",
+    );
+}
+
+#[test]
+fn test_table_units() {
+    test("wiregauge(12) -> mm", "2.052574 millimeter (length)");
+    test(
+        "2.05 mm -> wiregauge",
+        "approx. 12.01145 wiregauge (length)",
+    );
+    test("0.46 inch -> awg", "-3 wiregauge (length)");
+    test(
+        "[10, 12] awg -> mm",
+        "[2.052574, 2.58826] millimeter (length)",
+    );
+    test(
+        "-4 awg",
+        "wiregauge is only defined for readings from -3 to 40, got: <-4 (dimensionless)>",
+    );
+    test(
+        "1 inch -> awg",
+        "wiregauge is only defined for quantities from <79.883 micrometer (length)> \
+         to <11.684 millimeter (length)>, got: <25.4 millimeter (length)>",
+    );
+    test(
+        "4 gasmark -> °C",
+        "530/3, approx. 176.6666 °C (temperature)",
+    );
+}
+
+#[test]
+fn test_table_units_from_units_file() {
+    let mut ctx = simple_context().unwrap();
+    ctx.load(gnu_units::parse_str(
+        "sieve[micron] 10 2000  20 850  40 425  \\\n  60 250\n",
+    ));
+    assert_eq!(
+        one_line(&mut ctx, "30 sieve -> micron").unwrap(),
+        "637.5 micrometer (length)"
+    );
+    assert_eq!(
+        one_line(&mut ctx, "250 micron -> sieve").unwrap(),
+        "60 sieve (length)"
+    );
+    assert_eq!(
+        one_line(&mut ctx, "(50 ± 2) sieve -> micron").unwrap(),
+        "337.5 ± 17.5 micrometer (length)"
+    );
+}
+
+#[test]
+fn test_uncertainty() {
+    test("5.0 ± 0.2 m", "5 ± 0.2 meter (length)");
+    test("5 +/- 0.2 m", "5 ± 0.2 meter (length)");
+    test("(5 m) ± (2 cm)", "5 ± 0.02 meter (length)");
+    test("(1 ± 0.5 km)", "1 ± 0.5 kilometer (length)");
+    test("5 m ± 2 cm", "5 ± 0.02 meter (length)");
+    test("5 m ± 2", "5 ± 2 meter (length)");
+    test("1 ± 0.5 km", "1 ± 0.5 kilometer (length)");
+    test("2 * 3 ± 1", "6 ± 1 (dimensionless)");
+    test("5 m ± 2 cm + 1 m", "6 ± 0.02 meter (length)");
+    test(
+        "5 m ± 2 s",
+        "Uncertainty must have the same units as the value: \
+         <5 meter (length)> ± <2 second (time)>",
+    );
+}
+
+#[test]
+fn test_uncertainty_propagation() {
+    test("(5 ± 0.2 m) + (3 ± 0.1 m)", "8 ± 0.2236067 meter (length)");
+    test("(5 ± 0.2 m) - 3 m", "2 ± 0.2 meter (length)");
+    test("-(3 ± 0.5)", "-3 ± 0.5 (dimensionless)");
+    test("(4 ± 0.3 m) * (3 ± 0.4 m)", "12 ± 1.835755 meter^2 (area)");
+    test(
+        "(4 ± 0.3 m) / (2 ± 0.1 s)",
+        "2 ± 0.1802775 meter / second (velocity)",
+    );
+    test("(2 ± 0.1 m)^2", "4 ± 0.4 meter^2 (area)");
+    test("2^(3 ± 0.1)", "8 ± 0.5545177 (dimensionless)");
+    test("sqrt(4 ± 0.4 m^2)", "2 ± 0.1000000 meter (length)");
+    test("sin(0 ± 0.1)", "approx. 0 ± 0.1000000 (dimensionless)");
+    test(
+        "sqrt(0 ± 1)",
+        "Uncertainty is unbounded: sqrt(0 ± 1 (dimensionless))",
+    );
+}
+
+#[test]
+fn test_uncertainty_conversion() {
+    test("(5 ± 0.2 m) -> cm", "500 ± 20 centimeter (length)");
+    test(
+        "(5 ± 0.2 m) -> 3 cm",
+        "500/3, approx. 166.6666 ± 6.666666 * 3 centimeter (length)",
+    );
+    test("(20 ± 0.5) °C -> °F", "68 ± 0.9 °F (temperature)");
+    test(
+        "1 m -> 5 ± 1 cm",
+        "Uncertainties are not allowed in the right hand side of conversions",
+    );
+}
+
+#[test]
+fn test_interval() {
+    test("[2, 3] kg", "[2, 3] kilogram (mass)");
+    test("[1000, 2000] m", "[1, 2] kilometer (length)");
+    test("-[2, 3] m", "[-3, -2] meter (length)");
+    test(
+        "[3, 2]",
+        "Lower bound of interval is greater than upper bound",
+    );
+    test("[2 m, 3 s]", "Interval bounds must have the same units");
+    test("[2, 3", "Expected `]`, got eof");
+    test("[ln(0), 1]", "Interval bounds must be finite");
+    test(
+        "[-1, 2] km * exp(1000)",
+        "Interval bounds must be finite: \
+         <[-1, 2] kilometer (length)> * <approx. 1.970071e434 (dimensionless)>",
+    );
+}
+
+#[test]
+fn test_interval_arithmetic() {
+    test("[2, 3] kg + [1, 2] kg", "[3, 5] kilogram (mass)");
+    test("[2, 3] kg - [1, 2] kg", "[0, 2] kilogram (mass)");
+    test("[2, 3] m * [-1, 2] m", "[-3, 6] meter^2 (area)");
+    test("[2, 3] m / [1, 2] s", "[1, 3] meter / second (velocity)");
+    test("[2, 3] kg + 1 kg", "[3, 4] kilogram (mass)");
+    test(
+        "[2, 3] / [-1, 2]",
+        "Division by an interval containing zero is undefined, \
+         since the result would be unbounded: \
+         <[2, 3] (dimensionless)> / <[-1, 2] (dimensionless)>",
+    );
+    test("[-2, 3]^2", "[0, 9] (dimensionless)");
+    test("[-3, -2]^2", "[4, 9] (dimensionless)");
+    test("[2, 3]^-1", "[1/3, 0.5] (dimensionless)");
+    test(
+        "[-1, 3]^-1",
+        "Raising an interval containing zero to a negative power is undefined: \
+         <[-1, 3] (dimensionless)> ^ <-1 (dimensionless)>",
+    );
+    test(
+        "2^[1, 2]",
+        "Interval exponents are not supported: \
+         <2 (dimensionless)> ^ <[1, 2] (dimensionless)>",
+    );
+}
+
+#[test]
+fn test_interval_functions() {
+    // Float bounds are rounded outwards.
+    test("sqrt([4, 9] m^2)", "[2, 3] meter (length)");
+    test("acos([0, 0.5])", "[1.047197, 1.570797] (dimensionless)");
+    test("cosh([-1, 2])", "[1, 3.762196] (dimensionless)");
+    // Bounds that are known exactly aren't widened.
+    test("ln([1, 2])", "[0, 0.6931472] (dimensionless)");
+    test("exp([0, 1])", "[1, 2.718282] (dimensionless)");
+    test("asin([-1, 1])", "[-1.570797, 1.570797] (dimensionless)");
+    test(
+        "sqrt([-4, 9])",
+        "Fractional powers of intervals with negative values are undefined: \
+         sqrt([-4, 9] (dimensionless))",
+    );
+    test(
+        "ln([-1, 2])",
+        "Logarithms of intervals with negative values are undefined: \
+         ln([-1, 2] (dimensionless))",
+    );
+    test(
+        "log10([-5, 5])",
+        "Logarithms of intervals with negative values are undefined: \
+         log10([-5, 5] (dimensionless))",
+    );
+    test(
+        "asin([0, 2])",
+        "asin is not defined for all of <[0, 2] (dimensionless)>",
+    );
+    test(
+        "acosh([0, 2])",
+        "acosh is not defined for all of <[0, 2] (dimensionless)>",
+    );
+    test(
+        "ln([0, 1])",
+        "ln is not defined for all of <[0, 1] (dimensionless)>",
+    );
+    test(
+        "atanh([0, 1])",
+        "atanh is not defined for all of <[0, 1] (dimensionless)>",
+    );
+    test("exp([0, 1000])", "Interval bounds must be finite");
+    test(
+        "sin([0, 1])",
+        "sin is not monotonic, so it is not defined for intervals",
+    );
+}
+
+#[test]
+fn test_interval_conversion() {
+    test("[2, 3] kg -> g", "[2000, 3000] gram (mass)");
+    test("[2, 3] km -> mi", "[1.242742, 1.864114] mile (length)");
+    test("[20, 25] °C -> °F", "[68, 77] °F (temperature)");
+    test(
+        "1 m -> [1, 2] cm",
+        "Intervals are not allowed in the right hand side of conversions",
+    );
+}
+
+#[test]
+fn test_complex() {
+    test("i^2", "-1 (dimensionless)");
+    test("j", "i (dimensionless)");
+    test("sqrt(-1)", "approx. i (dimensionless)");
+    test("sqrt(-4 m^2)", "approx. 2i meter (length)");
+    test("(3 + 4i) * (3 - 4i)", "25 (dimensionless)");
+    test("(3 + 4i) / (1 - 2i)", "-1 + 2i (dimensionless)");
+    test("(1 + i)^10", "32i (dimensionless)");
+    test("1.5 - 0.5i", "1.5 - 0.5i (dimensionless)");
+    test("(3 + 4i) m -> cm", "300 + 400i centimeter (length)");
+    test(
+        "1/(j 2 pi 50 Hz 10 uF)",
+        "approx. -318.3098i ohm (resistance)",
+    );
+    test(
+        "[1, 2] * i",
+        "Interval bounds must be real numbers: \
+         <[1, 2] (dimensionless)> * <i (dimensionless)>",
+    );
+}
+
+#[test]
+fn test_complex_functions() {
+    test("abs(3 + 4j)", "approx. 5 (dimensionless)");
+    test("arg(1 + i)", "approx. 0.7853981 (dimensionless)");
+    test("re(3 V + 4j V)", "3 volt (electrical_potential)");
+    test("im(3 + 4j)", "4 (dimensionless)");
+    test("conj(3 + 4j)", "3 - 4i (dimensionless)");
+    test("exp(i pi)", "approx. -1 (dimensionless)");
+    test("ln(-1)", "approx. 3.141592i (dimensionless)");
+    test("2^i", "approx. 0.7692389 + 0.6389612i (dimensionless)");
+    test("sin(i)", "sin is not defined for complex numbers");
+}
+
+#[test]
+fn test_complex_polar() {
+    test(
+        "(3 + 4i) V -> polar",
+        "approx. 5 ∠ 53.13010° volt (electrical_potential)",
+    );
+    test("-1 -> polar", "1 ∠ 180° (dimensionless)");
+    test(
+        "1/(j 2 pi 50 Hz 10 uF) -> polar",
+        "approx. 318.3098 ∠ -90° ohm (resistance)",
+    );
+    test("(3 + 4i) -> rectangular", "3 + 4i (dimensionless)");
+    test(
+        "[1, 2] -> polar",
+        "<[1, 2] (dimensionless)> to polar is not defined",
+    );
+}
+
+#[test]
+fn test_list() {
+    test("[1, 2, 3] + [4, 5, 6]", "5, 7, 9 (dimensionless)");
+    test("[1, 2, 3] * 2 m", "2 meter, 4 meter, 6 meter (length)");
+    test("2 / [1, 2, 4]", "2, 1, 0.5 (dimensionless)");
+    test("-[1, 2, 3] s", "-1 second, -2 second, -3 second (time)");
+    test("[1, 2, 3]^2", "1, 4, 9 (dimensionless)");
+    test("[1, 2,]", "1, 2 (dimensionless)");
+    test("[1,]", "1 (dimensionless)");
+    test(
+        "[1 m, 2 s, 3 kg]",
+        "1 meter (length), 2 second (time), 3 kilogram (mass)",
+    );
+    test(
+        "[1, 2,] + [1, 2, 3]",
+        "Lists must have the same length, got 2 and 3: \
+         <[1, 2] (dimensionless)> + <[1, 2, 3] (dimensionless)>",
+    );
+    test(
+        "[1, [2, 3], 4]",
+        "List elements must be numbers, got: <[2, 3] (dimensionless)>",
+    );
+    test("sqrt([1, 4, 9] m^2)", "1 meter, 2 meter, 3 meter (length)");
+    test(
+        "atan2([1, 2, 3], 1)",
+        "approx. 0.7853981, approx. 1.107148, approx. 1.249045 (dimensionless)",
+    );
+}
+
+#[test]
+fn test_list_functions() {
+    test("sum([1, 2, 3] kg)", "6 kilogram (mass)");
+    test("sum(1 kg, 2 kg)", "3 kilogram (mass)");
+    test("mean([1, 2, 3, 4])", "2.5 (dimensionless)");
+    test("min([3, 1, 2] m)", "1 meter (length)");
+    test("max(3 m, 5 m, 4 m)", "5 meter (length)");
+    test("min(5 min, 2 min)", "2 minute, 0 second (time)");
+    test("5 min", "5 minute, 0 second (time)");
+    test("norm([3, 4, 0] N)", "5 newton (force)");
+    test("dot([1, 2, 3], [4, 5, 6])", "32 (dimensionless)");
+    test(
+        "cross([1, 0, 0] m, [0, 1, 0] N)",
+        "0 joule, 0 joule, 1 joule (energy)",
+    );
+    test(
+        "cross([1, 2,], [3, 4,])",
+        "Cross product is only defined for 3-vectors, got 2 elements: \
+         cross([1, 2] (dimensionless), [3, 4] (dimensionless))",
+    );
+    test(
+        "sum([1 m, 2 s, 3])",
+        "Addition of units with mismatched units is not meaningful: \
+         sum([1 meter (length), 2 second (time), 3 (dimensionless)])",
+    );
+}
+
+#[test]
+fn test_two_element_list_functions() {
+    test("norm([3, 4,] m)", "5 meter (length)");
+    test("dot([1, 2,], [3, 4,])", "11 (dimensionless)");
+    test("sum([1, 2,] kg)", "3 kilogram (mass)");
+    test("mean([1, 2,])", "1.5 (dimensionless)");
+    test("max([1, 2,] m)", "2 meter (length)");
+    test(
+        "norm([3, 4] m)",
+        "norm takes a list, but `[a, b]` is an interval. \
+         Use a trailing comma for a list of two elements, as in `[a, b,]`",
+    );
+    test(
+        "dot([1, 2], [3, 4])",
+        "dot takes a list, but `[a, b]` is an interval. \
+         Use a trailing comma for a list of two elements, as in `[a, b,]`",
+    );
+}
+
+#[test]
+fn test_list_conversion() {
+    test(
+        "[1, 2.5, 7] mi -> km",
+        "1.609344 kilometer, 4.02336 kilometer, approx. 11.26540 kilometer (length)",
+    );
+    test("[20, 25, 30] °C -> °F", "68 °F, 77 °F, 86 °F (temperature)");
+    test(
+        "1 m -> [1, 2, 3] cm",
+        "Lists are not allowed in the right hand side of conversions",
+    );
+}
+
+#[test]
+fn test_log_units() {
+    test("20 dBm -> mW", "100 milliwatt (power)");
+    test("100 mW -> dBm", "20 dBm (power)");
+    test("-20 dBm", "10 microwatt (power)");
+    test("3 dB -> 1", "approx. 1.995262 (dimensionless)");
+    test("2 -> dB", "approx. 3.010299 dB (dimensionless)");
+    test("1 Np -> dB", "approx. 8.685889 dB (dimensionless)");
+    test("0 dBV -> mV", "1000 millivolt (electrical_potential)");
+    // Scales don't take over prefixed units like picohenry.
+    test("5 pH -> H", "5.0e-12 henry (inductance)");
+    test("1e-3 mol/L -> pHscale", "3 pHscale (molar_concentration)");
+    test(
+        "7 pHscale -> mol/L",
+        "0.0000001 mole / liter (molar_concentration)",
+    );
+    test("5 mag -> 1", "0.01 (dimensionless)");
+    test(
+        "[0, 10, 20,] dBm -> mW",
+        "1 milliwatt, 10 milliwatt, 100 milliwatt (power)",
+    );
+    test("(100 ± 1) mW -> dBm", "20 ± 0.04342944 dBm (power)");
+}
+
+#[test]
+fn test_log_unit_arithmetic() {
+    // Levels add as powers, not as decibels.
+    test("10 dBm + 10 dBm -> dBm", "approx. 13.01029 dBm (power)");
+    test("10 dBm + 10 dBm", "20 milliwatt (power)");
+    test(
+        "10 dBm + 3 dB",
+        "Addition of units with mismatched units is not meaningful: \
+         <10 milliwatt (power)> + <approx. 1.995262 (dimensionless)>",
+    );
+    test(
+        "-5 mW -> dBm",
+        "dBm is only defined for positive values, got: <-5 milliwatt (power)>",
+    );
+    test(
+        "dBm",
+        "dBm is a nonlinear unit and must follow a number, like `20 dBm`",
+    );
+    test(
+        "1 W -> hex dBm",
+        "Conversion to dBm is not defined in base 16",
+    );
+    test("5 m dB", "Expected dimensionless, got: <5 meter (length)>");
+}
+
+#[test]
+fn test_rounding_functions() {
+    test("round(3.14159 m, 1 cm) -> cm", "314 centimeter (length)");
+    test("round(2.5)", "3 (dimensionless)");
+    test("round(-2.5)", "-3 (dimensionless)");
+    test("floor(-2.5)", "-3 (dimensionless)");
+    test("ceil(2.1 kg)", "3 kilogram (mass)");
+    test("trunc(-2.7)", "-2 (dimensionless)");
+    test("round(pi, 1|8)", "3.125 (dimensionless)");
+    test("floor([1.5, 2.5, -0.5])", "1, 2, -1 (dimensionless)");
+    test("floor([1.2, 1.8] m)", "[1, 1] meter (length)");
+    // The result is a value, not just a display format.
+    test("round(2.54 cm, 1 mm) * 10 -> cm", "25 centimeter (length)");
+    test(
+        "round(1 m, 1 s)",
+        "Arguments must have matching dimensionality: \
+         round(1 meter (length), 1 second (time))",
+    );
+    test(
+        "round(1 m, 0 m)",
+        "Step must not be zero: round(1 meter (length), 0 meter (length))",
+    );
+}
+
+#[test]
+fn test_integer_functions() {
+    test("gcd(12, 18)", "6 (dimensionless)");
+    test("gcd(0.25, 0.1)", "0.05 (dimensionless)");
+    test("gcd(12 inch, 1 ft) -> inch", "12 inch (length)");
+    test("lcm(4, 6)", "12 (dimensionless)");
+    test("lcm(0.25 m, 0.1 m) -> m", "0.5 meter (length)");
+    test("lcm(0, 5)", "0 (dimensionless)");
+    test("mod(7, 3)", "1 (dimensionless)");
+    test("mod(-7, 3)", "2 (dimensionless)");
+    test("mod(7, -3)", "-2 (dimensionless)");
+    test("mod(10 m, 3 m)", "1 meter (length)");
+    test("mod(5.5, 2)", "1.5 (dimensionless)");
+    test(
+        "mod(5, 0)",
+        "Division by zero: mod(5 (dimensionless), 0 (dimensionless))",
+    );
+    test(
+        "mod(5 m, 2 s)",
+        "Arguments must have matching dimensionality: \
+         mod(5 meter (length), 2 second (time))",
+    );
+}
+
+#[test]
+fn test_integer_operators() {
+    test("17 mod 5", "2 (dimensionless)");
+    test("-7 mod 3", "2 (dimensionless)");
+    test("10 m mod 3 m", "1 meter (length)");
+    test("100 m // 3 m", "33 (dimensionless)");
+    test("-7 // 2", "-4 (dimensionless)");
+    test("7 m // 2", "3 meter (length)");
+    test("[10, 20, 30] mod 7", "3, 6, 2 (dimensionless)");
+    test(
+        "1 m mod 0 m",
+        "Division by zero: <1 meter (length)> mod <0 meter (length)>",
+    );
+    test(
+        "17 mod 5 kg",
+        "Arguments must have matching dimensionality: \
+         <17 (dimensionless)> mod <5 kilogram (mass)>",
+    );
+    test(
+        "m -> m mod 2 m",
+        "`mod` is not allowed in the right hand side of conversions",
+    );
+}
+
+#[test]
+fn test_factorial() {
+    test("10!", "3628800 (dimensionless)");
+    test("0!", "1 (dimensionless)");
+    test("-3!", "-6 (dimensionless)");
+    test("3!!", "720 (dimensionless)");
+    test("10 nCr 3", "120 (dimensionless)");
+    test("10 nPr 3", "720 (dimensionless)");
+    test("3 nCr 5", "0 (dimensionless)");
+    test("1000 nCr 998", "499500 (dimensionless)");
+    test(
+        "2.5!",
+        "Factorial is only defined for non-negative integers: <2.5 (dimensionless)>!",
+    );
+    test(
+        "(-3)!",
+        "Factorial is only defined for non-negative integers: <-3 (dimensionless)>!",
+    );
+    test(
+        "3 m nCr 2",
+        "nCr is only defined for non-negative integers: <3 meter (length)> nCr <2 (dimensionless)>",
+    );
+    test(
+        "20000!",
+        "Factorial is limited to 10000 factors: <20000 (dimensionless)>!",
+    );
+}
+
+#[test]
+fn test_percent_suffix() {
+    test("50%", "0.5 (dimensionless)");
+    test("200 * 15%", "30 (dimensionless)");
+    test("10!%", "36288 (dimensionless)");
+}
+
+#[test]
+fn test_percent_arithmetic() {
+    test("50 kg + 15%", "57.5 kilogram (mass)");
+    test("80 kg - 25%", "60 kilogram (mass)");
+    test("2 + 50%", "3 (dimensionless)");
+    test("10 m + 5 percent", "10.5 meter (length)");
+    test("1 m + 2 m + 10%", "3.3 meter (length)");
+    test("20% of 80 kg", "16 kilogram (mass)");
+    test("10 m + 5% of 10 m", "10.5 meter (length)");
+    test("50% of 50% of 8", "2 (dimensionless)");
+    test("percent of 80 kg", "800 gram (mass)");
+    test("12 / 80 -> %", "15 percent (dimensionless)");
+    test("3 ft / 1 m -> %", "91.44 percent (dimensionless)");
+}
+
+#[test]
+fn test_comparisons() {
+    test("3 ft < 1 m", "true");
+    test("1 m <= 100 cm", "true");
+    test("1 m == 100 cm", "true");
+    test("1 m != 100 cm", "false");
+    test("5 kg > 2 lb", "true");
+    test("2 >= 3", "false");
+    test("0.1 + 0.2 == 0.3", "true");
+    test("i == i", "true");
+    test("#2020-01-01# < #2021-01-01#", "true");
+    test("(1 < 2) == (2 > 1)", "true");
+    test(
+        "1 m < 1 s",
+        "Conformance error: 1 meter (length) != 1 second (time)\n\
+         Suggestions: divide left side by velocity, multiply right side by velocity",
+    );
+    test(
+        "i < 2",
+        "Complex numbers can't be compared: <i (dimensionless)> < <2 (dimensionless)>",
+    );
+    test(
+        "1 < 2 < 3",
+        "Operation is not defined: <true> < <3 (dimensionless)>",
+    );
+}
+
+#[test]
+fn test_boolean_logic() {
+    test("1 < 2 and 2 < 3", "true");
+    test("1 > 2 or 2 < 3", "true");
+    test("not 1 < 2", "false");
+    test("not (1 < 2 and 2 < 1)", "true");
+    test("1 > 2 and 1", "false");
+    test("1 < 2 or 1", "true");
+    test(
+        "1 and 2 < 3",
+        "Expected Bool for `and`, got <1 (dimensionless)>",
+    );
+    test("if(1 m < 2 ft, 1, 0)", "0 (dimensionless)");
+    test("if(1 < 2, 10 m, 1/0)", "10 meter (length)");
+    test(
+        "if(1, 2, 3)",
+        "Expected Bool for `if`, got <1 (dimensionless)>",
+    );
+    test(
+        "if(1 < 2, 3)",
+        "Argument number mismatch for if: Expected 3, got 2",
+    );
+}
+
+#[test]
+fn test_symbolic() {
+    test("sqrt(2)^2", "2 (dimensionless)");
+    test("sqrt(8)", "2 sqrt(2), approx. 2.828427 (dimensionless)");
+    test(
+        "1 / sqrt(2)",
+        "sqrt(2)/2, approx. 0.7071067 (dimensionless)",
+    );
+    test(
+        "sqrt(2) sqrt(3)",
+        "sqrt(6), approx. 2.449489 (dimensionless)",
+    );
+    test("3 pi", "3 pi, approx. 9.424777 (dimensionless)");
+    test("1 / (2 pi)", "1/(2 pi), approx. 0.1591549 (dimensionless)");
+    test("sqrt(pi^2)", "pi, approx. 3.141592 (dimensionless)");
+    test(
+        "90 degree -> radian",
+        "pi/2, approx. 1.570796 radian (angle)",
+    );
+    test("2 pi radian -> degree", "360 degree (angle)");
+    test("sqrt(2) + 1", "approx. 2.414213 (dimensionless)");
+    // Even powers of a radical are rational, however large they get.
+    test("sqrt(2)^100000 - 2^50000", "0 (dimensionless)");
+    test("sqrt(2)^201 / sqrt(2) - 2^100", "0 (dimensionless)");
+    // Sums of different radicals can't be kept exact.
+    test("(sqrt(2) + 1)^2", "approx. 5.828427 (dimensionless)");
+    test(
+        "pi -> digits 30",
+        "pi, approx. 3.1415926535897932384626433832795 (dimensionless)",
+    );
+    test(
+        "pi",
+        "Definition: π = pi, approx. 3.141592 (dimensionless). \
+         Ratio of a circle's circumference to its diameter.",
+    );
+}
+
+#[test]
+fn test_small_overflow() {
+    test(
+        "9223372036854775807 + 1 -> digits",
+        "9223372036854775808 (dimensionless)",
+    );
+    test(
+        "-9223372036854775807 - 2 -> digits",
+        "-9223372036854775809 (dimensionless)",
+    );
+    test(
+        "3037000500 * 3037000500 -> digits",
+        "9223372037000250000 (dimensionless)",
+    );
+    test("2^64 / 2^63", "2 (dimensionless)");
+    test(
+        "2^63 - 2^63 + 1/3",
+        "1/3, approx. 0.3333333 (dimensionless)",
+    );
+    test("1/4294967296 * 1/4294967296 * 2^64", "1 (dimensionless)");
+}
+
+#[test]
+fn test_small_boundaries() {
+    // -9223372036854775808 is i64::MIN, and 9223372036854775807 is
+    // i64::MAX.
+    test(
+        "-9223372036854775808 - 1 -> digits",
+        "-9223372036854775809 (dimensionless)",
+    );
+    test(
+        "-9223372036854775808 + 9223372036854775807",
+        "-1 (dimensionless)",
+    );
+    test(
+        "-9223372036854775808 * -1 -> digits",
+        "9223372036854775808 (dimensionless)",
+    );
+    test(
+        "-9223372036854775808 * 9223372036854775807 -> digits",
+        "-85070591730234615856620279821087277056 (dimensionless)",
+    );
+    test(
+        "9223372036854775807 * 9223372036854775807 -> digits",
+        "85070591730234615847396907784232501249 (dimensionless)",
+    );
+    test(
+        "-(-9223372036854775808) -> digits",
+        "9223372036854775808 (dimensionless)",
+    );
+    test(
+        "-9223372036854775807 -> digits",
+        "-9223372036854775807 (dimensionless)",
+    );
+    test(
+        "abs(-9223372036854775808) -> digits",
+        "9223372036854775808 (dimensionless)",
+    );
+    test(
+        "abs(9223372036854775807) -> digits",
+        "9223372036854775807 (dimensionless)",
+    );
+    test(
+        "-9223372036854775808 // -1 -> digits",
+        "9223372036854775808 (dimensionless)",
+    );
+    // Square roots of negative numbers go through f64.
+    test(
+        "sqrt(-9223372036854775808)",
+        "approx. 3.037000e9i (dimensionless)",
+    );
+    test(
+        "sqrt(-9223372036854775807)",
+        "approx. 3.037000e9i (dimensionless)",
+    );
+}
+
+#[test]
+fn test_sig_figs() {
+    test("pi -> 3 sig figs", "pi, approx. 3.14 (dimensionless)");
+    test(
+        "1/3 -> 4 significant figures",
+        "1/3, approx. 0.3333 (dimensionless)",
+    );
+    test("-2/3 -> 2 sigfigs", "-2/3, approx. -0.67 (dimensionless)");
+    test(
+        "9.996 -> 3 sig figs",
+        "2499/250, approx. 10.0 (dimensionless)",
+    );
+    test("2.5 -> 1 sig fig", "5/2, approx. 3 (dimensionless)");
+    test("1234567 -> 3 sig figs", "approx. 1230000 (dimensionless)");
+    test(
+        "12345678901 -> 3 sig figs",
+        "approx. 1.23e10 (dimensionless)",
+    );
+    test(
+        "0.000123456 -> 2 sig figs",
+        "approx. 0.00012 (dimensionless)",
+    );
+    test("1.234e-11 -> 2 sig figs", "approx. 1.2e-11 (dimensionless)");
+    test("4095 -> 2 sig figs hex", "approx. 1000 (dimensionless)");
+    test("1 mile -> 3 sig figs km", "approx. 1.61 kilometer (length)");
+    test("0.5 m -> 2 sig figs", "500 millimeter (length)");
+    test("5 ± 0.123 m -> 2 sig figs", "5.0 ± 0.12 meter (length)");
+    test("[sqrt(2), pi] -> 2 sig figs", "[1.4, 3.2] (dimensionless)");
+    test("1 -> 0 sig figs", "Significant figures must be at least 1");
+}
+
+#[test]
+fn test_eng_notation() {
+    test("0.00000047 farad -> eng", "470e-9 farad (capacitance)");
+    test("1500 -> eng", "1.5e3 (dimensionless)");
+    test("-0.0047 -> eng", "-4.7e-3 (dimensionless)");
+    test("123456 m -> eng", "123.456e3 meter (length)");
+    test(
+        "999.96e-9 F -> 3 sig figs eng",
+        "approx. 1.00e-6 farad (capacitance)",
+    );
+    test(
+        "[1, 2] -> eng",
+        "<[1, 2] (dimensionless)> to eng is not defined",
+    );
+    test(
+        "ln(0) -> eng",
+        "Cannot write <approx. -Inf (dimensionless)> in engineering notation, \
+         as it is not finite",
+    );
+}
+
+#[test]
+fn test_auto_prefix() {
+    test("0.00000047 farad -> auto", "470 nanofarad (capacitance)");
+    test(
+        "12345678 byte -> 3 sig figs auto byte",
+        "approx. 12.3 megabyte (information)",
+    );
+    test(
+        "12345678 byte -> auto binary B",
+        "approx. 11.77375 mebibyte (information)",
+    );
+    test("0.5 byte -> auto binary byte", "0.5 byte (information)");
+    // Prefixes go on the unit the number was written in.
+    test(
+        "12345678 byte -> auto",
+        "approx. 12.34567 megabyte (information)",
+    );
+    test(
+        "12345678 B -> auto binary",
+        "approx. 11.77375 mebibyte (information)",
+    );
+    test("-5000 kB -> auto", "-5 megabyte (information)");
+    test("0.001 ms -> auto", "1 microsecond (time)");
+    test(
+        "asin(2) kg -> auto",
+        "Cannot pick a prefix for <approx. NaN gram (mass)>, as it is not finite",
+    );
+    test(
+        "ln(0) kg -> auto",
+        "Cannot pick a prefix for <approx. -Inf gram (mass)>, as it is not finite",
+    );
+    test("5000 kg -> auto", "5 megagram (mass)");
+    test("3 km^2 -> auto", "3 kilometer^2 (area)");
+    test("0 m -> auto", "0 meter (length)");
+    test(
+        "1 m/s -> auto",
+        "Cannot pick a prefix for <1 meter / second (velocity)>, \
+         as it is not in a single unit",
+    );
+}
+
+#[test]
+fn test_fraction_conversion() {
+    test(
+        "3.17 inch -> inch nearest 1/16",
+        "approx. 3 3/16 (error +0.0175) inch (length)",
+    );
+    test("3.1875 inch -> inch nearest 1/64", "3 3/16 inch (length)");
+    test(
+        "-0.3 inch -> inch nearest 1/16",
+        "approx. -5/16 (error -0.0125) inch (length)",
+    );
+    test(
+        "70.3 inch -> ft;inch nearest 1/16",
+        "5 foot, 10 5/16 (error +0.0125) inch (length)",
+    );
+    test(
+        "71.99 inch -> ft;inch nearest 1/16",
+        "6 foot, 0 (error +0.01) inch (length)",
+    );
+    test("-2.5 -> fraction", "-2 1/2 (dimensionless)");
+    test("3.17 inch -> fraction inch", "3 17/100 inch (length)");
+    test(
+        "pi -> fraction",
+        "pi, approx. 3 16/113 (error +0.0000002667641) (dimensionless)",
+    );
+    test("2.5 m -> fraction nearest 1/8", "2 1/2 meter (length)");
+    test(
+        "[3.01, 3.2] inch -> inch nearest 1/16",
+        "[3, 3 1/4] inch (length)",
+    );
+    test(
+        "3 inch -> inch nearest 16",
+        "Expected a fraction like 1/16 after `nearest`",
+    );
+    test(
+        "3 inch -> digits 3 inch nearest 1/16",
+        "Cannot round to a fraction and a number of digits at once",
+    );
+    test(
+        "17.5 -> fraction nearest 1/16 hex",
+        "11 1/2 (dimensionless)",
+    );
+    test(
+        "17.5 -> fraction nearest 1/16 garbage",
+        "Expected end of conversion, got ident",
+    );
+    test(
+        "3 m -> ft;inch nearest 1/8 garbage",
+        "Expected end of conversion, got ident",
+    );
+    test(
+        "1.3 m -> ft;inch fraction",
+        "4 foot, 3 23/127 inch (length)",
+    );
+    test(
+        "1.3 m -> ft;inch fraction nearest 1/8",
+        "4 foot, 3 1/8 (error -0.05610236) inch (length)",
+    );
+    test(
+        "ln(0) -> fraction",
+        "Cannot write <approx. -Inf (dimensionless)> as a fraction, as it is not finite",
+    );
+    test(
+        "ln(0) m -> ft nearest 1/16",
+        "Cannot write <approx. -Inf meter (length)> as a fraction, as it is not finite",
+    );
+    test(
+        "ln(0) m -> ft;inch fraction",
+        "Cannot write <approx. -Inf meter (length)> as a fraction, as it is not finite",
+    );
+}
+
+#[test]
+fn test_rational_approximation() {
+    test(
+        "pi -> fraction max 1000",
+        "355/113 (error +0.0000002667641) (dimensionless)",
+    );
+    test(
+        "pi -> fraction max 100",
+        "311/99 (error -0.0001785121) (dimensionless)",
+    );
+    test("1/3 -> fraction max 10", "1/3 (dimensionless)");
+    test("0.75 -> fraction max 2", "1 (error +0.25) (dimensionless)");
+    test(
+        "pi -> fraction max 0",
+        "Maximum denominator must be at least 1",
+    );
+    test(
+        "[1, 2] -> fraction max 10",
+        "<[1, 2] (dimensionless)> to fraction is not defined",
+    );
+    test(
+        "(1+2i) -> fraction max 10",
+        "Cannot write <1 + 2i (dimensionless)> as a fraction, as it is not a real number",
+    );
+    test(
+        "pi -> fraction max 10 ft",
+        "Expected end of conversion, got ident",
+    );
+    test(
+        "ln(0) -> fraction max 10",
+        "Cannot write <approx. -Inf (dimensionless)> as a fraction, as it is not finite",
+    );
+}
+
+#[test]
+fn test_continued_fraction() {
+    test(
+        "355/113 -> continued fraction",
+        "[3; 7, 16] (dimensionless)",
+    );
+    test("3 -> continued fraction", "[3] (dimensionless)");
+    test("-2.5 -> continued fraction", "[-3; 2] (dimensionless)");
+    test(
+        "pi -> continued fraction",
+        "[3; 7, 15, 1, 292, 1, 1, 1, 2, 1, 3, 1, 14, 2, 1, 1, 2, 2, 2, 2, ...] (dimensionless)",
+    );
+    test(
+        "pi -> continued x",
+        "Expected `fraction` after `continued`, got ident",
+    );
+    test(
+        "pi -> continued",
+        "Expected `fraction` after `continued`, got eof",
+    );
+    test(
+        "3.3 m -> continued fraction ft",
+        "Expected end of conversion, got ident",
+    );
+    test(
+        "(1+i) -> continued fraction",
+        "Cannot write <1 + i (dimensionless)> as a continued fraction, \
+         as it is not a real number",
+    );
+    test(
+        "ln(0) -> continued fraction",
+        "Cannot write <approx. -Inf (dimensionless)> as a continued fraction, as it is not finite",
+    );
+    test(
+        "-ln(0) m -> continued fraction",
+        "Cannot write <approx. Inf meter (length)> as a continued fraction, as it is not finite",
+    );
+}
@@ -313,6 +313,24 @@ fn test_unit_list() {
     );
 }
 
+#[test]
+fn test_list() {
+    test(
+        "[1, 2, 3] m",
+        &[
+            s("", ListBegin),
+            child(vec![s("1", Number), s(" ", Plain), s("meter", Unit)]),
+            s(", ", ListSep),
+            child(vec![s("2", Number), s(" ", Plain), s("meter", Unit)]),
+            s(", ", ListSep),
+            child(vec![s("3", Number), s(" ", Plain), s("meter", Unit)]),
+            s(" (", Plain),
+            s("length", Quantity),
+            s(")", Plain),
+        ],
+    );
+}
+
 #[test]
 fn test_search() {
     test(
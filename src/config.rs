@@ -7,6 +7,7 @@ use ansi_term::{Color, Style};
 use color_eyre::Result;
 use eyre::{eyre, Report, WrapErr};
 use reqwest::header::USER_AGENT;
+use rink_core::bigfloat::DEFAULT_DIGITS;
 use rink_core::context::Context;
 use rink_core::fmt::FmtToken;
 use rink_core::{ast, date, gnu_units, CURRENCY_FILE, DATES_FILE, DEFAULT_FILE};
@@ -55,6 +56,12 @@ pub struct Rink {
     pub prompt: String,
     /// Use multi-line output for lists.
     pub long_output: bool,
+    /// Show the difference between two dates in calendar years and
+    /// months, rather than fixed-length ones.
+    pub calendar_durations: bool,
+    /// Significant digits that functions like `sqrt` and `sin` compute
+    /// their results to.
+    pub precision: u64,
 }
 
 #[derive(Serialize, Deserialize, Clone)]
@@ -182,6 +189,8 @@ impl Default for Rink {
         Rink {
             prompt: "> ".to_owned(),
             long_output: false,
+            calendar_durations: true,
+            precision: DEFAULT_DIGITS,
         }
     }
 }
@@ -262,6 +271,9 @@ pub fn read_config() -> Result<Config> {
 
 /// Creates a context by searching standard directories
 pub fn load(config: &Config) -> Result<Context> {
+    if config.rink.precision == 0 {
+        return Err(eyre!("rink.precision in config.toml must be at least 1"));
+    }
     let mut search_path = vec![PathBuf::from("./")];
     if let Some(config_dir) = dirs::config_dir() {
         search_path.push(config_dir);
@@ -276,6 +288,8 @@ pub fn load(config: &Config) -> Result<Context> {
         .unwrap_or_else(|_| DATES_FILE.to_owned());
 
     let mut ctx = Context::new();
+    ctx.calendar_durations = config.rink.calendar_durations;
+    ctx.precision = config.rink.precision;
     ctx.load(gnu_units::parse_str(&units));
     ctx.load_dates(date::parse_datefile(&dates));
 
@@ -31,6 +31,19 @@ export enum BinOpType {
   Frac = "frac",
   Pow = "pow",
   Equals = "equals",
+  PlusMinus = "plusMinus",
+  Mod = "mod",
+  FloorDiv = "floorDiv",
+  Choose = "choose",
+  Permute = "permute",
+  Less = "less",
+  LessEqual = "lessEqual",
+  Greater = "greater",
+  GreaterEqual = "greaterEqual",
+  Equal = "equal",
+  NotEqual = "notEqual",
+  And = "and",
+  Or = "or",
 }
 
 export interface BinOpExpr {
@@ -43,7 +56,8 @@ export interface BinOpExpr {
 export enum UnaryOpType {
   Negative = "negative",
   Positive = "positive",
-  // Degree
+  Factorial = "factorial",
+  Not = "not",
 }
 
 export interface UnaryOpExpr {
@@ -69,6 +83,17 @@ export interface CallExpr {
   args: Expr[];
 }
 
+export interface IntervalExpr {
+  type: "interval";
+  lower: Expr;
+  upper: Expr;
+}
+
+export interface ListExpr {
+  type: "list";
+  items: Expr[];
+}
+
 export interface ErrorExpr {
   type: "error";
   message: string;
@@ -84,4 +109,6 @@ export type Expr =
   | MulExpr
   | OfExpr
   | CallExpr
+  | IntervalExpr
+  | ListExpr
   | ErrorExpr;
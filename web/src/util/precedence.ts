@@ -4,10 +4,15 @@ export enum Precedence {
   Term = 0,
   Plus = 1,
   Pow = 2,
-  Mul = 3,
-  Div = 4,
-  Add = 5,
-  Equals = 6,
+  PlusMinus = 3,
+  Mul = 4,
+  Div = 5,
+  Add = 6,
+  Compare = 7,
+  Not = 8,
+  And = 9,
+  Or = 10,
+  Equals = 11,
 }
 
 export function symbol(op: BinOpType): string {
@@ -22,6 +27,32 @@ export function symbol(op: BinOpType): string {
       return "^";
     case BinOpType.Equals:
       return " = ";
+    case BinOpType.PlusMinus:
+      return " ± ";
+    case BinOpType.Mod:
+      return " mod ";
+    case BinOpType.FloorDiv:
+      return " // ";
+    case BinOpType.Choose:
+      return " nCr ";
+    case BinOpType.Permute:
+      return " nPr ";
+    case BinOpType.Less:
+      return " < ";
+    case BinOpType.LessEqual:
+      return " <= ";
+    case BinOpType.Greater:
+      return " > ";
+    case BinOpType.GreaterEqual:
+      return " >= ";
+    case BinOpType.Equal:
+      return " == ";
+    case BinOpType.NotEqual:
+      return " != ";
+    case BinOpType.And:
+      return " and ";
+    case BinOpType.Or:
+      return " or ";
   }
 }
 
@@ -37,6 +68,24 @@ export function expectedPrecedence(op: BinOpType): Precedence {
       return Precedence.Pow;
     case BinOpType.Equals:
       return Precedence.Equals;
+    case BinOpType.PlusMinus:
+      return Precedence.PlusMinus;
+    case BinOpType.Mod:
+    case BinOpType.FloorDiv:
+    case BinOpType.Choose:
+    case BinOpType.Permute:
+      return Precedence.Div;
+    case BinOpType.Less:
+    case BinOpType.LessEqual:
+    case BinOpType.Greater:
+    case BinOpType.GreaterEqual:
+    case BinOpType.Equal:
+    case BinOpType.NotEqual:
+      return Precedence.Compare;
+    case BinOpType.And:
+      return Precedence.And;
+    case BinOpType.Or:
+      return Precedence.Or;
   }
 }
 
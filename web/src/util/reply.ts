@@ -10,6 +10,9 @@ export interface NumberParts {
   quantity: string | null;
   dimensions: string | null;
   rawDimensions: Quantity | null;
+  uncertainty: string | null;
+  roundingError: string | null;
+  interval: [string, string] | null;
 }
 
 export interface Quantity {
@@ -31,6 +34,7 @@ export interface DateReply {
   nanosecond: number;
   string: string;
   rfc3339: string;
+  formatted: string | null;
 }
 
 export interface PropertyReply {
@@ -60,6 +64,7 @@ export interface Duration {
 export interface DurationReply extends Duration {
   type: "duration";
   raw: NumberParts;
+  formatted: string | null;
 }
 
 export interface ExprLiteral {
@@ -126,11 +131,38 @@ export interface UnitListReply {
   list: NumberParts[];
 }
 
+export interface ListReply {
+  type: "list";
+  items: NumberParts[];
+  quantity: string | null;
+}
+
+export interface FractionReply {
+  type: "fraction";
+  numer: string;
+  denom: string;
+  terms: string[] | null;
+  truncated: boolean;
+  error: string | null;
+  rest: NumberParts;
+}
+
+export interface BoolReply {
+  type: "bool";
+  value: boolean;
+}
+
 export interface SearchReply {
   type: "search";
   results: NumberParts[];
 }
 
+export interface BindingReply {
+  type: "binding";
+  name: string;
+  value: QueryReply | null;
+}
+
 export type QueryReply =
   | NumberReply
   | DateReply
@@ -141,7 +173,11 @@ export type QueryReply =
   | FactorizeReply
   | UnitsForReply
   | UnitListReply
-  | SearchReply;
+  | ListReply
+  | FractionReply
+  | BoolReply
+  | SearchReply
+  | BindingReply;
 
 export interface ConformanceError {
   type: "conformance";
@@ -185,12 +221,19 @@ export function describe(result: QueryResult): string {
       return `Definition of substance "${result.name}"`;
     case "unitsFor":
       return `Units for ${result.of.quantity}`;
+    case "binding":
+      return result.value
+        ? `Set variable ${result.name}`
+        : `Unset variable ${result.name}`;
     case "conversion":
     case "date":
     case "duration":
     case "factorize":
     case "number":
     case "unitList":
+    case "list":
+    case "fraction":
+    case "bool":
       return "";
   }
   return "";